@@ -0,0 +1,277 @@
+use aead::AuthenticationError;
+use permutation::{extract_lane_bytes, xor_lane_bytes};
+use permutation::ascon::permute_words;
+use secret::Secret;
+use utils::compare::constant_time_eq;
+
+// See NIST Special Publication 800-232, Section 4.
+const AEAD128_IV: u64 = 0x00001000808C0001;
+const RATE: usize = 16;
+const INIT_ROUNDS: usize = 12;
+const BLOCK_ROUNDS: usize = 8;
+
+fn load_word(bytes: &[u8]) -> Secret<u64> {
+    let mut word = [Secret::new(0)];
+    xor_lane_bytes(&mut word, 0, bytes);
+    word[0]
+}
+
+/// Ascon-AEAD128, with a 128-bit key, nonce and tag.
+///
+/// Never encrypt two messages with the same key and nonce.
+pub struct AsconAead128 {
+    key: [Secret<u64>; 2]
+}
+
+impl AsconAead128 {
+    pub fn new(key: &[u8; 16]) -> AsconAead128 {
+        AsconAead128 {
+            key: [load_word(&key[..8]), load_word(&key[8..])]
+        }
+    }
+
+    /// Starts a message that can be processed in pieces.
+    pub fn start(&self, nonce: &[u8; 16]) -> AsconAead128Stream {
+        let mut words = [Secret::new(AEAD128_IV), self.key[0], self.key[1], load_word(&nonce[..8]), load_word(&nonce[8..])];
+        permute_words(&mut words, INIT_ROUNDS);
+        words[3] ^= self.key[0];
+        words[4] ^= self.key[1];
+
+        AsconAead128Stream {
+            words: words,
+            key: self.key,
+            position: 0,
+            has_associated_data: false,
+            in_text: false
+        }
+    }
+
+    /// Encrypts `buffer` in place and writes the tag covering it and `associated_data`.
+    pub fn encrypt(&self, nonce: &[u8; 16], associated_data: &[u8], buffer: &mut [u8], tag: &mut [u8; 16]) {
+        let mut stream = self.start(nonce);
+        stream.update_associated_data(associated_data);
+        stream.encrypt(buffer);
+        stream.finalize(tag);
+    }
+
+    /// Decrypts `buffer` in place and checks it against `tag`. On failure, `buffer` is zeroed.
+    pub fn decrypt(&self, nonce: &[u8; 16], associated_data: &[u8], buffer: &mut [u8], tag: &[u8; 16]) -> Result<(), AuthenticationError> {
+        let mut stream = self.start(nonce);
+        stream.update_associated_data(associated_data);
+        stream.decrypt(buffer);
+        let result = stream.verify(tag);
+        if result.is_err() {
+            for byte in buffer.iter_mut() {
+                *byte = 0;
+            }
+        }
+        result
+    }
+}
+
+/// A single Ascon-AEAD128 message in progress.
+///
+/// All associated data must be supplied before any text. Decrypted text is returned before the
+/// tag has been checked, so it must not be used until `verify` succeeds.
+pub struct AsconAead128Stream {
+    words: [Secret<u64>; 5],
+    key: [Secret<u64>; 2],
+    position: usize,
+    has_associated_data: bool,
+    in_text: bool
+}
+
+impl AsconAead128Stream {
+    pub fn update_associated_data(&mut self, associated_data: &[u8]) {
+        assert!(!self.in_text, "associated data must come before the text");
+
+        if !associated_data.is_empty() {
+            self.has_associated_data = true;
+        }
+        let mut start = 0;
+        while start < associated_data.len() {
+            let end = self.piece_end(start, associated_data.len());
+            xor_lane_bytes(&mut self.words, self.position, &associated_data[start..end]);
+            self.advance(end - start);
+            start = end;
+        }
+    }
+
+    pub fn encrypt(&mut self, buffer: &mut [u8]) {
+        self.start_text();
+        let mut start = 0;
+        while start < buffer.len() {
+            let end = self.piece_end(start, buffer.len());
+            xor_lane_bytes(&mut self.words, self.position, &buffer[start..end]);
+            extract_lane_bytes(&self.words, self.position, &mut buffer[start..end]);
+            self.advance(end - start);
+            start = end;
+        }
+    }
+
+    pub fn decrypt(&mut self, buffer: &mut [u8]) {
+        self.start_text();
+        let mut keystream = [0; RATE];
+        let mut start = 0;
+        while start < buffer.len() {
+            let end = self.piece_end(start, buffer.len());
+            let keystream = &mut keystream[..end - start];
+            extract_lane_bytes(&self.words, self.position, keystream);
+            for (byte, &key) in buffer[start..end].iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+            xor_lane_bytes(&mut self.words, self.position, &buffer[start..end]);
+            self.advance(end - start);
+            start = end;
+        }
+    }
+
+    pub fn finalize(mut self, tag: &mut [u8; 16]) {
+        self.start_text();
+        xor_lane_bytes(&mut self.words, self.position, &[0x01]);
+        self.words[2] ^= self.key[0];
+        self.words[3] ^= self.key[1];
+        permute_words(&mut self.words, INIT_ROUNDS);
+
+        let tag_words = [self.words[3] ^ self.key[0], self.words[4] ^ self.key[1]];
+        extract_lane_bytes(&tag_words, 0, tag);
+    }
+
+    /// Checks `tag` in constant time.
+    pub fn verify(self, tag: &[u8; 16]) -> Result<(), AuthenticationError> {
+        let mut expected = [0; 16];
+        self.finalize(&mut expected);
+        if constant_time_eq(&expected, tag) {
+            Ok(())
+        } else {
+            Err(AuthenticationError)
+        }
+    }
+
+    // The end of the piece of input starting at `start` that fits in the current block.
+    fn piece_end(&self, start: usize, len: usize) -> usize {
+        let space = RATE - self.position;
+        if len - start < space { len } else { start + space }
+    }
+
+    fn advance(&mut self, len: usize) {
+        self.position += len;
+        if self.position == RATE {
+            permute_words(&mut self.words, BLOCK_ROUNDS);
+            self.position = 0;
+        }
+    }
+
+    // Pads the associated data, if there was any, and separates it from the text.
+    fn start_text(&mut self) {
+        if self.in_text {
+            return;
+        }
+        if self.has_associated_data {
+            xor_lane_bytes(&mut self.words, self.position, &[0x01]);
+            permute_words(&mut self.words, BLOCK_ROUNDS);
+        }
+        self.words[4] ^= 1 << 63;
+        self.position = 0;
+        self.in_text = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsconAead128;
+
+    use aead::AuthenticationError;
+    use utils::hex;
+
+    const KEY: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    const NONCE: [u8; 16] = [16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31];
+
+    // Entries of LWC_AEAD_KAT_128_128.txt from the reference implementation, where the plaintext
+    // and associated data count up from 00. The expected value is the ciphertext followed by the tag.
+    const KATS: [(usize, usize, &'static str); 6] = [
+        (0, 0, "4F9C278211BEC9316BF68F46EE8B2EC6"),
+        (0, 1, "7133E5C79505FD75061DF412C0DEA4B9"),
+        (1, 0, "C84C4BC1957CAD5AA2660F67326C05EEB7"),
+        (16, 16, "427A75EE5D9B70C085F5CDE0091C124299BFA1078C1EC1DBFBD5276EA8C6CEFF"),
+        (17, 15, "73537EFB49916B98353DE1BB60784EF2A055977F9A7EDA4AEA0E2C2BBAE8BE76DF"),
+        (32, 32, "16D2F2A7C74BDA41ADB551F0D6958F801612E3CD0AF14D8AC32B56D25E250769F269B70ADB97C9DBC6A4F0535F802728")
+    ];
+
+    #[test]
+    fn known_answers() {
+        let cipher = AsconAead128::new(&KEY);
+        for &(text_len, ad_len, expected) in KATS.iter() {
+            let plaintext: Vec<u8> = (0..text_len as u8).collect();
+            let associated_data: Vec<u8> = (0..ad_len as u8).collect();
+            let expected = hex::decode(expected);
+
+            let mut buffer = plaintext.clone();
+            let mut tag = [0; 16];
+            cipher.encrypt(&NONCE, &associated_data, &mut buffer, &mut tag);
+            assert_eq!(&buffer[..], &expected[..text_len]);
+            assert_eq!(&tag[..], &expected[text_len..]);
+
+            cipher.decrypt(&NONCE, &associated_data, &mut buffer, &tag).unwrap();
+            assert_eq!(buffer, plaintext);
+        }
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let cipher = AsconAead128::new(&KEY);
+        let associated_data: Vec<u8> = (0..40).collect();
+        let plaintext: Vec<u8> = (0..100).collect();
+
+        let mut expected = plaintext.clone();
+        let mut expected_tag = [0; 16];
+        cipher.encrypt(&NONCE, &associated_data, &mut expected, &mut expected_tag);
+
+        for &split in [0, 1, 15, 16, 17, 33].iter() {
+            let mut stream = cipher.start(&NONCE);
+            stream.update_associated_data(&associated_data[..split]);
+            stream.update_associated_data(&associated_data[split..]);
+            let mut buffer = plaintext.clone();
+            {
+                let (first, second) = buffer.split_at_mut(split);
+                stream.encrypt(first);
+                stream.encrypt(second);
+            }
+            let mut tag = [0; 16];
+            stream.finalize(&mut tag);
+            assert_eq!(buffer, expected);
+            assert_eq!(tag, expected_tag);
+
+            let mut stream = cipher.start(&NONCE);
+            stream.update_associated_data(&associated_data);
+            {
+                let (first, second) = buffer.split_at_mut(split);
+                stream.decrypt(first);
+                stream.decrypt(second);
+            }
+            stream.verify(&tag).unwrap();
+            assert_eq!(buffer, plaintext);
+        }
+    }
+
+    #[test]
+    fn tampering_detected() {
+        let cipher = AsconAead128::new(&KEY);
+        let mut buffer = b"attack at dawn".to_vec();
+        let mut tag = [0; 16];
+        cipher.encrypt(&NONCE, b"header", &mut buffer, &mut tag);
+
+        let mut bad_buffer = buffer.clone();
+        bad_buffer[0] ^= 1;
+        assert_eq!(cipher.decrypt(&NONCE, b"header", &mut bad_buffer, &tag), Err(AuthenticationError));
+        assert!(bad_buffer.iter().all(|&b| b == 0));
+
+        let mut copy = buffer.clone();
+        assert_eq!(cipher.decrypt(&NONCE, b"headex", &mut copy, &tag), Err(AuthenticationError));
+
+        let mut bad_tag = tag;
+        bad_tag[15] ^= 0x80;
+        let mut copy = buffer.clone();
+        assert_eq!(cipher.decrypt(&NONCE, b"header", &mut copy, &bad_tag), Err(AuthenticationError));
+    }
+}
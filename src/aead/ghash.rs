@@ -0,0 +1,208 @@
+use cipher::block::backend::{Backend, Feature};
+use secret::Secret;
+use utils::traits::wrapping::WrappingSub;
+
+// x^128 + x^7 + x^2 + x + 1, with the bits of each byte reversed as in GCM
+const REDUCTION: u128 = 0xE1 << 120;
+
+/// GHASH, the universal hash of GCM, from NIST Special Publication 800-38D, Section 6.4.
+///
+/// Blocks, the key H and the output are big-endian, so bit 0 of a block in GCM's numbering is the
+/// most significant bit. The hardware backend uses carry-less multiplication.
+#[derive(Clone)]
+pub struct Ghash {
+    key: Secret<u128>,
+    accumulator: Secret<u128>,
+    backend: Backend
+}
+
+impl Ghash {
+    pub fn new(key: Secret<u128>) -> Ghash {
+        Ghash::with_backend(key, Backend::detect(Feature::Pclmulqdq))
+    }
+
+    /// Panics if this CPU does not support `backend`.
+    pub fn with_backend(key: Secret<u128>, backend: Backend) -> Ghash {
+        assert!(backend.is_supported(Feature::Pclmulqdq), "carry-less multiplication is not supported");
+        Ghash {
+            key: key,
+            accumulator: Secret::new(0),
+            backend: backend
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn update_block(&mut self, block: Secret<u128>) {
+        let sum = self.accumulator ^ block;
+        self.accumulator = match self.backend {
+            Backend::Portable => multiply_portable(sum, self.key),
+            Backend::Hardware => multiply_hardware(sum, self.key)
+        };
+    }
+
+    /// Absorbs `data`, padding its last block with zeros, as GCM does for the associated data
+    /// and the ciphertext.
+    pub fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut bytes = [0; 16];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(Secret::new(u128::from_be_bytes(bytes)));
+        }
+    }
+
+    pub fn finalize(self) -> Secret<u128> {
+        self.accumulator
+    }
+}
+
+// Algorithm 1 of SP 800-38D, with masks in place of the branches
+fn multiply_portable(x: Secret<u128>, y: Secret<u128>) -> Secret<u128> {
+    let mut product = Secret::new(0);
+    let mut v = y;
+    for i in 0..128 {
+        let bit = (x >> (127 - i)) & 1;
+        product ^= v & Secret::new(0).wrapping_sub(bit);
+        v = (v >> 1) ^ (Secret::new(0).wrapping_sub(v & 1) & REDUCTION);
+    }
+    product
+}
+
+#[cfg(target_arch = "x86_64")]
+fn multiply_hardware(x: Secret<u128>, y: Secret<u128>) -> Secret<u128> {
+    Secret::new(unsafe { x86_64::multiply(x.expose(), y.expose()) })
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn multiply_hardware(_x: Secret<u128>, _y: Secret<u128>) -> Secret<u128> {
+    unreachable!()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use std::arch::x86_64::*;
+
+    #[inline(always)]
+    unsafe fn load(value: u128) -> __m128i {
+        _mm_set_epi64x((value >> 64) as i64, value as i64)
+    }
+
+    #[inline(always)]
+    unsafe fn store(value: __m128i) -> u128 {
+        let mut halves = [0u64; 2];
+        _mm_storeu_si128(halves.as_mut_ptr() as *mut __m128i, value);
+        ((halves[1] as u128) << 64) | halves[0] as u128
+    }
+
+    /// Multiplication in GCM's field, following Figure 5 of Gueron and Kounavis, "Intel
+    /// Carry-Less Multiplication Instruction and its Usage for Computing the GCM Mode". Our
+    /// big-endian blocks are already the byte-reflected operands it expects.
+    #[target_feature(enable = "pclmulqdq,sse2")]
+    pub unsafe fn multiply(x: u128, y: u128) -> u128 {
+        let (a, b) = (load(x), load(y));
+
+        // The 256-bit carry-less product, by schoolbook multiplication of the 64-bit halves
+        let mut low = _mm_clmulepi64_si128(a, b, 0x00);
+        let mut middle = _mm_xor_si128(_mm_clmulepi64_si128(a, b, 0x10), _mm_clmulepi64_si128(a, b, 0x01));
+        let mut high = _mm_clmulepi64_si128(a, b, 0x11);
+        low = _mm_xor_si128(low, _mm_slli_si128(middle, 8));
+        high = _mm_xor_si128(high, _mm_srli_si128(middle, 8));
+
+        // The operands are bit-reflected, so the product must be shifted left by one.
+        let low_carries = _mm_srli_epi32(low, 31);
+        let high_carries = _mm_srli_epi32(high, 31);
+        low = _mm_slli_epi32(low, 1);
+        high = _mm_slli_epi32(high, 1);
+        let crossing = _mm_srli_si128(low_carries, 12);
+        low = _mm_or_si128(low, _mm_slli_si128(low_carries, 4));
+        high = _mm_or_si128(high, _mm_slli_si128(high_carries, 4));
+        high = _mm_or_si128(high, crossing);
+
+        // Reduce modulo x^128 + x^7 + x^2 + x + 1, in two phases.
+        middle = _mm_xor_si128(_mm_xor_si128(_mm_slli_epi32(low, 31), _mm_slli_epi32(low, 30)), _mm_slli_epi32(low, 25));
+        let spill = _mm_srli_si128(middle, 4);
+        low = _mm_xor_si128(low, _mm_slli_si128(middle, 12));
+        let mut reduced = _mm_xor_si128(_mm_xor_si128(_mm_srli_epi32(low, 1), _mm_srli_epi32(low, 2)), _mm_srli_epi32(low, 7));
+        reduced = _mm_xor_si128(_mm_xor_si128(reduced, spill), low);
+        store(_mm_xor_si128(high, reduced))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    extern crate rand;
+
+    use super::Ghash;
+
+    use cipher::block::backend::{Backend, Feature, backends};
+    use secret::Secret;
+    use utils::hex;
+
+    use self::test::Bencher;
+    use self::rand::{Rng, thread_rng};
+
+    fn random_block<R: Rng>(rng: &mut R) -> u128 {
+        ((rng.gen::<u64>() as u128) << 64) | rng.gen::<u64>() as u128
+    }
+
+    #[test]
+    fn gcm_test_case_4() {
+        // From "The Galois/Counter Mode of Operation (GCM)" by McGrew and Viega, Appendix B
+        let key = Secret::new(0xb83b533708bf535d0aa6e52980d53b78);
+        let associated_data = hex::decode("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+        let ciphertext = hex::decode("
+            42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e
+            21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091");
+        for &backend in backends(Feature::Pclmulqdq).iter() {
+            let mut ghash = Ghash::with_backend(key, backend);
+            ghash.update_padded(&associated_data);
+            ghash.update_padded(&ciphertext);
+            ghash.update_block(Secret::new((160 << 64) | 480));
+            assert_eq!(ghash.finalize().expose(), 0x698e57f70e6ecc7fd9463b7260a9ae5f);
+        }
+    }
+
+    #[test]
+    fn backends_agree() {
+        if !Backend::Hardware.is_supported(Feature::Pclmulqdq) {
+            return;
+        }
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let key = Secret::new(random_block(&mut rng));
+            let mut portable = Ghash::with_backend(key, Backend::Portable);
+            let mut hardware = Ghash::with_backend(key, Backend::Hardware);
+            for _ in 0..10 {
+                let block = Secret::new(random_block(&mut rng));
+                portable.update_block(block);
+                hardware.update_block(block);
+            }
+            assert_eq!(portable.finalize().expose(), hardware.finalize().expose());
+        }
+    }
+
+    fn bench_backend(bencher: &mut Bencher, backend: Backend) {
+        if !backend.is_supported(Feature::Pclmulqdq) {
+            return;
+        }
+        let mut ghash = Ghash::with_backend(Secret::new(0x66e94bd4ef8a2c3b884cfa59ca342b2e), backend);
+        let data = [0; 1024];
+        bencher.bytes = data.len() as u64;
+        bencher.iter(|| {
+            ghash.update_padded(&data);
+        });
+    }
+
+    #[bench]
+    fn bench_portable(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Portable);
+    }
+
+    #[bench]
+    fn bench_hardware(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Hardware);
+    }
+}
@@ -0,0 +1,374 @@
+use aead::AuthenticationError;
+use secret::Secret;
+use utils::compare::constant_time_eq;
+use utils::traits::truncate::Truncate;
+use utils::traits::wrapping::WrappingSub;
+
+// See the Grain-128AEADv2 specification submitted to the NIST lightweight cryptography
+// competition. The initialization clocks the registers 320 times with the output fed back, then
+// 64 times with the key fed in again, and the next 128 outputs fill the accumulator and the
+// shift register of the authenticator.
+const INIT_STEPS: usize = 320 / 32;
+const KEY_STEPS: usize = 64 / 32;
+const TAG_LEN: usize = 8;
+
+fn load_words(bytes: &[u8], words: &mut [Secret<u32>]) {
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = Secret::new(chunk.iter().rev().fold(0, |word, &byte| (word << 8) | byte as u32));
+    }
+}
+
+// The next 32 values of bit `offset` of a register
+fn tap(words: &[Secret<u32>; 4], offset: u32) -> Secret<u32> {
+    let (index, shift) = ((offset / 32) as usize, offset % 32);
+    if shift == 0 {
+        words[index]
+    } else {
+        (words[index] >> shift) | (words[index + 1] << (32 - shift))
+    }
+}
+
+fn push(words: &mut [Secret<u32>; 4], bits: Secret<u32>) {
+    *words = [words[1], words[2], words[3], bits];
+}
+
+// Moves the even bits to the low half and the odd bits to the high half. See Hacker's Delight,
+// Section 7-2.
+fn unshuffle(x: Secret<u32>) -> Secret<u32> {
+    let mut x = x;
+    let t = (x ^ (x >> 1)) & 0x22222222;
+    x ^= t ^ (t << 1);
+    let t = (x ^ (x >> 2)) & 0x0C0C0C0C;
+    x ^= t ^ (t << 2);
+    let t = (x ^ (x >> 4)) & 0x00F000F0;
+    x ^= t ^ (t << 4);
+    let t = (x ^ (x >> 8)) & 0x0000FF00;
+    x ^ t ^ (t << 8)
+}
+
+/// The Grain-128 keystream generator: a 128-bit LFSR and a 128-bit NFSR, with bit i of each the
+/// bit that will leave after i more clocks.
+///
+/// No tap is within 32 bits of the end where bits enter, so the next 32 values of every tap are
+/// known and 32 clocks can be computed at once.
+struct Grain128 {
+    lfsr: [Secret<u32>; 4],
+    nfsr: [Secret<u32>; 4]
+}
+
+impl Grain128 {
+    // The next 32 output bits, as the pre-output function y
+    fn output(&self) -> Secret<u32> {
+        let (s, b) = (&self.lfsr, &self.nfsr);
+        let h = (tap(b, 12) & tap(s, 8)) ^ (tap(s, 13) & tap(s, 20)) ^ (tap(b, 95) & tap(s, 42))
+            ^ (tap(s, 60) & tap(s, 79)) ^ (tap(b, 12) & tap(b, 95) & tap(s, 94));
+        let mut y = h ^ tap(s, 93);
+        for &offset in [2, 15, 36, 45, 64, 73, 89].iter() {
+            y ^= tap(b, offset);
+        }
+        y
+    }
+
+    // Clocks 32 times, XORing `lfsr_input` and `nfsr_input` into the new bits, and returns the
+    // output from before the clocks.
+    fn step(&mut self, lfsr_input: Secret<u32>, nfsr_input: Secret<u32>) -> Secret<u32> {
+        let y = self.output();
+        let (s, b) = (&self.lfsr, &self.nfsr);
+        let f = tap(s, 0) ^ tap(s, 7) ^ tap(s, 38) ^ tap(s, 70) ^ tap(s, 81) ^ tap(s, 96);
+        let g = tap(b, 0) ^ tap(b, 26) ^ tap(b, 56) ^ tap(b, 91) ^ tap(b, 96)
+            ^ (tap(b, 3) & tap(b, 67)) ^ (tap(b, 11) & tap(b, 13)) ^ (tap(b, 17) & tap(b, 18))
+            ^ (tap(b, 27) & tap(b, 59)) ^ (tap(b, 40) & tap(b, 48)) ^ (tap(b, 61) & tap(b, 65))
+            ^ (tap(b, 68) & tap(b, 84)) ^ (tap(b, 22) & tap(b, 24) & tap(b, 25))
+            ^ (tap(b, 70) & tap(b, 78) & tap(b, 82)) ^ (tap(b, 88) & tap(b, 92) & tap(b, 93) & tap(b, 95));
+        let nfsr_bits = tap(s, 0) ^ g ^ nfsr_input;
+        push(&mut self.lfsr, f ^ lfsr_input);
+        push(&mut self.nfsr, nfsr_bits);
+        y
+    }
+}
+
+/// Grain-128AEADv2, with a 128-bit key, a 96-bit nonce and a 64-bit tag.
+///
+/// Bits are numbered from the least significant bit of the first byte, for the key, the nonce,
+/// the messages and the tag. Never encrypt two messages with the same key and nonce.
+///
+/// The keystream generator is checked against the published Grain-128a vectors, but the
+/// authenticator has not been checked against the designers' or NIST's test vectors.
+pub struct Grain128Aead {
+    key: [Secret<u32>; 4]
+}
+
+impl Grain128Aead {
+    pub fn new(key: &[u8; 16]) -> Grain128Aead {
+        let mut words = [Secret::new(0); 4];
+        load_words(key, &mut words);
+        Grain128Aead {
+            key: words
+        }
+    }
+
+    fn start(&self, nonce: &[u8; 12]) -> Grain128AeadState {
+        let mut lfsr = [Secret::new(0x7FFFFFFF); 4];
+        load_words(nonce, &mut lfsr[..3]);
+        let mut grain = Grain128 {
+            lfsr: lfsr,
+            nfsr: self.key
+        };
+
+        for _ in 0..INIT_STEPS {
+            let y = grain.output();
+            grain.step(y, y);
+        }
+        for i in 0..KEY_STEPS {
+            grain.step(self.key[2 + i], self.key[i]);
+        }
+
+        let words: Vec<Secret<u64>> = (0..4).map(|_| Secret::<u64>::from(grain.step(Secret::new(0), Secret::new(0)))).collect();
+        Grain128AeadState {
+            grain: grain,
+            accumulator: words[0] | (words[1] << 32),
+            register: words[2] | (words[3] << 32),
+            keystream: [Secret::new(0); 2],
+            authentication: [Secret::new(0); 2],
+            position: 0
+        }
+    }
+
+    /// Encrypts `buffer` in place and writes the tag covering it and `associated_data`.
+    pub fn encrypt(&self, nonce: &[u8; 12], associated_data: &[u8], buffer: &mut [u8], tag: &mut [u8; TAG_LEN]) {
+        let mut state = self.start(nonce);
+        state.authenticate_associated_data(associated_data);
+        for byte in buffer.iter_mut() {
+            let keystream = state.next_keystream();
+            state.authenticate(Secret::new(*byte));
+            *byte ^= keystream.expose();
+        }
+        state.finalize(tag);
+    }
+
+    /// Decrypts `buffer` in place and checks it against `tag`. On failure, `buffer` is zeroed.
+    pub fn decrypt(&self, nonce: &[u8; 12], associated_data: &[u8], buffer: &mut [u8], tag: &[u8; TAG_LEN]) -> Result<(), AuthenticationError> {
+        let mut state = self.start(nonce);
+        state.authenticate_associated_data(associated_data);
+        for byte in buffer.iter_mut() {
+            let keystream = state.next_keystream();
+            *byte ^= keystream.expose();
+            state.authenticate(Secret::new(*byte));
+        }
+
+        let mut expected = [0; TAG_LEN];
+        state.finalize(&mut expected);
+        if constant_time_eq(&expected, tag) {
+            Ok(())
+        } else {
+            for byte in buffer.iter_mut() {
+                *byte = 0;
+            }
+            Err(AuthenticationError)
+        }
+    }
+}
+
+struct Grain128AeadState {
+    grain: Grain128,
+    accumulator: Secret<u64>,
+    register: Secret<u64>,
+    // Output split into the even bits, which encrypt, and the odd bits, which authenticate,
+    // 8 bits per byte of message
+    keystream: [Secret<u8>; 2],
+    authentication: [Secret<u8>; 2],
+    position: usize
+}
+
+impl Grain128AeadState {
+    // The associated data is preceded by its length in DER encoding, and its keystream is unused.
+    fn authenticate_associated_data(&mut self, associated_data: &[u8]) {
+        let len = associated_data.len();
+        let len_bytes = (0..8).rev().map(|i| (len as u64 >> (8 * i)) as u8).skip_while(|&byte| byte == 0).collect::<Vec<u8>>();
+        let mut encoded_len = Vec::new();
+        if len < 128 {
+            encoded_len.push(len as u8);
+        } else {
+            encoded_len.push(0x80 | len_bytes.len() as u8);
+            encoded_len.extend_from_slice(&len_bytes);
+        }
+
+        for &byte in encoded_len.iter().chain(associated_data.iter()) {
+            self.next_keystream();
+            self.authenticate(Secret::new(byte));
+        }
+    }
+
+    // The keystream for the next byte, without moving past it
+    fn next_keystream(&mut self) -> Secret<u8> {
+        if self.position == 0 {
+            let bits = unshuffle(self.grain.step(Secret::new(0), Secret::new(0)));
+            self.keystream = [bits.truncate(), (bits >> 8).truncate()];
+            self.authentication = [(bits >> 16).truncate(), (bits >> 24).truncate()];
+        }
+        self.keystream[self.position]
+    }
+
+    // Authenticates a byte of message and moves past its keystream. When a message bit is set,
+    // the register is added to the accumulator, and each bit shifts an authentication bit into
+    // the register.
+    fn authenticate(&mut self, message: Secret<u8>) {
+        let message = Secret::<u64>::from(message);
+        let authentication = Secret::<u64>::from(self.authentication[self.position]);
+        for i in 0..8 {
+            let mask = Secret::new(0).wrapping_sub((message >> i) & 1);
+            self.accumulator ^= self.register & mask;
+            self.register = (self.register >> 1) | (((authentication >> i) & 1) << 63);
+        }
+        self.position = (self.position + 1) % 2;
+    }
+
+    // The message is padded with a single 1 bit.
+    fn finalize(mut self, tag: &mut [u8; TAG_LEN]) {
+        self.accumulator ^= self.register;
+        for (i, byte) in tag.iter_mut().enumerate() {
+            let value: Secret<u8> = (self.accumulator >> (8 * i as u32)).truncate();
+            *byte = value.expose();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Grain128, Grain128Aead, load_words};
+
+    use aead::AuthenticationError;
+    use secret::Secret;
+    use utils::hex;
+
+    use self::test::Bencher;
+
+    const KEY: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    const NONCE: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+    // Self-generated by a bit-by-bit model of the specification, not taken from the NIST
+    // lightweight cryptography KAT file, so these only catch regressions in the authenticator. The
+    // associated data and plaintext count up from 00, and the expected value is the ciphertext
+    // followed by the tag.
+    const SELF_GENERATED: [(usize, usize, &'static str); 7] = [
+        (0, 0, "1399a94a59b44ab8"),
+        (1, 0, "4c1baa163832d048"),
+        (0, 1, "acb8c0da3f621dc4f5"),
+        (1, 1, "3d1568df2d91bc9db8"),
+        (8, 8, "dc55ed090c5b3f5cc5eded36337b34ff"),
+        (0, 17, "ac3c2e5f258b2d8fd45de5010453375407711d92c19c3fff8d"),
+        (32, 32, "0bbaac38e479fba826e26d7b4645c2cd689e2f8c23ba0d47dc10e9e7483edff0b57d67e319fb2e64")
+    ];
+
+    // Associated data longer than 127 bytes, which has a long DER length. Also self-generated.
+    const LONG_ASSOCIATED_DATA: [(usize, usize, &'static str); 2] = [
+        (130, 5, "f5ab58a79e62c909f360c2590d"),
+        (300, 33, "5127db2af63964e7f32c7b36630012f8b06e4e29fe63016742d7979209ee69d74f632a71540c6927ff")
+    ];
+
+    fn counting(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    fn check(associated_len: usize, text_len: usize, expected: &str) {
+        let cipher = Grain128Aead::new(&KEY);
+        let expected = hex::decode(expected);
+        let associated_data = counting(associated_len);
+        let mut buffer = counting(text_len);
+        let mut tag = [0; 8];
+        cipher.encrypt(&NONCE, &associated_data, &mut buffer, &mut tag);
+        assert_eq!(&buffer[..], &expected[..text_len]);
+        assert_eq!(&tag[..], &expected[text_len..]);
+
+        assert_eq!(cipher.decrypt(&NONCE, &associated_data, &mut buffer, &tag), Ok(()));
+        assert_eq!(buffer, counting(text_len));
+    }
+
+    // The pre-output test vectors of Grain-128a, from Ågren et al., "The Grain Family of Stream
+    // Ciphers", which has the same generator with 256 initialization clocks and no key fed in
+    // again. Its bits are numbered from the most significant bit of each byte.
+    #[test]
+    fn grain128a_pre_output() {
+        let vectors = [
+            ("00000000000000000000000000000000", "000000000000000000000000",
+             "c0207f221660650b6a952ae26586136fa0904140c8621cfe8660c0dec0969e9436f4ace92cf1ebb7"),
+            ("0123456789abcdef123456789abcdef0", "0123456789abcdef12345678",
+             "f88720c13f46e6a43c07eeed89161a4dd73bd6b8be8b6b116879714ebb630e0a4c12f0399412982c")
+        ];
+        let reversed = |hex_string: &str| hex::decode(hex_string).iter().map(|byte| byte.reverse_bits()).collect::<Vec<u8>>();
+        for &(key, iv, expected) in vectors.iter() {
+            let mut grain = Grain128 {
+                lfsr: [Secret::new(0x7FFFFFFF); 4],
+                nfsr: [Secret::new(0); 4]
+            };
+            load_words(&reversed(key), &mut grain.nfsr);
+            load_words(&reversed(iv), &mut grain.lfsr[..3]);
+            for _ in 0..256 / 32 {
+                let y = grain.output();
+                grain.step(y, y);
+            }
+
+            let mut output = Vec::new();
+            for _ in 0..10 {
+                let y = grain.step(Secret::new(0), Secret::new(0)).expose();
+                output.extend_from_slice(&[y as u8, (y >> 8) as u8, (y >> 16) as u8, (y >> 24) as u8]);
+            }
+            assert_eq!(output, reversed(expected));
+        }
+    }
+
+    #[test]
+    fn zero_key_and_nonce() {
+        // Self-generated, like `SELF_GENERATED`
+        let mut tag = [0; 8];
+        Grain128Aead::new(&[0; 16]).encrypt(&[0; 12], &[], &mut [], &mut tag);
+        assert_eq!(&tag[..], &hex::decode("8868f40eccffa687")[..]);
+    }
+
+    #[test]
+    fn self_generated() {
+        for &(associated_len, text_len, expected) in SELF_GENERATED.iter() {
+            check(associated_len, text_len, expected);
+        }
+    }
+
+    #[test]
+    fn long_associated_data() {
+        for &(associated_len, text_len, expected) in LONG_ASSOCIATED_DATA.iter() {
+            check(associated_len, text_len, expected);
+        }
+    }
+
+    #[test]
+    fn tampering_detected() {
+        let cipher = Grain128Aead::new(&KEY);
+        let mut buffer = counting(20);
+        let mut tag = [0; 8];
+        cipher.encrypt(&NONCE, b"header", &mut buffer, &mut tag);
+
+        let mut modified = buffer.clone();
+        modified[19] ^= 0x80;
+        assert_eq!(cipher.decrypt(&NONCE, b"header", &mut modified, &tag), Err(AuthenticationError));
+        assert_eq!(modified, vec![0; 20]);
+
+        let mut copy = buffer.clone();
+        assert_eq!(cipher.decrypt(&NONCE, b"Header", &mut copy, &tag), Err(AuthenticationError));
+
+        let mut copy = buffer.clone();
+        tag[0] ^= 1;
+        assert_eq!(cipher.decrypt(&NONCE, b"header", &mut copy, &tag), Err(AuthenticationError));
+    }
+
+    #[bench]
+    fn bench(bencher: &mut Bencher) {
+        let cipher = Grain128Aead::new(&KEY);
+        let mut buffer = [0; 1024];
+        let mut tag = [0; 8];
+        bencher.bytes = buffer.len() as u64;
+        bencher.iter(|| {
+            cipher.encrypt(&NONCE, &[], &mut buffer, &mut tag);
+        });
+    }
+}
@@ -0,0 +1,20 @@
+use std::error::Error;
+use std::fmt;
+
+pub mod spongewrap;
+pub mod ascon;
+pub mod norx;
+pub mod grain128aead;
+pub mod ghash;
+
+/// The error returned when a ciphertext or its associated data has been tampered with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AuthenticationError;
+
+impl fmt::Display for AuthenticationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("authentication tag mismatch")
+    }
+}
+
+impl Error for AuthenticationError { }
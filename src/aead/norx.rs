@@ -0,0 +1,442 @@
+use std::marker::PhantomData;
+
+use aead::AuthenticationError;
+use permutation::{ByteState, Permutation};
+use permutation::norx::{Norx, NorxWord};
+use secret::Secret;
+use utils::compare::constant_time_eq;
+
+use typenum::consts::{U1, U2, U4};
+use typenum::uint::Unsigned;
+
+// Domain separation constants, XORed into the last word before each permutation. See the NORX
+// v3.0 specification, Section 2.
+const HEADER_TAG: u8 = 0x01;
+const PAYLOAD_TAG: u8 = 0x02;
+const TRAILER_TAG: u8 = 0x04;
+const FINAL_TAG: u8 = 0x08;
+const BRANCH_TAG: u8 = 0x10;
+const MERGE_TAG: u8 = 0x20;
+
+// The rate is the first 12 of the 16 words.
+const RATE_WORDS: usize = 12;
+// The rate in bytes with 64-bit words, the largest there is
+const MAX_RATE_BYTES: usize = RATE_WORDS * 8;
+
+/// The NORX authenticated encryption scheme with `Rounds` rounds and `Parallelism` lanes, with a
+/// key and nonce of four words each and a tag of four words.
+///
+/// With more than one lane, the payload blocks are spread across independent copies of the state
+/// that are merged again afterwards, so the result differs from the sequential mode.
+///
+/// This has only been checked against a transcription of the specification in the tests, not
+/// against the designers' test vectors.
+pub struct NorxAead<T, Rounds, Parallelism> {
+    key: [T; 4],
+    _marker: PhantomData<(Rounds, Parallelism)>
+}
+
+pub type Norx32_4_1 = NorxAead<Secret<u32>, U4, U1>;
+pub type Norx32_4_2 = NorxAead<Secret<u32>, U4, U2>;
+pub type Norx32_4_4 = NorxAead<Secret<u32>, U4, U4>;
+pub type Norx64_4_1 = NorxAead<Secret<u64>, U4, U1>;
+pub type Norx64_4_2 = NorxAead<Secret<u64>, U4, U2>;
+pub type Norx64_4_4 = NorxAead<Secret<u64>, U4, U4>;
+
+impl<T: NorxWord, Rounds: Unsigned, Parallelism: Unsigned> NorxAead<T, Rounds, Parallelism> {
+    /// The size of keys, nonces and tags in bytes
+    pub fn key_len() -> usize {
+        4 * T::bytes()
+    }
+
+    pub fn new(key: &[u8]) -> NorxAead<T, Rounds, Parallelism> {
+        assert_eq!(key.len(), Self::key_len());
+        assert!(Parallelism::to_usize() > 0);
+
+        let mut words = [T::zero(); 4];
+        for (i, &byte) in key.iter().enumerate() {
+            words[i / T::bytes()].xor_byte(i % T::bytes(), byte);
+        }
+        NorxAead {
+            key: words,
+            _marker: PhantomData
+        }
+    }
+
+    /// Encrypts `payload` in place and writes a tag covering it, `header` and `trailer`.
+    pub fn encrypt(&self, nonce: &[u8], header: &[u8], payload: &mut [u8], trailer: &[u8], tag: &mut [u8]) {
+        assert_eq!(tag.len(), Self::key_len());
+
+        let mut state = self.initialize(nonce);
+        absorb(&mut state, header, HEADER_TAG);
+        process_payload::<T, Rounds>(&mut state, payload, false, Parallelism::to_usize());
+        absorb(&mut state, trailer, TRAILER_TAG);
+        self.finalize(state, tag);
+    }
+
+    /// Decrypts `payload` in place and checks it against `tag`. On failure, `payload` is zeroed.
+    pub fn decrypt(&self, nonce: &[u8], header: &[u8], payload: &mut [u8], trailer: &[u8], tag: &[u8]) -> Result<(), AuthenticationError> {
+        assert_eq!(tag.len(), Self::key_len());
+
+        let mut state = self.initialize(nonce);
+        absorb(&mut state, header, HEADER_TAG);
+        process_payload::<T, Rounds>(&mut state, payload, true, Parallelism::to_usize());
+        absorb(&mut state, trailer, TRAILER_TAG);
+        let mut expected = vec![0; tag.len()];
+        self.finalize(state, &mut expected);
+
+        if constant_time_eq(&expected, tag) {
+            Ok(())
+        } else {
+            for byte in payload.iter_mut() {
+                *byte = 0;
+            }
+            Err(AuthenticationError)
+        }
+    }
+
+    fn initialize(&self, nonce: &[u8]) -> Norx<T, Rounds> {
+        assert_eq!(nonce.len(), Self::key_len());
+
+        // The constants u8..u15 are the last half of F^2(0, 1, ..., 15).
+        let mut constants = [T::zero(); 16];
+        for (i, word) in constants.iter_mut().enumerate() {
+            word.xor_byte(0, i as u8);
+        }
+        T::f_rounds(&mut constants, 2);
+
+        let mut words = [T::zero(); 16];
+        words[8..].copy_from_slice(&constants[8..]);
+        for (i, &byte) in nonce.iter().enumerate() {
+            words[i / T::bytes()].xor_byte(i % T::bytes(), byte);
+        }
+        words[4..8].copy_from_slice(&self.key);
+
+        let bits = 8 * T::bytes();
+        xor_small(&mut words[12], bits);
+        xor_small(&mut words[13], Rounds::to_usize());
+        xor_small(&mut words[14], Parallelism::to_usize());
+        xor_small(&mut words[15], 4 * bits);
+
+        let mut state = Norx::new(words);
+        state.permute();
+        self.add_key(&mut state);
+        state
+    }
+
+    fn finalize(&self, mut state: Norx<T, Rounds>, tag: &mut [u8]) {
+        add_domain(&mut state, FINAL_TAG);
+        state.permute();
+        self.add_key(&mut state);
+        state.permute();
+        self.add_key(&mut state);
+        state.extract_bytes(12 * T::bytes(), tag);
+    }
+
+    fn add_key(&self, state: &mut Norx<T, Rounds>) {
+        let mut words = state.words();
+        for (word, &key) in words[12..].iter_mut().zip(self.key.iter()) {
+            *word = *word ^ key;
+        }
+        *state = Norx::new(words);
+    }
+}
+
+fn xor_small<T: NorxWord>(word: &mut T, value: usize) {
+    word.xor_byte(0, value as u8);
+    word.xor_byte(1, (value >> 8) as u8);
+}
+
+fn add_domain<T: NorxWord, Rounds: Unsigned>(state: &mut Norx<T, Rounds>, domain: u8) {
+    state.xor_bytes(15 * T::bytes(), &[domain]);
+}
+
+// Adds multi-rate padding for a final block of `len` bytes that is already in the state.
+fn pad<T: NorxWord, Rounds: Unsigned>(state: &mut Norx<T, Rounds>, len: usize) {
+    state.xor_bytes(len, &[0x01]);
+    state.xor_bytes(RATE_WORDS * T::bytes() - 1, &[0x80]);
+}
+
+fn absorb<T: NorxWord, Rounds: Unsigned>(state: &mut Norx<T, Rounds>, data: &[u8], domain: u8) {
+    let rate = RATE_WORDS * T::bytes();
+    if data.is_empty() {
+        return;
+    }
+
+    // Nonempty data always ends with a padded block, even if that block is only padding.
+    let mut blocks = data.chunks(rate).peekable();
+    while let Some(block) = blocks.next() {
+        add_domain(state, domain);
+        state.permute();
+        state.xor_bytes(0, block);
+        if block.len() < rate {
+            pad(state, block.len());
+        } else if blocks.peek().is_none() {
+            add_domain(state, domain);
+            state.permute();
+            pad(state, 0);
+        }
+    }
+}
+
+// Encrypts or decrypts one payload block in place, padding it if it is the last.
+fn crypt_block<T: NorxWord, Rounds: Unsigned>(state: &mut Norx<T, Rounds>, block: &mut [u8], last: bool, decrypt: bool) {
+    add_domain(state, PAYLOAD_TAG);
+    state.permute();
+    if decrypt {
+        let mut buffer = [0; MAX_RATE_BYTES];
+        let keystream = &mut buffer[..block.len()];
+        state.extract_bytes(0, keystream);
+        for (byte, &key) in block.iter_mut().zip(keystream.iter()) {
+            *byte ^= key;
+        }
+        state.xor_bytes(0, block);
+    } else {
+        state.xor_bytes(0, block);
+        state.extract_bytes(0, block);
+    }
+    if last {
+        pad(state, block.len());
+    }
+}
+
+fn process_payload<T: NorxWord, Rounds: Unsigned>(state: &mut Norx<T, Rounds>, payload: &mut [u8], decrypt: bool, lanes: usize) {
+    let rate = RATE_WORDS * T::bytes();
+
+    if lanes == 1 {
+        crypt_blocks(&mut [state], payload, rate, decrypt);
+        return;
+    }
+
+    // Branch: each lane starts from its own copy of the state, marked with its index.
+    let mut states: Vec<Norx<T, Rounds>> = (0..lanes).map(|i| {
+        let mut lane = state.clone();
+        add_domain(&mut lane, BRANCH_TAG);
+        lane.permute();
+        let mut words = lane.words();
+        for word in words[..RATE_WORDS].iter_mut() {
+            xor_small(word, i);
+        }
+        Norx::new(words)
+    }).collect();
+
+    {
+        let mut lane_refs: Vec<&mut Norx<T, Rounds>> = states.iter_mut().collect();
+        crypt_blocks(&mut lane_refs, payload, rate, decrypt);
+    }
+
+    // Merge: every lane is permuted once more, then the lanes are combined pairwise.
+    for lane in states.iter_mut() {
+        add_domain(lane, MERGE_TAG);
+        lane.permute();
+    }
+    while states.len() > 1 {
+        let mut merged = Vec::new();
+        let mut pairs = states.into_iter();
+        while let Some(first) = pairs.next() {
+            merged.push(match pairs.next() {
+                Some(second) => first ^ second,
+                None => first
+            });
+        }
+        states = merged;
+    }
+    *state = states.pop().unwrap();
+}
+
+// Deals the payload blocks out to the lanes in turn. A nonempty payload always ends with a padded
+// block, even if that block is empty.
+fn crypt_blocks<T: NorxWord, Rounds: Unsigned>(lanes: &mut [&mut Norx<T, Rounds>], payload: &mut [u8], rate: usize, decrypt: bool) {
+    if payload.is_empty() {
+        return;
+    }
+
+    let full_blocks = payload.len() / rate;
+    let mut index = 0;
+    for block in payload.chunks_mut(rate) {
+        let lane = index % lanes.len();
+        crypt_block(lanes[lane], block, index == full_blocks, decrypt);
+        index += 1;
+    }
+    if index == full_blocks {
+        let lane = index % lanes.len();
+        crypt_block(lanes[lane], &mut [], true, decrypt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp;
+
+    use super::{NorxAead, Norx32_4_1, Norx32_4_2, Norx32_4_4, Norx64_4_1, Norx64_4_2, Norx64_4_4};
+    use super::{HEADER_TAG, PAYLOAD_TAG, TRAILER_TAG, FINAL_TAG, BRANCH_TAG, MERGE_TAG};
+
+    use permutation::ByteState;
+    use permutation::norx::{Norx, NorxWord};
+    use secret::Secret;
+
+    use typenum::consts::{U1, U2, U4};
+    use typenum::uint::Unsigned;
+
+    // A direct transcription of the specification that only uses the scalar permutation,
+    // processes the lanes one after another and merges them with a single XOR.
+    fn reference<T: NorxWord>(key: &[u8], nonce: &[u8], header: &[u8], payload: &[u8], trailer: &[u8], lanes: usize) -> (Vec<u8>, Vec<u8>) {
+        let w = T::bytes();
+        let rate = 12 * w;
+        let domain = |state: &mut Norx<T, U4>, tag: u8| {
+            state.xor_bytes(15 * w, &[tag]);
+            state.permute_scalar();
+        };
+        let absorb = |state: &mut Norx<T, U4>, data: &[u8], tag: u8| {
+            if data.is_empty() {
+                return;
+            }
+            let mut padded = data.to_vec();
+            padded.push(0x01);
+            while !padded.len().is_multiple_of(rate) {
+                padded.push(0);
+            }
+            let len = padded.len();
+            padded[len - 1] ^= 0x80;
+            for block in padded.chunks(rate) {
+                domain(state, tag);
+                state.xor_bytes(0, block);
+            }
+        };
+
+        let mut constants = [T::zero(); 16];
+        for (i, word) in constants.iter_mut().enumerate() {
+            word.xor_byte(0, i as u8);
+        }
+        let mut init: Norx<T, U2> = Norx::new(constants);
+        init.permute_scalar();
+
+        let mut state: Norx<T, U4> = Norx::new(init.words());
+        let mut bytes = vec![0; 16 * w];
+        state.extract_bytes(0, &mut bytes);
+        state.xor_bytes(0, &bytes[..8 * w]);
+        state.xor_bytes(0, nonce);
+        state.xor_bytes(4 * w, key);
+        let params = [8 * w, 4, lanes, 32 * w];
+        for (i, &param) in params.iter().enumerate() {
+            state.xor_bytes((12 + i) * w, &[param as u8, (param >> 8) as u8]);
+        }
+        state.permute_scalar();
+        state.xor_bytes(12 * w, key);
+
+        absorb(&mut state, header, HEADER_TAG);
+
+        let mut ciphertext = payload.to_vec();
+        let blocks = if payload.is_empty() { 0 } else { payload.len() / rate + 1 };
+        let mut lane_states = Vec::new();
+        for lane in 0..lanes {
+            let mut lane_state = state.clone();
+            if lanes > 1 {
+                domain(&mut lane_state, BRANCH_TAG);
+                for i in 0..12 {
+                    lane_state.xor_bytes(i * w, &[lane as u8]);
+                }
+            }
+            for index in (lane..blocks).filter(|index| index % lanes == lane) {
+                let start = index * rate;
+                let end = cmp::min(start + rate, payload.len());
+                domain(&mut lane_state, PAYLOAD_TAG);
+                lane_state.xor_bytes(0, &payload[start..end]);
+                lane_state.extract_bytes(0, &mut ciphertext[start..end]);
+                if index == blocks - 1 {
+                    lane_state.xor_bytes(end - start, &[0x01]);
+                    lane_state.xor_bytes(rate - 1, &[0x80]);
+                }
+            }
+            if lanes > 1 {
+                domain(&mut lane_state, MERGE_TAG);
+            }
+            lane_states.push(lane_state);
+        }
+        let mut state = lane_states.pop().unwrap();
+        for lane_state in lane_states {
+            state = state ^ lane_state;
+        }
+
+        absorb(&mut state, trailer, TRAILER_TAG);
+
+        domain(&mut state, FINAL_TAG);
+        state.xor_bytes(12 * w, key);
+        state.permute_scalar();
+        state.xor_bytes(12 * w, key);
+        let mut tag = vec![0; 4 * w];
+        state.extract_bytes(12 * w, &mut tag);
+        (ciphertext, tag)
+    }
+
+    fn check<T: NorxWord, P: Unsigned>() {
+        let len = NorxAead::<T, U4, P>::key_len();
+        let key: Vec<u8> = (0..len as u8).collect();
+        let nonce: Vec<u8> = (0..len as u8).map(|i| i + 0x20).collect();
+        let header: Vec<u8> = (0..150u8).collect();
+        let trailer: Vec<u8> = (0..20u8).collect();
+        let cipher = NorxAead::<T, U4, P>::new(&key);
+
+        let rate = 12 * T::bytes();
+        for &payload_len in [0, 1, rate - 1, rate, rate + 1, 3 * rate, 5 * rate + 7, 1000].iter() {
+            let plaintext: Vec<u8> = (0..payload_len).map(|i| (i * 7) as u8).collect();
+            let (expected_ciphertext, expected_tag) = reference::<T>(&key, &nonce, &header, &plaintext, &trailer, P::to_usize());
+
+            let mut buffer = plaintext.clone();
+            let mut tag = vec![0; len];
+            cipher.encrypt(&nonce, &header, &mut buffer, &trailer, &mut tag);
+            assert_eq!(buffer, expected_ciphertext);
+            assert_eq!(tag, expected_tag);
+
+            assert!(cipher.decrypt(&nonce, &header, &mut buffer, &trailer, &tag).is_ok());
+            assert_eq!(buffer, plaintext);
+
+            cipher.encrypt(&nonce, &header, &mut buffer, &trailer, &mut tag);
+            tag[0] ^= 1;
+            assert!(cipher.decrypt(&nonce, &header, &mut buffer, &trailer, &tag).is_err());
+            assert!(buffer.iter().all(|&byte| byte == 0));
+        }
+    }
+
+    #[test]
+    fn matches_reference_32() {
+        check::<Secret<u32>, U1>();
+        check::<Secret<u32>, U2>();
+        check::<Secret<u32>, U4>();
+    }
+
+    #[test]
+    fn matches_reference_64() {
+        check::<Secret<u64>, U1>();
+        check::<Secret<u64>, U2>();
+        check::<Secret<u64>, U4>();
+    }
+
+    #[test]
+    fn lanes_are_separated() {
+        let key = [0x42; 32];
+        let nonce = [0x24; 32];
+        let mut tags = Vec::new();
+        for p in 0..3 {
+            let mut buffer = [0; 400];
+            let mut tag = [0; 32];
+            match p {
+                0 => Norx64_4_1::new(&key).encrypt(&nonce, b"", &mut buffer, b"", &mut tag),
+                1 => Norx64_4_2::new(&key).encrypt(&nonce, b"", &mut buffer, b"", &mut tag),
+                _ => Norx64_4_4::new(&key).encrypt(&nonce, b"", &mut buffer, b"", &mut tag)
+            }
+            tags.push(tag);
+        }
+        assert!(tags[0] != tags[1] && tags[1] != tags[2] && tags[0] != tags[2]);
+
+        let key = [0x42; 16];
+        let nonce = [0x24; 16];
+        let mut tag1 = [0; 16];
+        let mut tag2 = [0; 16];
+        let mut tag4 = [0; 16];
+        Norx32_4_1::new(&key).encrypt(&nonce, b"", &mut [], b"", &mut tag1);
+        Norx32_4_2::new(&key).encrypt(&nonce, b"", &mut [], b"", &mut tag2);
+        Norx32_4_4::new(&key).encrypt(&nonce, b"", &mut [], b"", &mut tag4);
+        assert!(tag1 != tag2 && tag2 != tag4);
+    }
+}
@@ -0,0 +1,187 @@
+use aead::AuthenticationError;
+use permutation::{ByteState, Permutation};
+use permutation::duplex::KeyedDuplex;
+use utils::compare::constant_time_eq;
+
+// Frame bits, encoded as domain bits for the duplex. Every block but the last of the header and
+// the last of the body is followed by a 0; the last header block and the other body blocks are
+// followed by a 1.
+const FRAME_0: u8 = 0x02;
+const FRAME_1: u8 = 0x03;
+
+/// Authenticated encryption in the style of SpongeWrap, on top of a keyed duplex.
+///
+/// The object is stateful: every message wrapped or unwrapped is authenticated together with all
+/// of the messages before it, so the same sequence of calls must be made on both ends. A nonce
+/// should be placed in the header of the first message if the key is reused.
+pub struct SpongeWrap<P> {
+    duplex: KeyedDuplex<P>
+}
+
+impl<P: Permutation + ByteState + Default> SpongeWrap<P> {
+    pub fn new(key: &[u8], rate: usize) -> SpongeWrap<P> {
+        SpongeWrap {
+            duplex: KeyedDuplex::new(key, rate)
+        }
+    }
+
+    /// Encrypts `body` in place and writes an authentication tag covering `header` and `body`.
+    pub fn wrap(&mut self, header: &[u8], body: &mut [u8], tag: &mut [u8]) {
+        let rate = self.duplex.rate();
+        let mut keystream = vec![0; rate];
+
+        self.absorb_header(header, &mut keystream);
+
+        let mut blocks = body.chunks_mut(rate).peekable();
+        if blocks.peek().is_none() {
+            self.duplex.duplex_with_domain(&[], FRAME_0, &mut keystream);
+        }
+        while let Some(block) = blocks.next() {
+            let plaintext = block.to_vec();
+            for (byte, &key) in block.iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+
+            let frame = if blocks.peek().is_some() { FRAME_1 } else { FRAME_0 };
+            self.duplex.duplex_with_domain(&plaintext, frame, &mut keystream);
+        }
+
+        self.squeeze_tag(&keystream, tag);
+    }
+
+    /// Decrypts `body` in place and checks it against `tag`. On failure, `body` is zeroed.
+    pub fn unwrap(&mut self, header: &[u8], body: &mut [u8], tag: &[u8]) -> Result<(), AuthenticationError> {
+        let rate = self.duplex.rate();
+        let mut keystream = vec![0; rate];
+
+        self.absorb_header(header, &mut keystream);
+
+        {
+            let mut blocks = body.chunks_mut(rate).peekable();
+            if blocks.peek().is_none() {
+                self.duplex.duplex_with_domain(&[], FRAME_0, &mut keystream);
+            }
+            while let Some(block) = blocks.next() {
+                for (byte, &key) in block.iter_mut().zip(keystream.iter()) {
+                    *byte ^= key;
+                }
+
+                let frame = if blocks.peek().is_some() { FRAME_1 } else { FRAME_0 };
+                self.duplex.duplex_with_domain(block, frame, &mut keystream);
+            }
+        }
+
+        let mut expected = vec![0; tag.len()];
+        self.squeeze_tag(&keystream, &mut expected);
+
+        if constant_time_eq(&expected, tag) {
+            Ok(())
+        } else {
+            for byte in body.iter_mut() {
+                *byte = 0;
+            }
+            Err(AuthenticationError)
+        }
+    }
+
+    // Absorbs the header, leaving the keystream for the first body block in `keystream`.
+    fn absorb_header(&mut self, header: &[u8], keystream: &mut [u8]) {
+        let rate = self.duplex.rate();
+        let mut blocks = header.chunks(rate).peekable();
+        if blocks.peek().is_none() {
+            self.duplex.duplex_with_domain(&[], FRAME_1, keystream);
+        }
+        while let Some(block) = blocks.next() {
+            if blocks.peek().is_some() {
+                self.duplex.duplex_with_domain(block, FRAME_0, &mut []);
+            } else {
+                self.duplex.duplex_with_domain(block, FRAME_1, keystream);
+            }
+        }
+    }
+
+    // The output of the final body block starts the tag; longer tags need further empty blocks.
+    fn squeeze_tag(&mut self, keystream: &[u8], tag: &mut [u8]) {
+        for (i, block) in tag.chunks_mut(keystream.len()).enumerate() {
+            if i == 0 {
+                let len = block.len();
+                block.copy_from_slice(&keystream[..len]);
+            } else {
+                self.duplex.duplex_with_domain(&[], FRAME_0, block);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpongeWrap;
+
+    use aead::AuthenticationError;
+    use permutation::norx::{Norx32, Norx64};
+
+    #[test]
+    fn round_trip() {
+        for &len in [0, 1, 47, 48, 49, 200].iter() {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut body = plaintext.clone();
+            let mut tag = [0; 16];
+            SpongeWrap::<Norx32>::new(b"secret key", 48).wrap(b"header", &mut body, &mut tag);
+            if len > 0 {
+                assert!(body != plaintext);
+            }
+
+            SpongeWrap::<Norx32>::new(b"secret key", 48).unwrap(b"header", &mut body, &tag).unwrap();
+            assert_eq!(body, plaintext);
+        }
+    }
+
+    #[test]
+    fn long_tag_round_trip() {
+        let mut body = b"a message".to_vec();
+        let mut tag = [0; 200];
+        SpongeWrap::<Norx64>::new(b"secret key", 96).wrap(&[], &mut body, &mut tag);
+        SpongeWrap::<Norx64>::new(b"secret key", 96).unwrap(&[], &mut body, &tag).unwrap();
+        assert_eq!(&body[..], b"a message");
+    }
+
+    #[test]
+    fn multiple_messages() {
+        let mut sender = SpongeWrap::<Norx32>::new(b"secret key", 48);
+        let mut receiver = SpongeWrap::<Norx32>::new(b"secret key", 48);
+        let mut first = b"first".to_vec();
+        let mut second = b"second".to_vec();
+        let mut tag1 = [0; 16];
+        let mut tag2 = [0; 16];
+        sender.wrap(b"nonce", &mut first, &mut tag1);
+        sender.wrap(&[], &mut second, &mut tag2);
+
+        receiver.unwrap(b"nonce", &mut first, &tag1).unwrap();
+        receiver.unwrap(&[], &mut second, &tag2).unwrap();
+        assert_eq!(&first[..], b"first");
+        assert_eq!(&second[..], b"second");
+    }
+
+    #[test]
+    fn tampering_detected() {
+        let mut body = b"attack at dawn".to_vec();
+        let mut tag = [0; 16];
+        SpongeWrap::<Norx32>::new(b"secret key", 48).wrap(b"header", &mut body, &mut tag);
+
+        let mut bad_body = body.clone();
+        bad_body[3] ^= 1;
+        assert_eq!(SpongeWrap::<Norx32>::new(b"secret key", 48).unwrap(b"header", &mut bad_body, &tag), Err(AuthenticationError));
+        assert!(bad_body.iter().all(|&b| b == 0));
+
+        let mut copy = body.clone();
+        assert_eq!(SpongeWrap::<Norx32>::new(b"secret key", 48).unwrap(b"headex", &mut copy, &tag), Err(AuthenticationError));
+
+        let mut bad_tag = tag;
+        bad_tag[15] ^= 0x80;
+        let mut copy = body.clone();
+        assert_eq!(SpongeWrap::<Norx32>::new(b"secret key", 48).unwrap(b"header", &mut copy, &bad_tag), Err(AuthenticationError));
+
+        let mut copy = body.clone();
+        assert_eq!(SpongeWrap::<Norx32>::new(b"other key", 48).unwrap(b"header", &mut copy, &tag), Err(AuthenticationError));
+    }
+}
@@ -0,0 +1,65 @@
+use std::arch::x86_64::*;
+
+use secret::Secret;
+
+// AES-NI works on the bytes of a block in memory order, and our blocks are big-endian.
+#[inline(always)]
+fn load(block: Secret<u128>) -> __m128i {
+    let bytes = block.expose().to_be_bytes();
+    unsafe { _mm_loadu_si128(bytes.as_ptr() as *const __m128i) }
+}
+
+#[inline(always)]
+fn store(block: __m128i) -> Secret<u128> {
+    let mut bytes = [0; 16];
+    unsafe { _mm_storeu_si128(bytes.as_mut_ptr() as *mut __m128i, block) };
+    Secret::new(u128::from_be_bytes(bytes))
+}
+
+/// Round keys in the form that AES-NI uses. Decryption uses the equivalent inverse cipher of
+/// FIPS 197, Section 5.3.5, so its middle round keys have InvMixColumns applied.
+pub struct Schedule {
+    encryption: Vec<__m128i>,
+    decryption: Vec<__m128i>
+}
+
+impl Schedule {
+    /// The CPU must support AES-NI.
+    #[target_feature(enable = "aes,sse2")]
+    pub unsafe fn new(round_keys: &[Secret<u128>]) -> Schedule {
+        let encryption: Vec<__m128i> = round_keys.iter().map(|&key| load(key)).collect();
+        let rounds = encryption.len() - 1;
+        let mut decryption = vec![encryption[rounds]];
+        for &key in encryption[1..rounds].iter().rev() {
+            decryption.push(_mm_aesimc_si128(key));
+        }
+        decryption.push(encryption[0]);
+
+        Schedule {
+            encryption: encryption,
+            decryption: decryption
+        }
+    }
+
+    #[target_feature(enable = "aes,sse2")]
+    pub unsafe fn encrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        let keys = &self.encryption;
+        let rounds = keys.len() - 1;
+        let mut state = _mm_xor_si128(load(block), keys[0]);
+        for &key in keys[1..rounds].iter() {
+            state = _mm_aesenc_si128(state, key);
+        }
+        store(_mm_aesenclast_si128(state, keys[rounds]))
+    }
+
+    #[target_feature(enable = "aes,sse2")]
+    pub unsafe fn decrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        let keys = &self.decryption;
+        let rounds = keys.len() - 1;
+        let mut state = _mm_xor_si128(load(block), keys[0]);
+        for &key in keys[1..rounds].iter() {
+            state = _mm_aesdec_si128(state, key);
+        }
+        store(_mm_aesdeclast_si128(state, keys[rounds]))
+    }
+}
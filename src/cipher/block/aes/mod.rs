@@ -0,0 +1,622 @@
+use std::marker::PhantomData;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::backend::{Backend, Feature};
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::rotate::{RotateLeft, RotateRight};
+use utils::traits::truncate::Truncate;
+
+use typenum::consts::{U4, U6, U8};
+
+#[cfg(target_arch = "x86_64")]
+mod aesni;
+
+// The state in bitsliced form: bit i of every byte of the state is gathered into plane i. Within
+// each plane, byte j of the block (row j % 4, column j / 4, as in FIPS 197) is at bit 15 - j.
+type Planes = [Secret<u16>; 8];
+
+enum Schedule {
+    Portable(Vec<Planes>),
+    #[cfg(target_arch = "x86_64")]
+    AesNi(aesni::Schedule)
+}
+
+/// The AES block cipher, with a key of `KeyWords` 32-bit words, as in FIPS 197.
+///
+/// Blocks are big-endian, so the first byte of a block is its most significant byte, and key
+/// words are the words w[0], w[1], ... of the key expansion. The portable backend works on a
+/// bitsliced state: the S-box is the Boyar-Peralta circuit, evaluated on every byte at once, so
+/// there are no table lookups, even in the key schedule. The hardware backend uses AES-NI.
+pub struct Aes<KeyWords> {
+    schedule: Schedule,
+    _marker: PhantomData<KeyWords>
+}
+
+pub type Aes128 = Aes<U4>;
+pub type Aes192 = Aes<U6>;
+pub type Aes256 = Aes<U8>;
+
+impl<KeyWords: ArrayLength<Secret<u32>>> Aes<KeyWords> {
+    /// Expands `key` for `backend`, panicking if this CPU does not support it.
+    pub fn with_backend(key: Array<Secret<u32>, KeyWords>, backend: Backend) -> Aes<KeyWords> {
+        assert!(backend.is_supported(Feature::Aes), "AES-NI is not supported");
+        let round_keys = key_schedule(&key);
+        let schedule = match backend {
+            Backend::Portable => Schedule::Portable(round_keys.into_iter().map(to_planes).collect()),
+            Backend::Hardware => hardware_schedule(&round_keys)
+        };
+
+        Aes {
+            schedule: schedule,
+            _marker: PhantomData
+        }
+    }
+}
+
+impl<KeyWords> Aes<KeyWords> {
+    pub fn backend(&self) -> Backend {
+        match self.schedule {
+            Schedule::Portable(_) => Backend::Portable,
+            #[cfg(target_arch = "x86_64")]
+            Schedule::AesNi(_) => Backend::Hardware
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn hardware_schedule(round_keys: &[Secret<u128>]) -> Schedule {
+    Schedule::AesNi(unsafe { aesni::Schedule::new(round_keys) })
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn hardware_schedule(_round_keys: &[Secret<u128>]) -> Schedule {
+    unreachable!()
+}
+
+impl<KeyWords: ArrayLength<Secret<u32>>> Keyed for Aes<KeyWords> {
+    type Key = Array<Secret<u32>, KeyWords>;
+
+    /// Uses the hardware backend if it is available.
+    fn from_key(key: Array<Secret<u32>, KeyWords>) -> Aes<KeyWords> {
+        Aes::with_backend(key, Backend::detect(Feature::Aes))
+    }
+}
+
+impl<KeyWords> BlockFn for Aes<KeyWords> {
+    type Block = Secret<u128>;
+    fn encrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        match self.schedule {
+            Schedule::Portable(ref round_keys) => encrypt_portable(round_keys, block),
+            #[cfg(target_arch = "x86_64")]
+            Schedule::AesNi(ref schedule) => unsafe { schedule.encrypt(block) }
+        }
+    }
+}
+
+impl<KeyWords> BlockCipher for Aes<KeyWords> {
+    fn decrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        match self.schedule {
+            Schedule::Portable(ref round_keys) => decrypt_portable(round_keys, block),
+            #[cfg(target_arch = "x86_64")]
+            Schedule::AesNi(ref schedule) => unsafe { schedule.decrypt(block) }
+        }
+    }
+}
+
+fn encrypt_portable(round_keys: &[Planes], block: Secret<u128>) -> Secret<u128> {
+    let rounds = round_keys.len() - 1;
+    let mut state = to_planes(block);
+    add_round_key(&mut state, &round_keys[0]);
+    for round_key in round_keys[1..rounds].iter() {
+        sub_bytes(&mut state);
+        shift_rows(&mut state);
+        mix_columns(&mut state);
+        add_round_key(&mut state, round_key);
+    }
+    sub_bytes(&mut state);
+    shift_rows(&mut state);
+    add_round_key(&mut state, &round_keys[rounds]);
+    from_planes(state)
+}
+
+fn decrypt_portable(round_keys: &[Planes], block: Secret<u128>) -> Secret<u128> {
+    let rounds = round_keys.len() - 1;
+    let mut state = to_planes(block);
+    add_round_key(&mut state, &round_keys[rounds]);
+    for round_key in round_keys[1..rounds].iter().rev() {
+        inv_shift_rows(&mut state);
+        inv_sub_bytes(&mut state);
+        add_round_key(&mut state, round_key);
+        inv_mix_columns(&mut state);
+    }
+    inv_shift_rows(&mut state);
+    inv_sub_bytes(&mut state);
+    add_round_key(&mut state, &round_keys[0]);
+    from_planes(state)
+}
+
+// FIPS 197, Section 5.2. The round constants are public, so they are computed directly.
+fn key_schedule(key: &[Secret<u32>]) -> Vec<Secret<u128>> {
+    let key_words = key.len();
+    let rounds = key_words + 6;
+    let mut words = key.to_vec();
+    let mut round_constant = 1u32;
+    for i in key_words..4 * (rounds + 1) {
+        let mut temp = words[i - 1];
+        if i % key_words == 0 {
+            temp = sub_word(temp.rotate_left(8)) ^ (round_constant << 24);
+            round_constant <<= 1;
+            if round_constant & 0x100 != 0 {
+                round_constant ^= 0x11B;
+            }
+        } else if key_words > 6 && i % key_words == 4 {
+            temp = sub_word(temp);
+        }
+        let word = words[i - key_words] ^ temp;
+        words.push(word);
+    }
+
+    words.chunks(4).map(|chunk| {
+        chunk.iter().fold(Secret::new(0), |block, &word| (block << 32) | Secret::<u128>::from(word))
+    }).collect()
+}
+
+// Runs the S-box on the four bytes of `word` by putting them in an otherwise empty state.
+fn sub_word(word: Secret<u32>) -> Secret<u32> {
+    let mut state = to_planes(Secret::<u128>::from(word));
+    sub_bytes(&mut state);
+    from_planes(state).truncate()
+}
+
+#[inline(always)]
+fn delta_swap(word: Secret<u64>, mask: u64, shift: u32) -> Secret<u64> {
+    let temp = ((word >> shift) ^ word) & mask;
+    word ^ temp ^ (temp << shift)
+}
+
+// Transposes the 8x8 bit matrix whose rows are the bytes of `word`, so that bit i of byte j
+// moves to bit j of byte i. This is its own inverse. See Hacker's Delight, Section 7-3.
+fn transpose(word: Secret<u64>) -> Secret<u64> {
+    let word = delta_swap(word, 0x00AA00AA00AA00AA, 7);
+    let word = delta_swap(word, 0x0000CCCC0000CCCC, 14);
+    delta_swap(word, 0x00000000F0F0F0F0, 28)
+}
+
+// Each half of the block is 8 bytes, so transposing it leaves bit i of its bytes in byte i,
+// with the first byte in the most significant bit.
+fn to_planes(block: Secret<u128>) -> Planes {
+    let high = transpose((block >> 64).truncate());
+    let low = transpose(block.truncate());
+    let mut planes = [Secret::new(0); 8];
+    for (i, plane) in planes.iter_mut().enumerate() {
+        let high_byte: Secret<u16> = ((high >> (8 * i as u32)) & 0xFF).truncate();
+        let low_byte: Secret<u16> = ((low >> (8 * i as u32)) & 0xFF).truncate();
+        *plane = (high_byte << 8) | low_byte;
+    }
+    planes
+}
+
+fn from_planes(planes: Planes) -> Secret<u128> {
+    let mut high = Secret::new(0);
+    let mut low = Secret::new(0);
+    for (i, &plane) in planes.iter().enumerate() {
+        high |= Secret::<u64>::from(plane >> 8) << (8 * i as u32);
+        low |= Secret::<u64>::from(plane & 0xFF) << (8 * i as u32);
+    }
+    (Secret::<u128>::from(transpose(high)) << 64) | Secret::<u128>::from(transpose(low))
+}
+
+fn add_round_key(state: &mut Planes, round_key: &Planes) {
+    for (plane, &key) in state.iter_mut().zip(round_key.iter()) {
+        *plane ^= key;
+    }
+}
+
+// The AES S-box as a circuit of 113 gates, from "A depth-16 circuit for the AES S-box" by Boyar
+// and Peralta (2011). Inputs and outputs are numbered from the most significant bit.
+fn sub_bytes(state: &mut Planes) {
+    let x0 = state[7];
+    let x1 = state[6];
+    let x2 = state[5];
+    let x3 = state[4];
+    let x4 = state[3];
+    let x5 = state[2];
+    let x6 = state[1];
+    let x7 = state[0];
+
+    // Top linear transformation
+    let y14 = x3 ^ x5;
+    let y13 = x0 ^ x6;
+    let y9 = x0 ^ x3;
+    let y8 = x0 ^ x5;
+    let t0 = x1 ^ x2;
+    let y1 = t0 ^ x7;
+    let y4 = y1 ^ x3;
+    let y12 = y13 ^ y14;
+    let y2 = y1 ^ x0;
+    let y5 = y1 ^ x6;
+    let y3 = y5 ^ y8;
+    let t1 = x4 ^ y12;
+    let y15 = t1 ^ x5;
+    let y20 = t1 ^ x1;
+    let y6 = y15 ^ x7;
+    let y10 = y15 ^ t0;
+    let y11 = y20 ^ y9;
+    let y7 = x7 ^ y11;
+    let y17 = y10 ^ y11;
+    let y19 = y10 ^ y8;
+    let y16 = t0 ^ y11;
+    let y21 = y13 ^ y16;
+    let y18 = x0 ^ y16;
+
+    // Nonlinear middle: inversion in GF(2^4)^2
+    let t2 = y12 & y15;
+    let t3 = y3 & y6;
+    let t4 = t3 ^ t2;
+    let t5 = y4 & x7;
+    let t6 = t5 ^ t2;
+    let t7 = y13 & y16;
+    let t8 = y5 & y1;
+    let t9 = t8 ^ t7;
+    let t10 = y2 & y7;
+    let t11 = t10 ^ t7;
+    let t12 = y9 & y11;
+    let t13 = y14 & y17;
+    let t14 = t13 ^ t12;
+    let t15 = y8 & y10;
+    let t16 = t15 ^ t12;
+    let t17 = t4 ^ t14;
+    let t18 = t6 ^ t16;
+    let t19 = t9 ^ t14;
+    let t20 = t11 ^ t16;
+    let t21 = t17 ^ y20;
+    let t22 = t18 ^ y19;
+    let t23 = t19 ^ y21;
+    let t24 = t20 ^ y18;
+
+    let t25 = t21 ^ t22;
+    let t26 = t21 & t23;
+    let t27 = t24 ^ t26;
+    let t28 = t25 & t27;
+    let t29 = t28 ^ t22;
+    let t30 = t23 ^ t24;
+    let t31 = t22 ^ t26;
+    let t32 = t31 & t30;
+    let t33 = t32 ^ t24;
+    let t34 = t23 ^ t33;
+    let t35 = t27 ^ t33;
+    let t36 = t24 & t35;
+    let t37 = t36 ^ t34;
+    let t38 = t27 ^ t36;
+    let t39 = t29 & t38;
+    let t40 = t25 ^ t39;
+
+    let t41 = t40 ^ t37;
+    let t42 = t29 ^ t33;
+    let t43 = t29 ^ t40;
+    let t44 = t33 ^ t37;
+    let t45 = t42 ^ t41;
+    let z0 = t44 & y15;
+    let z1 = t37 & y6;
+    let z2 = t33 & x7;
+    let z3 = t43 & y16;
+    let z4 = t40 & y1;
+    let z5 = t29 & y7;
+    let z6 = t42 & y11;
+    let z7 = t45 & y17;
+    let z8 = t41 & y10;
+    let z9 = t44 & y12;
+    let z10 = t37 & y3;
+    let z11 = t33 & y4;
+    let z12 = t43 & y13;
+    let z13 = t40 & y5;
+    let z14 = t29 & y2;
+    let z15 = t42 & y9;
+    let z16 = t45 & y14;
+    let z17 = t41 & y8;
+
+    // Bottom linear transformation
+    let t46 = z15 ^ z16;
+    let t47 = z10 ^ z11;
+    let t48 = z5 ^ z13;
+    let t49 = z9 ^ z10;
+    let t50 = z2 ^ z12;
+    let t51 = z2 ^ z5;
+    let t52 = z7 ^ z8;
+    let t53 = z0 ^ z3;
+    let t54 = z6 ^ z7;
+    let t55 = z16 ^ z17;
+    let t56 = z12 ^ t48;
+    let t57 = t50 ^ t53;
+    let t58 = z4 ^ t46;
+    let t59 = z3 ^ t54;
+    let t60 = t46 ^ t57;
+    let t61 = z14 ^ t57;
+    let t62 = t52 ^ t58;
+    let t63 = t49 ^ t58;
+    let t64 = z4 ^ t59;
+    let t65 = t61 ^ t62;
+    let t66 = z1 ^ t63;
+    let s0 = t59 ^ t63;
+    let s6 = t56 ^ !t62;
+    let s7 = t48 ^ !t60;
+    let t67 = t64 ^ t65;
+    let s3 = t53 ^ t66;
+    let s4 = t51 ^ t66;
+    let s5 = t47 ^ t65;
+    let s1 = t64 ^ !s3;
+    let s2 = t55 ^ !t67;
+
+    *state = [s7, s6, s5, s4, s3, s2, s1, s0];
+}
+
+// The inverse of the affine map in the S-box, along with its constant. Since the S-box is
+// S(x) = A(x^-1) ^ 0x63, its inverse is this map, then the S-box, then this map again.
+fn inv_affine(state: &mut Planes) {
+    let q = *state;
+    for (i, plane) in state.iter_mut().enumerate() {
+        *plane = q[(i + 7) % 8] ^ q[(i + 5) % 8] ^ q[(i + 2) % 8];
+    }
+    state[0] = !state[0];
+    state[2] = !state[2];
+}
+
+fn inv_sub_bytes(state: &mut Planes) {
+    inv_affine(state);
+    sub_bytes(state);
+    inv_affine(state);
+}
+
+// Row r of a plane is the bits 0x8888 >> r, so rotating it by four bits moves it by one column.
+fn shift_rows(state: &mut Planes) {
+    for plane in state.iter_mut() {
+        let x = *plane;
+        *plane = (x & 0x8888) | (x & 0x4444).rotate_left(4) | (x & 0x2222).rotate_left(8) | (x & 0x1111).rotate_left(12);
+    }
+}
+
+fn inv_shift_rows(state: &mut Planes) {
+    for plane in state.iter_mut() {
+        let x = *plane;
+        *plane = (x & 0x8888) | (x & 0x4444).rotate_right(4) | (x & 0x2222).rotate_right(8) | (x & 0x1111).rotate_right(12);
+    }
+}
+
+// Moves every row up by one or two, within each column.
+fn rotate_rows_1(x: Secret<u16>) -> Secret<u16> {
+    ((x << 1) & 0xEEEE) | ((x >> 3) & 0x1111)
+}
+
+fn rotate_rows_2(x: Secret<u16>) -> Secret<u16> {
+    ((x << 2) & 0xCCCC) | ((x >> 2) & 0x3333)
+}
+
+// Multiplication by x in GF(2^8), modulo x^8 + x^4 + x^3 + x + 1
+fn xtime(x: Planes) -> Planes {
+    [x[7], x[0] ^ x[7], x[1], x[2] ^ x[7], x[3] ^ x[7], x[4], x[5], x[6]]
+}
+
+// Each byte becomes 2 * a0 + 3 * a1 + a2 + a3 = 2 * (a0 + a1) + a1 + (a2 + a3), where a1, a2
+// and a3 are the following rows of the column.
+fn mix_columns(state: &mut Planes) {
+    let mut sums = [Secret::new(0); 8];
+    for (sum, &plane) in sums.iter_mut().zip(state.iter()) {
+        *sum = plane ^ rotate_rows_1(plane);
+    }
+    let doubled = xtime(sums);
+    for ((plane, &double), &sum) in state.iter_mut().zip(doubled.iter()).zip(sums.iter()) {
+        *plane = double ^ rotate_rows_1(*plane) ^ rotate_rows_2(sum);
+    }
+}
+
+// InvMixColumns is MixColumns after adding 4 * (a0 + a2) to each byte. See "The Design of
+// Rijndael", Section 4.1.3.
+fn inv_mix_columns(state: &mut Planes) {
+    let mut sums = [Secret::new(0); 8];
+    for (sum, &plane) in sums.iter_mut().zip(state.iter()) {
+        *sum = plane ^ rotate_rows_2(plane);
+    }
+    let quadrupled = xtime(xtime(sums));
+    for (plane, &term) in state.iter_mut().zip(quadrupled.iter()) {
+        *plane ^= term;
+    }
+    mix_columns(state);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    extern crate rand;
+
+    use super::{Aes, Aes128, Aes192, Aes256, key_schedule, to_planes, from_planes, sub_bytes, inv_sub_bytes};
+
+    use array::{Array, ArrayLength};
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::backend::{Backend, Feature, backends};
+    use secret::Secret;
+
+    use typenum::consts::{U4, U6, U8};
+
+    use self::test::Bencher;
+    use self::rand::{Rng, thread_rng};
+
+    fn parse_block(hex: &str) -> u128 {
+        u128::from_str_radix(hex, 16).unwrap()
+    }
+
+    // The backends that this CPU can run
+    fn parse_key<N: ArrayLength<Secret<u32>>>(hex: &str) -> Array<Secret<u32>, N> {
+        Array::from_fn(|i| Secret::new(u32::from_str_radix(&hex[8 * i..8 * i + 8], 16).unwrap()))
+    }
+
+    // The S-box computed from its definition: inversion in GF(2^8), then the affine map
+    fn reference_sbox(x: u8) -> u8 {
+        fn multiply(a: u8, b: u8) -> u8 {
+            let (mut a, mut b, mut product) = (a, b, 0);
+            while b != 0 {
+                if b & 1 != 0 {
+                    product ^= a;
+                }
+                a = (a << 1) ^ if a & 0x80 != 0 { 0x1B } else { 0 };
+                b >>= 1;
+            }
+            product
+        }
+        let inverse = (1..256).map(|y| y as u8).find(|&y| multiply(x, y) == 1).unwrap_or(0);
+        inverse ^ inverse.rotate_left(1) ^ inverse.rotate_left(2) ^ inverse.rotate_left(3) ^ inverse.rotate_left(4) ^ 0x63
+    }
+
+    #[test]
+    fn planes_roundtrip() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let block = ((rng.gen::<u64>() as u128) << 64) | rng.gen::<u64>() as u128;
+            let planes = to_planes(Secret::new(block));
+            for j in 0..16 {
+                let byte = (block >> (8 * (15 - j))) as u8;
+                for (i, plane) in planes.iter().enumerate() {
+                    assert_eq!(((plane.expose() >> (15 - j)) & 1) as u8, (byte >> i) & 1);
+                }
+            }
+            assert_eq!(from_planes(planes).expose(), block);
+        }
+    }
+
+    #[test]
+    fn sbox_matches_definition() {
+        for x in 0..256 {
+            // Put x in one byte and its complement in another to check the bytes stay apart.
+            let block = ((x as u128) << 40) | ((!x as u8 as u128) << 104);
+            let mut state = to_planes(Secret::new(block));
+            sub_bytes(&mut state);
+            let output = from_planes(state).expose();
+            assert_eq!((output >> 40) as u8, reference_sbox(x as u8));
+            assert_eq!((output >> 104) as u8, reference_sbox(!x as u8));
+
+            inv_sub_bytes(&mut state);
+            assert_eq!(from_planes(state).expose(), block);
+        }
+    }
+
+    #[test]
+    fn fips197_key_expansion() {
+        // FIPS 197, Appendix A: the last four words of each expansion
+        let schedule = key_schedule(&parse_key::<U4>("2b7e151628aed2a6abf7158809cf4f3c"));
+        assert_eq!(schedule[10].expose(), parse_block("d014f9a8c9ee2589e13f0cc8b6630ca6"));
+        let schedule = key_schedule(&parse_key::<U6>("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b"));
+        assert_eq!(schedule[12].expose(), parse_block("e98ba06f448c773c8ecc720401002202"));
+        let schedule = key_schedule(&parse_key::<U8>("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4"));
+        assert_eq!(schedule[14].expose(), parse_block("fe4890d1e6188d0b046df344706c631e"));
+    }
+
+    fn check<C: BlockCipher<Block=Secret<u128>>>(cipher: &C, plaintext: &str, ciphertext: &str) {
+        assert_eq!(cipher.encrypt(Secret::new(parse_block(plaintext))).expose(), parse_block(ciphertext));
+        assert_eq!(cipher.decrypt(Secret::new(parse_block(ciphertext))).expose(), parse_block(plaintext));
+    }
+
+    #[test]
+    fn fips197_examples() {
+        for &backend in backends(Feature::Aes).iter() {
+            // Appendix B
+            check(&Aes128::with_backend(parse_key("2b7e151628aed2a6abf7158809cf4f3c"), backend),
+                  "3243f6a8885a308d313198a2e0370734", "3925841d02dc09fbdc118597196a0b32");
+            // Appendix C
+            let plaintext = "00112233445566778899aabbccddeeff";
+            check(&Aes128::with_backend(parse_key("000102030405060708090a0b0c0d0e0f"), backend),
+                  plaintext, "69c4e0d86a7b0430d8cdb78070b4c55a");
+            check(&Aes192::with_backend(parse_key("000102030405060708090a0b0c0d0e0f1011121314151617"), backend),
+                  plaintext, "dda97ca4864cdfe06eaf70a0ec0d7191");
+            check(&Aes256::with_backend(parse_key("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"), backend),
+                  plaintext, "8ea2b7ca516745bfeafc49904b496089");
+        }
+    }
+
+    // The first entry of each of the AESAVS known answer tests (Appendices B to E): GFSbox,
+    // KeySbox, VarTxt and VarKey, as (key, plaintext, ciphertext).
+    const AESAVS: [(&'static str, &'static str, &'static str); 12] = [
+        ("00000000000000000000000000000000", "f34481ec3cc627bacd5dc3fb08f273e6", "0336763e966d92595a567cc9ce537f5e"),
+        ("10a58869d74be5a374cf867cfb473859", "00000000000000000000000000000000", "6d251e6944b051e04eaa6fb4dbf78465"),
+        ("00000000000000000000000000000000", "80000000000000000000000000000000", "3ad78e726c1ec02b7ebfe92b23d9ec34"),
+        ("80000000000000000000000000000000", "00000000000000000000000000000000", "0edd33d3c621e546455bd8ba1418bec8"),
+        ("000000000000000000000000000000000000000000000000", "1b077a6af4b7f98229de786d7516b639", "275cfc0413d8ccb70513c3859b1d0f72"),
+        ("e9f065d7c13573587f7875357dfbb16c53489f6a4bd0f7cd", "00000000000000000000000000000000", "0956259c9cd5cfd0181cca53380cde06"),
+        ("000000000000000000000000000000000000000000000000", "80000000000000000000000000000000", "6cd02513e8d4dc986b4afe087a60bd0c"),
+        ("800000000000000000000000000000000000000000000000", "00000000000000000000000000000000", "de885dc87f5a92594082d02cc1e1b42c"),
+        ("0000000000000000000000000000000000000000000000000000000000000000", "014730f80ac625fe84f026c60bfd547d", "5c9d844ed46f9885085e5d6a4f94c7d7"),
+        ("c47b0294dbbbee0fec4757f22ffeee3587ca4730c3d33b691df38bab076bc558", "00000000000000000000000000000000", "46f2fb342d6f0ab477476fc501242c5f"),
+        ("0000000000000000000000000000000000000000000000000000000000000000", "80000000000000000000000000000000", "ddc6bf790c15760d8d9aeb6f9a75fd4e"),
+        ("8000000000000000000000000000000000000000000000000000000000000000", "00000000000000000000000000000000", "e35a6dcb19b201a01ebcfa8aa22b5759")
+    ];
+
+    #[test]
+    fn aesavs() {
+        for &backend in backends(Feature::Aes).iter() {
+            for &(key, plaintext, ciphertext) in AESAVS.iter() {
+                match key.len() {
+                    32 => check(&Aes128::with_backend(parse_key(key), backend), plaintext, ciphertext),
+                    48 => check(&Aes192::with_backend(parse_key(key), backend), plaintext, ciphertext),
+                    _ => check(&Aes256::with_backend(parse_key(key), backend), plaintext, ciphertext)
+                }
+            }
+        }
+    }
+
+    fn check_backends_agree<N: ArrayLength<Secret<u32>>>() {
+        if !Backend::Hardware.is_supported(Feature::Aes) {
+            return;
+        }
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let words: Vec<u32> = (0..N::to_usize()).map(|_| rng.gen()).collect();
+            let portable = Aes::<N>::with_backend(Array::from_fn(|i| Secret::new(words[i])), Backend::Portable);
+            let hardware = Aes::<N>::with_backend(Array::from_fn(|i| Secret::new(words[i])), Backend::Hardware);
+            assert_eq!(hardware.backend(), Backend::Hardware);
+            for _ in 0..10 {
+                let block = Secret::new(((rng.gen::<u64>() as u128) << 64) | rng.gen::<u64>() as u128);
+                assert_eq!(portable.encrypt(block).expose(), hardware.encrypt(block).expose());
+                assert_eq!(portable.decrypt(block).expose(), hardware.decrypt(block).expose());
+            }
+        }
+    }
+
+    #[test]
+    fn backends_agree() {
+        check_backends_agree::<U4>();
+        check_backends_agree::<U6>();
+        check_backends_agree::<U8>();
+    }
+
+    fn bench_backend(bencher: &mut Bencher, backend: Backend, decrypt: bool) {
+        if !backend.is_supported(Feature::Aes) {
+            return;
+        }
+        let aes = Aes128::with_backend(parse_key("000102030405060708090a0b0c0d0e0f"), backend);
+        let mut block = Secret::new(0);
+        bencher.bytes = 16;
+        bencher.iter(|| {
+            block = if decrypt { aes.decrypt(block) } else { aes.encrypt(block) };
+        });
+    }
+
+    #[bench]
+    fn bench_encrypt_portable(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Portable, false);
+    }
+
+    #[bench]
+    fn bench_decrypt_portable(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Portable, true);
+    }
+
+    #[bench]
+    fn bench_encrypt_hardware(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Hardware, false);
+    }
+
+    #[bench]
+    fn bench_decrypt_hardware(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Hardware, true);
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FORCE_PORTABLE: AtomicBool = AtomicBool::new(false);
+
+/// A group of special-purpose instructions that some algorithm can run on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// AES-NI, for AES
+    Aes,
+    /// Carry-less multiplication, for GHASH
+    Pclmulqdq,
+    /// The SHA extensions, for SHA-256
+    Sha
+}
+
+/// The implementation that an algorithm runs on.
+///
+/// Both backends run in constant time and give identical results. The hardware backend is just
+/// much faster, when the CPU has it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Plain code written with `Secret`, which runs anywhere
+    Portable,
+    /// The instructions for the algorithm's `Feature`
+    Hardware
+}
+
+impl Backend {
+    /// The hardware backend if the CPU supports `feature` and the portable path has not been
+    /// forced, and the portable backend otherwise.
+    pub fn detect(feature: Feature) -> Backend {
+        if !FORCE_PORTABLE.load(Ordering::Relaxed) && Backend::Hardware.is_supported(feature) {
+            Backend::Hardware
+        } else {
+            Backend::Portable
+        }
+    }
+
+    /// Whether this backend can run `feature`'s algorithm on this CPU
+    pub fn is_supported(self, feature: Feature) -> bool {
+        match self {
+            Backend::Portable => true,
+            Backend::Hardware => hardware_supports(feature)
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn hardware_supports(feature: Feature) -> bool {
+    match feature {
+        Feature::Aes => is_x86_feature_detected!("aes") && is_x86_feature_detected!("sse2"),
+        Feature::Pclmulqdq => is_x86_feature_detected!("pclmulqdq") && is_x86_feature_detected!("sse2"),
+        Feature::Sha => is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse2")
+            && is_x86_feature_detected!("ssse3") && is_x86_feature_detected!("sse4.1")
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn hardware_supports(_feature: Feature) -> bool {
+    false
+}
+
+/// Makes `Backend::detect` choose the portable backend from now on, or stop doing so. This is
+/// for testing and benchmarking the portable code on machines with hardware support; it does
+/// not affect objects that already exist.
+pub fn force_portable(force: bool) {
+    FORCE_PORTABLE.store(force, Ordering::Relaxed);
+}
+
+/// The backends that can run `feature` on this machine, for tests that check every one of them
+#[cfg(test)]
+pub fn backends(feature: Feature) -> Vec<Backend> {
+    [Backend::Portable, Backend::Hardware].iter().cloned().filter(|backend| backend.is_supported(feature)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Feature, force_portable};
+
+    use aead::ghash::Ghash;
+    use array::Array;
+    use cipher::block::BlockFn;
+    use cipher::block::aes::Aes128;
+    use hash::{FixedOutput, Update};
+    use hash::sha2::Sha256;
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+
+    #[test]
+    fn portable_always_supported() {
+        for &feature in [Feature::Aes, Feature::Pclmulqdq, Feature::Sha].iter() {
+            assert!(Backend::Portable.is_supported(feature));
+            let detected = Backend::detect(feature);
+            assert!(detected.is_supported(feature));
+        }
+    }
+
+    #[test]
+    fn forced_portable() {
+        // Other tests may run while the flag is set. They still pass, since both backends give the
+        // same results.
+        let features = [Feature::Aes, Feature::Pclmulqdq, Feature::Sha];
+        force_portable(true);
+        let detected: Vec<Backend> = features.iter().map(|&feature| Backend::detect(feature)).collect();
+        let key = [0x00010203, 0x04050607, 0x08090A0B, 0x0C0D0E0F];
+        let aes = Aes128::from_key(Array::from_fn(|i| Secret::new(key[i])));
+        let mut ghash = Ghash::new(Secret::new(0x66E94BD4EF8A2C3B884CFA59CA342B2E));
+        let mut sha256 = Sha256::new();
+        force_portable(false);
+
+        assert_eq!(detected, vec![Backend::Portable; 3]);
+        assert_eq!((aes.backend(), ghash.backend(), sha256.backend()), (Backend::Portable, Backend::Portable, Backend::Portable));
+
+        // FIPS 197, Appendix C.1
+        assert_eq!(aes.encrypt(Secret::new(0x00112233445566778899AABBCCDDEEFF)).expose(), 0x69C4E0D86A7B0430D8CDB78070B4C55A);
+
+        // The GHASH of test case 2 of McGrew and Viega's GCM paper
+        ghash.update_block(Secret::new(0x0388DACE60B6A392F328C2B971B2FE78));
+        ghash.update_block(Secret::new(0x80));
+        assert_eq!(ghash.finalize().expose(), 0xF38CBB1AD69223DCC3457AE5B6B0F885);
+
+        // FIPS 180-2, Appendix B.1
+        sha256.update(b"abc");
+        let mut digest = vec![0; 32];
+        sha256.finalize(&mut digest);
+        assert_eq!(digest, hex::decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+    }
+}
@@ -0,0 +1,342 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::Arc;
+
+use array::Array;
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::feistel::Feistel;
+use cipher::block::sbox::TableAccess;
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::truncate::Truncate;
+use utils::traits::wrapping::WrappingAdd;
+
+use typenum::consts::U16;
+
+mod tables;
+
+type SBoxes = [[Secret<u32>; 256]; 4];
+
+fn round_function<Access: TableAccess>(sboxes: &SBoxes, x: Secret<u32>) -> Secret<u32> {
+    let a = Access::lookup(&sboxes[0], (x >> 24).truncate());
+    let b = Access::lookup(&sboxes[1], (x >> 16).truncate());
+    let c = Access::lookup(&sboxes[2], (x >> 8).truncate());
+    let d = Access::lookup(&sboxes[3], x.truncate());
+    (a.wrapping_add(b) ^ c).wrapping_add(d)
+}
+
+// The P-array and S-boxes while the key schedule is running. The schedule encrypts with the
+// state it is still modifying, so this does not use `Feistel`.
+struct State {
+    p: [Secret<u32>; 18],
+    s: SBoxes
+}
+
+impl State {
+    fn initial() -> State {
+        let mut state = State {
+            p: [Secret::new(0); 18],
+            s: [[Secret::new(0); 256]; 4]
+        };
+        for (word, &initial) in state.p.iter_mut().zip(tables::P.iter()) {
+            *word = Secret::new(initial);
+        }
+        for (sbox, initial) in state.s.iter_mut().zip(tables::S.iter()) {
+            for (word, &initial) in sbox.iter_mut().zip(initial.iter()) {
+                *word = Secret::new(initial);
+            }
+        }
+        state
+    }
+
+    fn encrypt<Access: TableAccess>(&self, mut left: Secret<u32>, mut right: Secret<u32>) -> (Secret<u32>, Secret<u32>) {
+        for &key in self.p[..16].iter() {
+            left ^= key;
+            right ^= round_function::<Access>(&self.s, left);
+            mem::swap(&mut left, &mut right);
+        }
+        (right ^ self.p[17], left ^ self.p[16])
+    }
+
+    // XORs the key, repeated as needed, into the P-array, then replaces the P-array and the
+    // S-boxes with repeated encryptions of a block that starts at zero. Before each encryption,
+    // the next two words of `salt`, also repeated as needed, are XORed into the block. Plain
+    // Blowfish has no salt, which is the same as a salt of zeros.
+    fn expand_key<Access: TableAccess>(&mut self, key: &[Secret<u8>], salt: &[Secret<u32>; 4]) {
+        let mut bytes = key.iter().cycle();
+        for word in self.p.iter_mut() {
+            let mut key_word = Secret::new(0);
+            for _ in 0..4 {
+                key_word = (key_word << 8) | Secret::<u32>::from(*bytes.next().unwrap());
+            }
+            *word ^= key_word;
+        }
+
+        let mut salt = salt.iter().cycle();
+        let (mut left, mut right) = (Secret::new(0), Secret::new(0));
+        for i in 0..(18 + 4 * 256) / 2 {
+            left ^= *salt.next().unwrap();
+            right ^= *salt.next().unwrap();
+            let (new_left, new_right) = self.encrypt::<Access>(left, right);
+            left = new_left;
+            right = new_right;
+            if i < 9 {
+                self.p[2 * i] = left;
+                self.p[2 * i + 1] = right;
+            } else {
+                let sbox = &mut self.s[(i - 9) / 128];
+                sbox[2 * ((i - 9) % 128)] = left;
+                sbox[2 * ((i - 9) % 128) + 1] = right;
+            }
+        }
+    }
+}
+
+struct BlowfishRound<Access> {
+    sboxes: Arc<SBoxes>,
+    key: Secret<u32>,
+    _marker: PhantomData<Access>
+}
+
+impl<Access: TableAccess> BlockFn for BlowfishRound<Access> {
+    type Block = Secret<u32>;
+    fn encrypt(&self, block: Secret<u32>) -> Secret<u32> {
+        round_function::<Access>(&self.sboxes, block) ^ self.key
+    }
+}
+
+impl<Access> Keyed for BlowfishRound<Access> {
+    type Key = (Arc<SBoxes>, Secret<u32>);
+    fn from_key(key: (Arc<SBoxes>, Secret<u32>)) -> BlowfishRound<Access> {
+        BlowfishRound {
+            sboxes: key.0,
+            key: key.1,
+            _marker: PhantomData
+        }
+    }
+}
+
+/// The Blowfish block cipher, reading its S-boxes as chosen by `Access`, with a key of 4 to 56
+/// bytes.
+///
+/// Blowfish XORs P[i] into the left half before each round. Keeping the left half XORed with the
+/// next P entry instead makes it an ordinary Feistel network whose round i computes
+/// F(x) ^ P[i + 1], with P[0] and P[17] applied outside.
+pub struct Blowfish<Access> {
+    first: Secret<u32>,
+    last: Secret<u32>,
+    inner: Feistel<BlowfishRound<Access>, U16>
+}
+
+impl<Access: TableAccess> BlockFn for Blowfish<Access> {
+    type Block = Secret<u64>;
+    fn encrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let (left, right) = split_block(block);
+        let (left, right) = self.inner.encrypt((right, left ^ self.first));
+        join_block((right ^ self.last, left))
+    }
+}
+
+impl<Access: TableAccess> BlockCipher for Blowfish<Access> {
+    fn decrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let (left, right) = split_block(block);
+        let (left, right) = self.inner.decrypt((right, left ^ self.last));
+        join_block((right ^ self.first, left))
+    }
+}
+
+impl<Access: TableAccess> Blowfish<Access> {
+    /// EksBlowfishSetup, the expensive key schedule of bcrypt, from Provos and Mazières, "A
+    /// Future-Adaptable Password Scheme". The salted schedule is followed by 2^`cost` rounds of
+    /// alternately expanding the key and the salt. `key` must be 1 to 72 bytes long.
+    pub fn eks_setup(cost: u32, salt: &[u8; 16], key: &[Secret<u8>]) -> Blowfish<Access> {
+        assert!((1..=72).contains(&key.len()), "EksBlowfish keys are 1 to 72 bytes long");
+        assert!(cost < 32, "the cost must be less than 32");
+
+        let mut salt_words = [Secret::new(0); 4];
+        for (word, chunk) in salt_words.iter_mut().zip(salt.chunks(4)) {
+            *word = Secret::new(u32::from(chunk[0]) << 24 | u32::from(chunk[1]) << 16
+                | u32::from(chunk[2]) << 8 | u32::from(chunk[3]));
+        }
+        let salt_bytes: Vec<Secret<u8>> = salt.iter().map(|&byte| Secret::new(byte)).collect();
+        let unsalted = [Secret::new(0); 4];
+
+        let mut state = State::initial();
+        state.expand_key::<Access>(key, &salt_words);
+        for _ in 0..1u64 << cost {
+            state.expand_key::<Access>(key, &unsalted);
+            state.expand_key::<Access>(&salt_bytes, &unsalted);
+        }
+        Blowfish::from_state(state)
+    }
+
+    fn from_state(state: State) -> Blowfish<Access> {
+        let p = state.p;
+        let sboxes = Arc::new(state.s);
+        Blowfish {
+            first: p[0],
+            last: p[17],
+            inner: Keyed::from_key(Array::from_fn(|i| (sboxes.clone(), p[i + 1])))
+        }
+    }
+}
+
+impl<Access: TableAccess> Keyed for Blowfish<Access> {
+    type Key = Vec<Secret<u8>>;
+    fn from_key(key: Vec<Secret<u8>>) -> Blowfish<Access> {
+        assert!(key.len() >= 4 && key.len() <= 56, "Blowfish keys are 32 to 448 bits long");
+
+        let mut state = State::initial();
+        state.expand_key::<Access>(&key, &[Secret::new(0); 4]);
+        Blowfish::from_state(state)
+    }
+}
+
+fn split_block(block: Secret<u64>) -> (Secret<u32>, Secret<u32>) {
+    ((block >> 32).truncate(), block.truncate())
+}
+
+fn join_block(parts: (Secret<u32>, Secret<u32>)) -> Secret<u64> {
+    Secret::<u64>::from(parts.0) << 32 | Secret::<u64>::from(parts.1)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::Blowfish;
+
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::sbox::{Indexed, Masked, TableAccess};
+    use keyed::Keyed;
+    use secret::Secret;
+
+    use self::test::Bencher;
+
+    fn key_bytes(key: &[u8]) -> Vec<Secret<u8>> {
+        key.iter().map(|&byte| Secret::new(byte)).collect()
+    }
+
+    fn key_u64(key: u64) -> Vec<Secret<u8>> {
+        (0..8).map(|i| Secret::new((key >> (56 - 8 * i)) as u8)).collect()
+    }
+
+    // Eric Young's test vectors, as (key, plaintext, ciphertext)
+    const EAY_VECTORS: [(u64, u64, u64); 34] = [
+        (0x0000000000000000, 0x0000000000000000, 0x4EF997456198DD78),
+        (0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF, 0x51866FD5B85ECB8A),
+        (0x3000000000000000, 0x1000000000000001, 0x7D856F9A613063F2),
+        (0x1111111111111111, 0x1111111111111111, 0x2466DD878B963C9D),
+        (0x0123456789ABCDEF, 0x1111111111111111, 0x61F9C3802281B096),
+        (0x1111111111111111, 0x0123456789ABCDEF, 0x7D0CC630AFDA1EC7),
+        (0x0000000000000000, 0x0000000000000000, 0x4EF997456198DD78),
+        (0xFEDCBA9876543210, 0x0123456789ABCDEF, 0x0ACEAB0FC6A0A28D),
+        (0x7CA110454A1A6E57, 0x01A1D6D039776742, 0x59C68245EB05282B),
+        (0x0131D9619DC1376E, 0x5CD54CA83DEF57DA, 0xB1B8CC0B250F09A0),
+        (0x07A1133E4A0B2686, 0x0248D43806F67172, 0x1730E5778BEA1DA4),
+        (0x3849674C2602319E, 0x51454B582DDF440A, 0xA25E7856CF2651EB),
+        (0x04B915BA43FEB5B6, 0x42FD443059577FA2, 0x353882B109CE8F1A),
+        (0x0113B970FD34F2CE, 0x059B5E0851CF143A, 0x48F4D0884C379918),
+        (0x0170F175468FB5E6, 0x0756D8E0774761D2, 0x432193B78951FC98),
+        (0x43297FAD38E373FE, 0x762514B829BF486A, 0x13F04154D69D1AE5),
+        (0x07A7137045DA2A16, 0x3BDD119049372802, 0x2EEDDA93FFD39C79),
+        (0x04689104C2FD3B2F, 0x26955F6835AF609A, 0xD887E0393C2DA6E3),
+        (0x37D06BB516CB7546, 0x164D5E404F275232, 0x5F99D04F5B163969),
+        (0x1F08260D1AC2465E, 0x6B056E18759F5CCA, 0x4A057A3B24D3977B),
+        (0x584023641ABA6176, 0x004BD6EF09176062, 0x452031C1E4FADA8E),
+        (0x025816164629B007, 0x480D39006EE762F2, 0x7555AE39F59B87BD),
+        (0x49793EBC79B3258F, 0x437540C8698F3CFA, 0x53C55F9CB49FC019),
+        (0x4FB05E1515AB73A7, 0x072D43A077075292, 0x7A8E7BFA937E89A3),
+        (0x49E95D6D4CA229BF, 0x02FE55778117F12A, 0xCF9C5D7A4986ADB5),
+        (0x018310DC409B26D6, 0x1D9D5C5018F728C2, 0xD1ABB290658BC778),
+        (0x1C587F1C13924FEF, 0x305532286D6F295A, 0x55CB3774D13EF201),
+        (0x0101010101010101, 0x0123456789ABCDEF, 0xFA34EC4847B268B2),
+        (0x1F1F1F1F0E0E0E0E, 0x0123456789ABCDEF, 0xA790795108EA3CAE),
+        (0xE0FEE0FEF1FEF1FE, 0x0123456789ABCDEF, 0xC39E072D9FAC631D),
+        (0x0000000000000000, 0xFFFFFFFFFFFFFFFF, 0x014933E0CDAFF6E4),
+        (0xFFFFFFFFFFFFFFFF, 0x0000000000000000, 0xF21E9A77B71C49BC),
+        (0x0123456789ABCDEF, 0x0000000000000000, 0x245946885754369A),
+        (0xFEDCBA9876543210, 0xFFFFFFFFFFFFFFFF, 0x6B5C5A9C5D9E0A5A)
+    ];
+
+    // Eric Young's variable key length vectors: prefixes of one key, from 4 bytes up, encrypting
+    // 0xFEDCBA9876543210
+    const SET_KEY: [u8; 24] = [
+        0xF0, 0xE1, 0xD2, 0xC3, 0xB4, 0xA5, 0x96, 0x87, 0x78, 0x69, 0x5A, 0x4B,
+        0x3C, 0x2D, 0x1E, 0x0F, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77
+    ];
+    const SET_KEY_CIPHERTEXTS: [u64; 21] = [
+        0xBE1E639408640F05, 0xB39E44481BDB1E6E, 0x9457AA83B1928C0D, 0x8BB77032F960629D,
+        0xE87A244E2CC85E82, 0x15750E7A4F4EC577, 0x122BA70B3AB64AE0, 0x3A833C9AFFC537F6,
+        0x9409DA87A90F6BF2, 0x884F80625060B8B4, 0x1F85031C19E11968, 0x79D9373A714CA34F,
+        0x93142887EE3BE15C, 0x03429E838CE2D14B, 0xA4299E27469FF67B, 0xAFD5AED1C1BC96A8,
+        0x10851C0E3858DA9F, 0xE6F51ED79B9DB21F, 0x64A6E14AFD36B46F, 0x80C7D7D45A5479AD,
+        0x05044B62FA52D080
+    ];
+
+    fn check_eay<Access: TableAccess>(vectors: &[(u64, u64, u64)]) {
+        for &(key, plaintext, ciphertext) in vectors.iter() {
+            let cipher = Blowfish::<Access>::from_key(key_u64(key));
+            assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+            assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+        }
+    }
+
+    fn check_set_key<Access: TableAccess>(ciphertexts: &[u64]) {
+        for (i, &ciphertext) in ciphertexts.iter().enumerate() {
+            let cipher = Blowfish::<Access>::from_key(key_bytes(&SET_KEY[..i + 4]));
+            assert_eq!(cipher.encrypt(Secret::new(0xFEDCBA9876543210)).expose(), ciphertext);
+        }
+    }
+
+    #[test]
+    fn eay() {
+        check_eay::<Indexed>(&EAY_VECTORS);
+    }
+
+    #[test]
+    fn set_key() {
+        check_set_key::<Indexed>(&SET_KEY_CIPHERTEXTS);
+    }
+
+    // The key schedule runs 521 encryptions, each of which scans whole S-boxes with `Masked`, so
+    // only a few keys are checked this way.
+    #[test]
+    fn masked() {
+        check_eay::<Masked>(&EAY_VECTORS[..2]);
+        check_set_key::<Masked>(&SET_KEY_CIPHERTEXTS[..1]);
+    }
+
+    #[test]
+    fn longest_key() {
+        let key: Vec<u8> = (0..56).collect();
+        let cipher = Blowfish::<Indexed>::from_key(key_bytes(&key));
+        assert_eq!(cipher.encrypt(Secret::new(0xFEDCBA9876543210)).expose(), 0x4F6B2ACB8A4BF891);
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_key() {
+        Blowfish::<Indexed>::from_key(key_bytes(&[1, 2, 3]));
+    }
+
+    #[bench]
+    fn bench_indexed(bencher: &mut Bencher) {
+        let cipher = Blowfish::<Indexed>::from_key(key_u64(0x0123456789ABCDEF));
+        let mut block = Secret::new(0);
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+
+    #[bench]
+    fn bench_masked(bencher: &mut Bencher) {
+        let cipher = Blowfish::<Masked>::from_key(key_u64(0x0123456789ABCDEF));
+        let mut block = Secret::new(0);
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
@@ -0,0 +1,324 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::feistel::LayeredFeistel;
+use cipher::block::sbox::TableAccess;
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::rotate::RotateLeft;
+use utils::traits::truncate::Truncate;
+
+use typenum::consts::{U6, U16, U24, U32};
+
+// See RFC 3713, Section 2.4.4. The other S-boxes are rotations of this one.
+const SBOX1: [u8; 256] = [
+    0x70, 0x82, 0x2C, 0xEC, 0xB3, 0x27, 0xC0, 0xE5, 0xE4, 0x85, 0x57, 0x35, 0xEA, 0x0C, 0xAE, 0x41,
+    0x23, 0xEF, 0x6B, 0x93, 0x45, 0x19, 0xA5, 0x21, 0xED, 0x0E, 0x4F, 0x4E, 0x1D, 0x65, 0x92, 0xBD,
+    0x86, 0xB8, 0xAF, 0x8F, 0x7C, 0xEB, 0x1F, 0xCE, 0x3E, 0x30, 0xDC, 0x5F, 0x5E, 0xC5, 0x0B, 0x1A,
+    0xA6, 0xE1, 0x39, 0xCA, 0xD5, 0x47, 0x5D, 0x3D, 0xD9, 0x01, 0x5A, 0xD6, 0x51, 0x56, 0x6C, 0x4D,
+    0x8B, 0x0D, 0x9A, 0x66, 0xFB, 0xCC, 0xB0, 0x2D, 0x74, 0x12, 0x2B, 0x20, 0xF0, 0xB1, 0x84, 0x99,
+    0xDF, 0x4C, 0xCB, 0xC2, 0x34, 0x7E, 0x76, 0x05, 0x6D, 0xB7, 0xA9, 0x31, 0xD1, 0x17, 0x04, 0xD7,
+    0x14, 0x58, 0x3A, 0x61, 0xDE, 0x1B, 0x11, 0x1C, 0x32, 0x0F, 0x9C, 0x16, 0x53, 0x18, 0xF2, 0x22,
+    0xFE, 0x44, 0xCF, 0xB2, 0xC3, 0xB5, 0x7A, 0x91, 0x24, 0x08, 0xE8, 0xA8, 0x60, 0xFC, 0x69, 0x50,
+    0xAA, 0xD0, 0xA0, 0x7D, 0xA1, 0x89, 0x62, 0x97, 0x54, 0x5B, 0x1E, 0x95, 0xE0, 0xFF, 0x64, 0xD2,
+    0x10, 0xC4, 0x00, 0x48, 0xA3, 0xF7, 0x75, 0xDB, 0x8A, 0x03, 0xE6, 0xDA, 0x09, 0x3F, 0xDD, 0x94,
+    0x87, 0x5C, 0x83, 0x02, 0xCD, 0x4A, 0x90, 0x33, 0x73, 0x67, 0xF6, 0xF3, 0x9D, 0x7F, 0xBF, 0xE2,
+    0x52, 0x9B, 0xD8, 0x26, 0xC8, 0x37, 0xC6, 0x3B, 0x81, 0x96, 0x6F, 0x4B, 0x13, 0xBE, 0x63, 0x2E,
+    0xE9, 0x79, 0xA7, 0x8C, 0x9F, 0x6E, 0xBC, 0x8E, 0x29, 0xF5, 0xF9, 0xB6, 0x2F, 0xFD, 0xB4, 0x59,
+    0x78, 0x98, 0x06, 0x6A, 0xE7, 0x46, 0x71, 0xBA, 0xD4, 0x25, 0xAB, 0x42, 0x88, 0xA2, 0x8D, 0xFA,
+    0x72, 0x07, 0xB9, 0x55, 0xF8, 0xEE, 0xAC, 0x0A, 0x36, 0x49, 0x2A, 0x68, 0x3C, 0x38, 0xF1, 0xA4,
+    0x40, 0x28, 0xD3, 0x7B, 0xBB, 0xC9, 0x43, 0xC1, 0x15, 0xE3, 0xAD, 0xF4, 0x77, 0xC7, 0x80, 0x9E
+];
+
+// The constants of the key schedule, from RFC 3713, Section 2.2
+const SIGMA: [u64; 6] = [
+    0xA09E667F3BCC908B, 0xB67AE8584CAA73B2, 0xC6EF372FE94F82BE,
+    0x54FF53A5F1D36F1C, 0x10E527FADE682D1D, 0xB05688C2B3E6C1FD
+];
+
+// Which S-box each byte of the input of F goes through, starting from the most significant
+const SBOX_ORDER: [usize; 8] = [0, 1, 2, 3, 1, 2, 3, 0];
+
+// The P-function: each byte of its output is the XOR of the bytes of its input that are set in
+// one of these masks, with the first byte as the most significant bit.
+const P: [u8; 8] = [0xB7, 0xDB, 0xED, 0x7E, 0xC7, 0x6B, 0x3D, 0x9E];
+
+type Tables = [[Secret<u32>; 256]; 4];
+
+// SBOX1 to SBOX4, widened so that they can be read through `TableAccess`
+fn tables() -> Tables {
+    let mut tables = [[Secret::new(0); 256]; 4];
+    for x in 0..256 {
+        let s = SBOX1[x];
+        tables[0][x] = Secret::new(u32::from(s));
+        tables[1][x] = Secret::new(u32::from(s.rotate_left(1)));
+        tables[2][x] = Secret::new(u32::from(s.rotate_left(7)));
+        tables[3][x] = Secret::new(u32::from(SBOX1[(x as u8).rotate_left(1) as usize]));
+    }
+    tables
+}
+
+// The F-function, with the subkey already XORed in
+fn f<Access: TableAccess>(tables: &Tables, x: Secret<u64>) -> Secret<u64> {
+    let mut t = [Secret::new(0); 8];
+    for (i, t) in t.iter_mut().enumerate() {
+        *t = Access::lookup(&tables[SBOX_ORDER[i]], (x >> (56 - 8 * i as u32)).truncate());
+    }
+
+    let mut y = Secret::new(0);
+    for &mask in P.iter() {
+        let byte = (0..8).filter(|i| mask >> (7 - i) & 1 == 1).fold(Secret::new(0), |byte, i| byte ^ t[i]);
+        y = (y << 8) | Secret::<u64>::from(byte);
+    }
+    y
+}
+
+fn fl(x: Secret<u64>, key: Secret<u64>) -> Secret<u64> {
+    let (mut left, mut right): (Secret<u32>, Secret<u32>) = ((x >> 32).truncate(), x.truncate());
+    let (key_left, key_right): (Secret<u32>, Secret<u32>) = ((key >> 32).truncate(), key.truncate());
+    right ^= (left & key_left).rotate_left(1);
+    left ^= right | key_right;
+    Secret::<u64>::from(left) << 32 | Secret::<u64>::from(right)
+}
+
+fn fl_inverse(y: Secret<u64>, key: Secret<u64>) -> Secret<u64> {
+    let (mut left, mut right): (Secret<u32>, Secret<u32>) = ((y >> 32).truncate(), y.truncate());
+    let (key_left, key_right): (Secret<u32>, Secret<u32>) = ((key >> 32).truncate(), key.truncate());
+    left ^= right | key_right;
+    right ^= (left & key_left).rotate_left(1);
+    Secret::<u64>::from(left) << 32 | Secret::<u64>::from(right)
+}
+
+struct CamelliaRound<Access> {
+    tables: Arc<Tables>,
+    key: Secret<u64>,
+    _marker: PhantomData<Access>
+}
+
+impl<Access: TableAccess> BlockFn for CamelliaRound<Access> {
+    type Block = Secret<u64>;
+    fn encrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        f::<Access>(&self.tables, block ^ self.key)
+    }
+}
+
+impl<Access> Keyed for CamelliaRound<Access> {
+    type Key = (Arc<Tables>, Secret<u64>);
+    fn from_key(key: (Arc<Tables>, Secret<u64>)) -> CamelliaRound<Access> {
+        CamelliaRound {
+            tables: key.0,
+            key: key.1,
+            _marker: PhantomData
+        }
+    }
+}
+
+// The FL and FL⁻¹ functions between groups of six rounds. Each group ends with the halves in
+// the order (D1, D2) and starts with them in the order (D2, D1), so this also swaps them.
+struct FlLayer {
+    keys: [Secret<u64>; 2]
+}
+
+impl BlockFn for FlLayer {
+    type Block = (Secret<u64>, Secret<u64>);
+    fn encrypt(&self, block: (Secret<u64>, Secret<u64>)) -> (Secret<u64>, Secret<u64>) {
+        (fl_inverse(block.1, self.keys[1]), fl(block.0, self.keys[0]))
+    }
+}
+
+impl BlockCipher for FlLayer {
+    fn decrypt(&self, block: (Secret<u64>, Secret<u64>)) -> (Secret<u64>, Secret<u64>) {
+        (fl_inverse(block.1, self.keys[0]), fl(block.0, self.keys[1]))
+    }
+}
+
+impl Keyed for FlLayer {
+    type Key = [Secret<u64>; 2];
+    fn from_key(key: [Secret<u64>; 2]) -> FlLayer {
+        FlLayer {
+            keys: key
+        }
+    }
+}
+
+/// The Camellia block cipher from RFC 3713, with a key of `KeyBytes` bytes, reading its S-boxes
+/// as chosen by `Access`.
+///
+/// Blocks are big-endian, as in the RFC. The rounds run as a `LayeredFeistel`: three groups of
+/// six rounds for 128-bit keys and four for longer keys, with FL and FL⁻¹ in between.
+pub struct Camellia<KeyBytes, Access> {
+    whitening: [Secret<u64>; 4],
+    inner: LayeredFeistel<CamelliaRound<Access>, FlLayer, U6>,
+    _marker: PhantomData<KeyBytes>
+}
+
+pub type Camellia128<Access> = Camellia<U16, Access>;
+pub type Camellia192<Access> = Camellia<U24, Access>;
+pub type Camellia256<Access> = Camellia<U32, Access>;
+
+impl<KeyBytes, Access: TableAccess> BlockFn for Camellia<KeyBytes, Access> {
+    type Block = Secret<u128>;
+    fn encrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        let d1 = Truncate::<Secret<u64>>::truncate(block >> 64) ^ self.whitening[0];
+        let d2 = Truncate::<Secret<u64>>::truncate(block) ^ self.whitening[1];
+        let (d1, d2) = self.inner.encrypt((d2, d1));
+        Secret::<u128>::from(d2 ^ self.whitening[2]) << 64 | Secret::<u128>::from(d1 ^ self.whitening[3])
+    }
+}
+
+impl<KeyBytes, Access: TableAccess> BlockCipher for Camellia<KeyBytes, Access> {
+    fn decrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        let d2 = Truncate::<Secret<u64>>::truncate(block >> 64) ^ self.whitening[2];
+        let d1 = Truncate::<Secret<u64>>::truncate(block) ^ self.whitening[3];
+        let (d2, d1) = self.inner.decrypt((d1, d2));
+        Secret::<u128>::from(d1 ^ self.whitening[0]) << 64 | Secret::<u128>::from(d2 ^ self.whitening[1])
+    }
+}
+
+// The two halves of `key` rotated left by `rotation` bits
+fn halves(key: Secret<u128>, rotation: u32) -> (Secret<u64>, Secret<u64>) {
+    let key = key.rotate_left(rotation);
+    ((key >> 64).truncate(), key.truncate())
+}
+
+// Round keys for a group of six rounds, from three rotated keys
+fn group(tables: &Arc<Tables>, keys: [(Secret<u64>, Secret<u64>); 3]) -> Array<(Arc<Tables>, Secret<u64>), U6> {
+    Array::from_fn(|i| {
+        let pair = keys[i / 2];
+        (tables.clone(), if i % 2 == 0 { pair.0 } else { pair.1 })
+    })
+}
+
+impl<KeyBytes: ArrayLength<Secret<u8>>, Access: TableAccess> Keyed for Camellia<KeyBytes, Access> {
+    type Key = Array<Secret<u8>, KeyBytes>;
+    fn from_key(key: Array<Secret<u8>, KeyBytes>) -> Camellia<KeyBytes, Access> {
+        assert!(key.len() == 16 || key.len() == 24 || key.len() == 32, "Camellia keys are 128, 192 or 256 bits long");
+
+        let word = |bytes: &[Secret<u8>]| bytes.iter().fold(Secret::new(0), |word, &byte| word << 8 | Secret::<u128>::from(byte));
+        let kl = word(&key[..16]);
+        let kr = match key.len() {
+            16 => Secret::new(0),
+            24 => {
+                let half = word(&key[16..]);
+                half << 64 | (!half & Secret::new(0xFFFFFFFFFFFFFFFF))
+            },
+            _ => word(&key[16..])
+        };
+
+        // Section 2.2 of RFC 3713
+        let tables = Arc::new(tables());
+        let sigma = |i: usize| Secret::new(SIGMA[i]);
+        let (mut d1, mut d2) = halves(kl ^ kr, 0);
+        d2 ^= f::<Access>(&tables, d1 ^ sigma(0));
+        d1 ^= f::<Access>(&tables, d2 ^ sigma(1));
+        let (l1, l2) = halves(kl, 0);
+        d1 ^= l1;
+        d2 ^= l2;
+        d2 ^= f::<Access>(&tables, d1 ^ sigma(2));
+        d1 ^= f::<Access>(&tables, d2 ^ sigma(3));
+        let ka = Secret::<u128>::from(d1) << 64 | Secret::<u128>::from(d2);
+        let (mut d1, mut d2) = halves(ka ^ kr, 0);
+        d2 ^= f::<Access>(&tables, d1 ^ sigma(4));
+        d1 ^= f::<Access>(&tables, d2 ^ sigma(5));
+        let kb = Secret::<u128>::from(d1) << 64 | Secret::<u128>::from(d2);
+
+        // Section 2.4
+        let (kw1, kw2) = halves(kl, 0);
+        let (first, rest, (kw3, kw4)) = if key.len() == 16 {
+            let k9_k10 = (halves(ka, 45).0, halves(kl, 60).1);
+            let first = group(&tables, [halves(ka, 0), halves(kl, 15), halves(ka, 15)]);
+            let rest = vec![
+                (pair(halves(ka, 30)), group(&tables, [halves(kl, 45), k9_k10, halves(ka, 60)])),
+                (pair(halves(kl, 77)), group(&tables, [halves(kl, 94), halves(ka, 94), halves(kl, 111)]))
+            ];
+            (first, rest, halves(ka, 111))
+        } else {
+            let first = group(&tables, [halves(kb, 0), halves(kr, 15), halves(ka, 15)]);
+            let rest = vec![
+                (pair(halves(kr, 30)), group(&tables, [halves(kb, 30), halves(kl, 45), halves(ka, 45)])),
+                (pair(halves(kl, 60)), group(&tables, [halves(kr, 60), halves(kb, 60), halves(kl, 77)])),
+                (pair(halves(ka, 77)), group(&tables, [halves(kr, 94), halves(ka, 94), halves(kl, 111)]))
+            ];
+            (first, rest, halves(kb, 111))
+        };
+
+        Camellia {
+            whitening: [kw1, kw2, kw3, kw4],
+            inner: Keyed::from_key((first, rest)),
+            _marker: PhantomData
+        }
+    }
+}
+
+fn pair(keys: (Secret<u64>, Secret<u64>)) -> [Secret<u64>; 2] {
+    [keys.0, keys.1]
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    extern crate rand;
+
+    use super::{Camellia, Camellia128};
+
+    use array::ArrayLength;
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::sbox::{Indexed, Masked, TableAccess};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::testing::{bench_encrypt, make_key};
+    use utils::hex;
+
+    use typenum::consts::{U16, U24, U32};
+
+    use self::test::Bencher;
+    use self::rand::{Rng, thread_rng};
+
+    fn check<N: ArrayLength<Secret<u8>>, Access: TableAccess>(key: &str, plaintext: u128, ciphertext: u128) {
+        let cipher = Camellia::<N, Access>::from_key(make_key(&hex::decode(key)));
+        assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+        assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+    }
+
+    fn check_rfc3713<Access: TableAccess>() {
+        // RFC 3713, Appendix A
+        let plaintext = 0x0123456789ABCDEFFEDCBA9876543210;
+        check::<U16, Access>("0123456789ABCDEFFEDCBA9876543210", plaintext, 0x67673138549669730857065648EABE43);
+        check::<U24, Access>("0123456789ABCDEFFEDCBA98765432100011223344556677", plaintext, 0xB4993401B3E996F84EE5CEE7D79B09B9);
+        check::<U32, Access>("0123456789ABCDEFFEDCBA987654321000112233445566778899AABBCCDDEEFF", plaintext, 0x9ACC237DFF16D76C20EF7C919E3A7509);
+    }
+
+    #[test]
+    fn rfc3713_indexed() {
+        check_rfc3713::<Indexed>();
+    }
+
+    #[test]
+    fn rfc3713_masked() {
+        check_rfc3713::<Masked>();
+    }
+
+    #[test]
+    fn accesses_agree() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            let key: Vec<u8> = (0..32).map(|_| rng.gen()).collect();
+            let block = Secret::new((rng.gen::<u64>() as u128) << 64 | rng.gen::<u64>() as u128);
+            let indexed = Camellia::<U32, Indexed>::from_key(make_key(&key));
+            let masked = Camellia::<U32, Masked>::from_key(make_key(&key));
+            assert_eq!(indexed.encrypt(block).expose(), masked.encrypt(block).expose());
+        }
+    }
+
+    #[bench]
+    fn bench_indexed(bencher: &mut Bencher) {
+        bench_encrypt(bencher, &Camellia128::<Indexed>::from_key(make_key(&[0; 16])), Secret::new(0), 16);
+    }
+
+    #[bench]
+    fn bench_masked(bencher: &mut Bencher) {
+        bench_encrypt(bencher, &Camellia128::<Masked>::from_key(make_key(&[0; 16])), Secret::new(0), 16);
+    }
+}
@@ -0,0 +1,153 @@
+use cipher::block::{BlockFn, BlockCipher};
+use keyed::Keyed;
+use permutation::{Permutation, InvertiblePermutation};
+
+use std::ops::BitXor;
+
+/// The Even-Mansour construction, turning a public permutation into a block cipher by whitening
+/// its input and output with two keys: `E(x) = P(x ^ k1) ^ k2`.
+pub struct EvenMansour<P> {
+    first: P,
+    second: P
+}
+
+impl<P> BlockFn for EvenMansour<P> where P: Permutation + Clone + BitXor<P, Output=P> {
+    type Block = P;
+
+    fn encrypt(&self, block: P) -> P {
+        let mut state = block ^ self.first.clone();
+        state.permute();
+        state ^ self.second.clone()
+    }
+}
+
+impl<P> BlockCipher for EvenMansour<P> where P: InvertiblePermutation + Clone + BitXor<P, Output=P> {
+    fn decrypt(&self, block: P) -> P {
+        let mut state = block ^ self.second.clone();
+        state.unpermute();
+        state ^ self.first.clone()
+    }
+}
+
+impl<P> Keyed for EvenMansour<P> {
+    type Key = (P, P);
+    fn from_key(key: (P, P)) -> EvenMansour<P> {
+        EvenMansour {
+            first: key.0,
+            second: key.1
+        }
+    }
+}
+
+/// The single-key variant of Even-Mansour, which uses the same key on both sides of the
+/// permutation: `E(x) = P(x ^ k) ^ k`.
+pub struct SingleKeyEvenMansour<P> {
+    key: P
+}
+
+impl<P> BlockFn for SingleKeyEvenMansour<P> where P: Permutation + Clone + BitXor<P, Output=P> {
+    type Block = P;
+
+    fn encrypt(&self, block: P) -> P {
+        let mut state = block ^ self.key.clone();
+        state.permute();
+        state ^ self.key.clone()
+    }
+}
+
+impl<P> BlockCipher for SingleKeyEvenMansour<P> where P: InvertiblePermutation + Clone + BitXor<P, Output=P> {
+    fn decrypt(&self, block: P) -> P {
+        let mut state = block ^ self.key.clone();
+        state.unpermute();
+        state ^ self.key.clone()
+    }
+}
+
+impl<P> Keyed for SingleKeyEvenMansour<P> {
+    type Key = P;
+    fn from_key(key: P) -> SingleKeyEvenMansour<P> {
+        SingleKeyEvenMansour {
+            key: key
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::{EvenMansour, SingleKeyEvenMansour};
+
+    use cipher::block::{BlockFn, BlockCipher};
+    use keyed::Keyed;
+    use permutation::norx::{Norx32, Norx64};
+    use secret::Secret;
+
+    use self::rand::{Rng, thread_rng};
+
+    fn random_norx32() -> Norx32 {
+        let mut rng = thread_rng();
+        let mut words = [Secret::new(0); 16];
+        for word in words.iter_mut() {
+            *word = Secret::new(rng.gen());
+        }
+        Norx32::new(words)
+    }
+
+    fn random_norx64() -> Norx64 {
+        let mut rng = thread_rng();
+        let mut words = [Secret::new(0); 16];
+        for word in words.iter_mut() {
+            *word = Secret::new(rng.gen());
+        }
+        Norx64::new(words)
+    }
+
+    fn exposed32(state: &Norx32) -> Vec<u32> {
+        state.words().iter().map(|w| w.expose()).collect()
+    }
+
+    fn exposed64(state: &Norx64) -> Vec<u64> {
+        state.words().iter().map(|w| w.expose()).collect()
+    }
+
+    #[test]
+    fn round_trip_32() {
+        for _ in 0..20 {
+            let cipher = EvenMansour::from_key((random_norx32(), random_norx32()));
+            let plaintext = random_norx32();
+            let ciphertext = cipher.encrypt(plaintext.clone());
+            assert!(exposed32(&ciphertext) != exposed32(&plaintext));
+            assert_eq!(exposed32(&cipher.decrypt(ciphertext)), exposed32(&plaintext));
+        }
+    }
+
+    #[test]
+    fn round_trip_64() {
+        for _ in 0..20 {
+            let cipher = EvenMansour::from_key((random_norx64(), random_norx64()));
+            let plaintext = random_norx64();
+            let ciphertext = cipher.encrypt(plaintext.clone());
+            assert_eq!(exposed64(&cipher.decrypt(ciphertext)), exposed64(&plaintext));
+        }
+    }
+
+    #[test]
+    fn single_key_round_trip() {
+        for _ in 0..20 {
+            let cipher = SingleKeyEvenMansour::from_key(random_norx32());
+            let plaintext = random_norx32();
+            let ciphertext = cipher.encrypt(plaintext.clone());
+            assert_eq!(exposed32(&cipher.decrypt(ciphertext)), exposed32(&plaintext));
+        }
+    }
+
+    #[test]
+    fn single_key_matches_two_keys() {
+        let key = random_norx32();
+        let single = SingleKeyEvenMansour::from_key(key.clone());
+        let double = EvenMansour::from_key((key.clone(), key));
+        let plaintext = random_norx32();
+        assert_eq!(exposed32(&single.encrypt(plaintext.clone())), exposed32(&double.encrypt(plaintext)));
+    }
+}
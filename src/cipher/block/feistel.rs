@@ -45,3 +45,45 @@ impl<RoundFn: Keyed, Rounds: ArrayLength<RoundFn> + ArrayLength<RoundFn::Key>> K
         }
     }
 }
+
+/// A Feistel network run in groups of `GroupRounds` rounds, with a keyed `Layer` applied to the
+/// whole block between consecutive groups, such as the FL and FL⁻¹ functions of Camellia.
+///
+/// Each layer takes the output of one group and gives the input of the next, so it also decides
+/// which half goes on which side.
+pub struct LayeredFeistel<RoundFn, Layer, GroupRounds: ArrayLength<RoundFn>> {
+    first: Feistel<RoundFn, GroupRounds>,
+    rest: Vec<(Layer, Feistel<RoundFn, GroupRounds>)>
+}
+
+impl<HalfBlock, RoundFn: BlockFn<Block=HalfBlock>, Layer: BlockFn<Block=(HalfBlock, HalfBlock)>, GroupRounds: ArrayLength<RoundFn>> BlockFn for LayeredFeistel<RoundFn, Layer, GroupRounds> where HalfBlock: Copy + BitXorAssign<HalfBlock> {
+    type Block = (HalfBlock, HalfBlock);
+
+    fn encrypt(&self, block: Self::Block) -> Self::Block {
+        self.rest.iter().fold(self.first.encrypt(block), |block, (layer, group)| {
+            group.encrypt(layer.encrypt(block))
+        })
+    }
+}
+
+impl<HalfBlock, RoundFn: BlockFn<Block=HalfBlock>, Layer: BlockCipher<Block=(HalfBlock, HalfBlock)>, GroupRounds: ArrayLength<RoundFn>> BlockCipher for LayeredFeistel<RoundFn, Layer, GroupRounds> where HalfBlock: Copy + BitXorAssign<HalfBlock> {
+    fn decrypt(&self, block: Self::Block) -> Self::Block {
+        let block = self.rest.iter().rev().fold(block, |block, (layer, group)| {
+            layer.decrypt(group.decrypt(block))
+        });
+        self.first.decrypt(block)
+    }
+}
+
+impl<RoundFn: Keyed, Layer: Keyed, GroupRounds: ArrayLength<RoundFn> + ArrayLength<RoundFn::Key>> Keyed for LayeredFeistel<RoundFn, Layer, GroupRounds> {
+    /// The round keys of the first group, then the key of each layer with the round keys of the
+    /// group that follows it
+    type Key = (Array<RoundFn::Key, GroupRounds>, Vec<(Layer::Key, Array<RoundFn::Key, GroupRounds>)>);
+    fn from_key(key: Self::Key) -> Self {
+        let (first, rest) = key;
+        LayeredFeistel {
+            first: Keyed::from_key(first),
+            rest: rest.into_iter().map(|(layer, group)| (Layer::from_key(layer), Keyed::from_key(group))).collect()
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::marker::PhantomData;
+
+use array::Array;
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::sbox;
+use cipher::block::spn::{InvertibleLayer, Layer, Spn, inverse_permute_bits, permute_bits};
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::rotate::RotateRight;
+
+use typenum::consts::{U16, U28, U40};
+use typenum::uint::Unsigned;
+
+// The S-box GS in algebraic normal form, as `sbox::substitute_anf` takes it
+const ANF: [u16; 4] = [0x011F, 0x013A, 0x4614, 0x2402];
+const INVERSE_ANF: [u16; 4] = [0x097F, 0x15F8, 0x0217, 0x0413];
+
+// The S-box as a table, from Banik et al., "GIFT: A Small Present"
+#[cfg(test)]
+const SBOX: [u8; 16] = [0x1, 0xA, 0x4, 0xC, 0x6, 0xF, 0x3, 0x9, 0x2, 0xD, 0xB, 0x7, 0x5, 0x0, 0x8, 0xE];
+
+/// SubCells, the S-box GS applied in parallel to bit planes of the state
+pub struct SubCells<Block> {
+    _marker: PhantomData<Block>
+}
+
+/// PermBits, the bit permutation of GIFT, which keeps every bit in the same position within its
+/// nibble
+pub struct PermBits<Block> {
+    _marker: PhantomData<Block>
+}
+
+// Bit i of a state of `bits` bits moves to 4⌊i/16⌋ + (bits/4)((3⌊(i mod 16)/4⌋ + (i mod 4)) mod 4) + (i mod 4).
+fn position(bits: u32, i: u32) -> u32 {
+    4 * (i / 16) + bits / 4 * ((3 * (i % 16 / 4) + i % 4) % 4) + i % 4
+}
+
+// The key state, where k[i] is the word called k_i in the paper
+type KeyState = [Secret<u16>; 8];
+
+fn key_state(key: &Array<Secret<u8>, U16>) -> KeyState {
+    let mut state = [Secret::new(0); 8];
+    for (i, word) in state.iter_mut().enumerate() {
+        *word = Secret::<u16>::from(key[14 - 2 * i]) << 8 | Secret::<u16>::from(key[15 - 2 * i]);
+    }
+    state
+}
+
+// k7 || ... || k0 ← (k1 ⋙ 2) || (k0 ⋙ 12) || k7 || ... || k2
+fn update_key_state(k: KeyState) -> KeyState {
+    [k[2], k[3], k[4], k[5], k[6], k[7], k[0].rotate_right(12), k[1].rotate_right(2)]
+}
+
+// The 6-bit round constants come from an LFSR, c ← c5..c1 || c5 ⊕ c4 ⊕ 1, starting from 0.
+fn next_constant(constant: u8) -> u8 {
+    constant << 1 & 0x3F | (constant >> 5 ^ constant >> 4 ^ 1) & 1
+}
+
+macro_rules! gift_impl {
+    { $name:ident, $t:ty, $bits:expr, $rounds:ty, $doc:expr, $round_key:expr } => {
+        impl Layer for SubCells<Secret<$t>> {
+            type Block = Secret<$t>;
+            fn apply(block: Secret<$t>) -> Secret<$t> {
+                sbox::substitute_nibbles(&ANF, block, Secret::new(!0 / 0xF))
+            }
+        }
+
+        impl InvertibleLayer for SubCells<Secret<$t>> {
+            fn invert(block: Secret<$t>) -> Secret<$t> {
+                sbox::substitute_nibbles(&INVERSE_ANF, block, Secret::new(!0 / 0xF))
+            }
+        }
+
+        impl Layer for PermBits<Secret<$t>> {
+            type Block = Secret<$t>;
+            fn apply(block: Secret<$t>) -> Secret<$t> {
+                permute_bits(block, Secret::new(1), $bits, |i| position($bits, i))
+            }
+        }
+
+        impl InvertibleLayer for PermBits<Secret<$t>> {
+            fn invert(block: Secret<$t>) -> Secret<$t> {
+                inverse_permute_bits(block, Secret::new(1), $bits, |i| position($bits, i))
+            }
+        }
+
+        #[doc = $doc]
+        pub struct $name {
+            inner: Spn<SubCells<Secret<$t>>, PermBits<Secret<$t>>, $rounds>
+        }
+
+        impl BlockFn for $name {
+            type Block = Secret<$t>;
+            fn encrypt(&self, block: Secret<$t>) -> Secret<$t> {
+                self.inner.encrypt(block)
+            }
+        }
+
+        impl BlockCipher for $name {
+            fn decrypt(&self, block: Secret<$t>) -> Secret<$t> {
+                self.inner.decrypt(block)
+            }
+        }
+
+        impl Keyed for $name {
+            type Key = Array<Secret<u8>, U16>;
+            fn from_key(key: Array<Secret<u8>, U16>) -> $name {
+                // GIFT adds each round key after the layers, so the SPN's first round key is 0 and
+                // its final key is the last round key. Round keys include the round constants and
+                // the single 1 in the top bit.
+                let round_key: fn(&KeyState) -> Secret<$t> = $round_key;
+                let mut state = key_state(&key);
+                let mut constant = 0;
+                let mut round_keys = vec![Secret::new(0)];
+                while round_keys.len() <= <$rounds>::to_usize() {
+                    constant = next_constant(constant);
+                    let constant_bits = permute_bits(<$t>::from(constant), 1, 6, |i| 4 * i + 3);
+                    round_keys.push(round_key(&state) ^ constant_bits ^ 1 << ($bits - 1));
+                    state = update_key_state(state);
+                }
+
+                let last = round_keys.pop().unwrap();
+                $name {
+                    inner: Spn::from_key((Array::from_fn(|i| round_keys[i]), last))
+                }
+            }
+        }
+    };
+}
+
+gift_impl! { Gift64, u64, 64, U28,
+             "GIFT-64, from Banik et al., \"GIFT: A Small Present\", as 28 rounds of an SPN. Blocks are the \
+              64-bit state and keys are big-endian, as in the designers' test vectors.",
+             |k| {
+                 // U = k1 goes into bits 4i + 1 and V = k0 into bits 4i.
+                 permute_bits(Secret::<u64>::from(k[1]), Secret::new(1), 16, |i| 4 * i + 1) |
+                     permute_bits(Secret::<u64>::from(k[0]), Secret::new(1), 16, |i| 4 * i)
+             } }
+
+gift_impl! { Gift128, u128, 128, U40,
+             "GIFT-128, from Banik et al., \"GIFT: A Small Present\", as 40 rounds of an SPN. Blocks are the \
+              128-bit state and keys are big-endian, as in the designers' test vectors.",
+             |k| {
+                 // U = k5 || k4 goes into bits 4i + 2 and V = k1 || k0 into bits 4i + 1.
+                 let u = Secret::<u128>::from(k[5]) << 16 | Secret::<u128>::from(k[4]);
+                 let v = Secret::<u128>::from(k[1]) << 16 | Secret::<u128>::from(k[0]);
+                 permute_bits(u, Secret::new(1), 32, |i| 4 * i + 2) | permute_bits(v, Secret::new(1), 32, |i| 4 * i + 1)
+             } }
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{SBOX, Gift64, Gift128, SubCells};
+
+    use array::Array;
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::spn::{InvertibleLayer, Layer};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+
+    use typenum::consts::U16;
+
+    use self::test::Bencher;
+
+    #[test]
+    fn anf_matches_table() {
+        let inputs = (0..16).fold(0, |block, i| block | i << (4 * i));
+        let outputs = SBOX.iter().enumerate().fold(0, |block, (i, &out)| block | u64::from(out) << (4 * i));
+        assert_eq!(SubCells::<Secret<u64>>::apply(Secret::new(inputs)).expose(), outputs);
+        assert_eq!(SubCells::<Secret<u64>>::invert(Secret::new(outputs)).expose(), inputs);
+    }
+
+    fn key(key: &str) -> Array<Secret<u8>, U16> {
+        let key = hex::decode(key);
+        Array::from_fn(|i| Secret::new(key[i]))
+    }
+
+    fn check64(key_hex: &str, plaintext: u64, ciphertext: u64) {
+        let cipher = Gift64::from_key(key(key_hex));
+        assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+        assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+    }
+
+    fn check128(key_hex: &str, plaintext: u128, ciphertext: u128) {
+        let cipher = Gift128::from_key(key(key_hex));
+        assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+        assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+    }
+
+    // The test vectors published by the designers
+    #[test]
+    fn gift64() {
+        check64("00000000000000000000000000000000", 0x0000000000000000, 0xF62BC3EF34F775AC);
+        check64("FEDCBA9876543210FEDCBA9876543210", 0xFEDCBA9876543210, 0xC1B71F66160FF587);
+        check64("BD91731EB6BC2713A1F9F6FFC75044E7", 0xC450C7727A9B8A7D, 0xE3272885FA94BA8B);
+    }
+
+    #[test]
+    fn gift128() {
+        check128("00000000000000000000000000000000", 0x00000000000000000000000000000000,
+                 0xCD0BD738388AD3F668B15A36CEB6FF92);
+        check128("FEDCBA9876543210FEDCBA9876543210", 0xFEDCBA9876543210FEDCBA9876543210,
+                 0x8422241A6DBF5A9346AF468409EE0152);
+        check128("D0F5C59A7700D3E799028FA9F90AD837", 0xE39C141FA57DBA43F08A85B6A91F86C1,
+                 0x13EDE67CBDCC3DBF400A62D6977265EA);
+    }
+
+    #[bench]
+    fn bench_gift64(bencher: &mut Bencher) {
+        let cipher = Gift64::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = Secret::new(0);
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+
+    #[bench]
+    fn bench_gift128(bencher: &mut Bencher) {
+        let cipher = Gift128::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = Secret::new(0);
+        bencher.bytes = 16;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
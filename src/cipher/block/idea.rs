@@ -0,0 +1,239 @@
+use array::Array;
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::lai_massey::{Identity, LaiMassey};
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::rotate::RotateLeft;
+use utils::traits::truncate::Truncate;
+use utils::traits::wrapping::{WrappingAdd, WrappingMul, WrappingSub};
+
+use typenum::consts::{U1, U16};
+
+const ROUNDS: usize = 8;
+
+/// Multiplication modulo 2^16 + 1, where 0 stands for 2^16 so that every word is invertible
+pub fn multiply(a: Secret<u16>, b: Secret<u16>) -> Secret<u16> {
+    // Only 0 borrows past the low 16 bits when 1 is subtracted, which sets exactly bit 16.
+    let widen = |x: Secret<u16>| {
+        let x = Secret::<u64>::from(x);
+        x | (x.wrapping_sub(Secret::new(1)) >> 16 & 1) << 16
+    };
+    let product = widen(a).wrapping_mul(widen(b));
+
+    // Since 2^16 = -1, the product is its low half minus its high half, plus the modulus if that
+    // borrows. A result of 2^16 truncates to 0 as it should.
+    let difference = (product & 0xFFFF).wrapping_sub(product >> 16);
+    let borrow = Secret::new(0).wrapping_sub(difference >> 63);
+    difference.wrapping_add(borrow & 0x10001).truncate()
+}
+
+/// The inverse modulo 2^16 + 1, as x^(2^16 - 1) with a fixed sequence of multiplications
+pub fn inverse(x: Secret<u16>) -> Secret<u16> {
+    (0..15).fold(x, |power, _| multiply(multiply(power, power), x))
+}
+
+fn split(half: Secret<u32>) -> (Secret<u16>, Secret<u16>) {
+    ((half >> 16).truncate(), half.truncate())
+}
+
+fn join(high: Secret<u16>, low: Secret<u16>) -> Secret<u32> {
+    Secret::<u32>::from(high) << 16 | Secret::<u32>::from(low)
+}
+
+/// The multiply-addition structure of IDEA, the round function of its Lai–Massey rounds. Its
+/// input is (X1 ⊕ X3, X2 ⊕ X4) after the key layer and its output (t1, t2) is XORed into both
+/// (X1, X2) and (X3, X4).
+pub struct MultiplyAdd {
+    k5: Secret<u16>,
+    k6: Secret<u16>
+}
+
+impl BlockFn for MultiplyAdd {
+    type Block = Secret<u32>;
+    fn encrypt(&self, half: Secret<u32>) -> Secret<u32> {
+        let (p, q) = split(half);
+        let t0 = multiply(p, self.k5);
+        let t1 = multiply(q.wrapping_add(t0), self.k6);
+        join(t1, t0.wrapping_add(t1))
+    }
+}
+
+impl Keyed for MultiplyAdd {
+    type Key = (Secret<u16>, Secret<u16>);
+    fn from_key(key: (Secret<u16>, Secret<u16>)) -> MultiplyAdd {
+        MultiplyAdd {
+            k5: key.0,
+            k6: key.1
+        }
+    }
+}
+
+// The key layer that starts each round and makes up the output transformation: multiplication
+// into X1 and X4 and addition into X2 and X3. Between rounds it first swaps X2 and X3. It keeps
+// the inverse keys for decryption, which undoes the layers in the opposite order rather than
+// rearranging the keys to reuse the encryption structure.
+struct KeyLayer {
+    swap: bool,
+    keys: [Secret<u16>; 4],
+    inverse_keys: [Secret<u16>; 4]
+}
+
+impl KeyLayer {
+    fn new(swap: bool, keys: &[Secret<u16>]) -> KeyLayer {
+        let zero = Secret::new(0);
+        KeyLayer {
+            swap: swap,
+            keys: [keys[0], keys[1], keys[2], keys[3]],
+            inverse_keys: [inverse(keys[0]), zero.wrapping_sub(keys[1]), zero.wrapping_sub(keys[2]), inverse(keys[3])]
+        }
+    }
+
+    fn swap(block: (Secret<u32>, Secret<u32>)) -> (Secret<u32>, Secret<u32>) {
+        let ((x1, x2), (x3, x4)) = (split(block.0), split(block.1));
+        (join(x1, x3), join(x2, x4))
+    }
+
+    fn apply(keys: &[Secret<u16>; 4], block: (Secret<u32>, Secret<u32>)) -> (Secret<u32>, Secret<u32>) {
+        let ((x1, x2), (x3, x4)) = (split(block.0), split(block.1));
+        (join(multiply(x1, keys[0]), x2.wrapping_add(keys[1])), join(x3.wrapping_add(keys[2]), multiply(x4, keys[3])))
+    }
+}
+
+impl BlockFn for KeyLayer {
+    type Block = (Secret<u32>, Secret<u32>);
+    fn encrypt(&self, block: (Secret<u32>, Secret<u32>)) -> (Secret<u32>, Secret<u32>) {
+        let block = if self.swap { KeyLayer::swap(block) } else { block };
+        KeyLayer::apply(&self.keys, block)
+    }
+}
+
+impl BlockCipher for KeyLayer {
+    fn decrypt(&self, block: (Secret<u32>, Secret<u32>)) -> (Secret<u32>, Secret<u32>) {
+        let block = KeyLayer::apply(&self.inverse_keys, block);
+        if self.swap { KeyLayer::swap(block) } else { block }
+    }
+}
+
+// A single Lai–Massey round, since a key layer follows each one
+type Round = LaiMassey<MultiplyAdd, Identity<Secret<u32>>, U1>;
+
+/// IDEA, from Lai and Massey, "A Proposal for a New Block Encryption Standard", as eight rounds of
+/// the Lai–Massey scheme on the halves (X1, X2) and (X3, X4), each after a key layer, and a final
+/// key layer. Blocks and keys are big-endian, as in the reference vectors.
+pub struct Idea {
+    first: KeyLayer,
+    rounds: Vec<(Round, KeyLayer)>
+}
+
+impl BlockFn for Idea {
+    type Block = Secret<u64>;
+    fn encrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let halves = self.first.encrypt(((block >> 32).truncate(), block.truncate()));
+        let (left, right) = self.rounds.iter().fold(halves, |halves, (round, layer)| {
+            layer.encrypt(round.encrypt(halves))
+        });
+        Secret::<u64>::from(left) << 32 | Secret::<u64>::from(right)
+    }
+}
+
+impl BlockCipher for Idea {
+    fn decrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let halves = ((block >> 32).truncate(), block.truncate());
+        let halves = self.rounds.iter().rev().fold(halves, |halves, (round, layer)| {
+            round.decrypt(layer.decrypt(halves))
+        });
+        let (left, right) = self.first.decrypt(halves);
+        Secret::<u64>::from(left) << 32 | Secret::<u64>::from(right)
+    }
+}
+
+impl Keyed for Idea {
+    type Key = Array<Secret<u8>, U16>;
+    fn from_key(key: Array<Secret<u8>, U16>) -> Idea {
+        // The 52 subkeys are the key's eight words, then the eight words of the key rotated left
+        // by 25 bits, and so on.
+        let mut key: Secret<u128> = key.iter().fold(Secret::new(0), |key, &byte| key << 8 | Secret::from(byte));
+        let mut subkeys = Vec::with_capacity(6 * ROUNDS + 8);
+        while subkeys.len() < 6 * ROUNDS + 4 {
+            for i in 0..8 {
+                let subkey: Secret<u16> = (key >> (112 - 16 * i)).truncate();
+                subkeys.push(subkey);
+            }
+            key = key.rotate_left(25);
+        }
+
+        Idea {
+            first: KeyLayer::new(false, &subkeys[0..4]),
+            rounds: (0..ROUNDS).map(|round| {
+                let ma_keys = (subkeys[6 * round + 4], subkeys[6 * round + 5]);
+                let next = &subkeys[6 * round + 6..6 * round + 10];
+                (LaiMassey::from_key(Array::from_fn(|_| ma_keys)), KeyLayer::new(round < ROUNDS - 1, next))
+            }).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Idea, inverse, multiply};
+
+    use array::Array;
+    use cipher::block::{BlockFn, BlockCipher};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+
+    use self::test::Bencher;
+
+    fn reference_multiply(a: u16, b: u16) -> u16 {
+        let widen = |x: u16| if x == 0 { 0x10000 } else { u64::from(x) };
+        (widen(a) * widen(b) % 0x10001) as u16
+    }
+
+    #[test]
+    fn multiplication() {
+        let values = [0, 1, 2, 3, 0x7FFF, 0x8000, 0x8001, 0xFFFE, 0xFFFF];
+        for &a in values.iter() {
+            for &b in values.iter() {
+                assert_eq!(multiply(Secret::new(a), Secret::new(b)).expose(), reference_multiply(a, b));
+            }
+        }
+        for a in 0..=0xFFFF {
+            assert_eq!(multiply(Secret::new(a), inverse(Secret::new(a))).expose(), 1);
+        }
+    }
+
+    fn check(key: &str, plaintext: u64, ciphertext: u64) {
+        let key = hex::decode(key);
+        let cipher = Idea::from_key(Array::from_fn(|i| Secret::new(key[i])));
+        assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+        assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+    }
+
+    #[test]
+    fn reference_vectors() {
+        // From Lai's thesis
+        check("00010002000300040005000600070008", 0x0000000100020003, 0x11FBED2B01986DE5);
+
+        // NESSIE set 1 vector 0, set 2 vector 0 and set 4 vector 0
+        check("80000000000000000000000000000000", 0x0000000000000000, 0xB1F5F7F87901370F);
+        check("00000000000000000000000000000000", 0x8000000000000000, 0x8001000180008000);
+        check("000102030405060708090A0B0C0D0E0F", 0x0011223344556677, 0xF526AB9A62C0D258);
+
+        // A random key, and an all-zero key whose subkeys are all 0, standing for 2^16
+        check("2BD6459F82C5B300952C49104881FF48", 0xEA024714AD5C4D84, 0xC8FB51D3516627A8);
+        check("00000000000000000000000000000000", 0x0000000000000000, 0x0001000100000000);
+    }
+
+    #[bench]
+    fn bench_encrypt(bencher: &mut Bencher) {
+        let cipher = Idea::from_key(Array::from_fn(|i| Secret::new(i as u8)));
+        let mut block = Secret::new(0);
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+use std::ops::BitXor;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use keyed::Keyed;
+use secret::Secret;
+
+/// An orthomorphism of the half-block group: a permutation σ such that x ↦ σ(x) ⊕ x is also a
+/// permutation. Vaudenay, "On the Lai-Massey Scheme", shows that without one, a round of the
+/// scheme leaves L ⊕ R unchanged and the whole cipher is easy to distinguish.
+pub trait Orthomorphism {
+    type HalfBlock;
+    fn apply(half: Self::HalfBlock) -> Self::HalfBlock;
+    fn invert(half: Self::HalfBlock) -> Self::HalfBlock;
+}
+
+/// The identity, for the original scheme of PES and IDEA, which break the invariant with layers
+/// around the rounds instead
+pub struct Identity<HalfBlock> {
+    _marker: PhantomData<HalfBlock>
+}
+
+impl<HalfBlock> Orthomorphism for Identity<HalfBlock> {
+    type HalfBlock = HalfBlock;
+    fn apply(half: HalfBlock) -> HalfBlock {
+        half
+    }
+
+    fn invert(half: HalfBlock) -> HalfBlock {
+        half
+    }
+}
+
+/// The orthomorphism of FOX, (a, b) ↦ (b, a ⊕ b) on the two halves of a word
+pub struct SwapXor<HalfBlock> {
+    _marker: PhantomData<HalfBlock>
+}
+
+macro_rules! swap_xor_impl {
+    { $t:ty, $half:expr } => {
+        impl Orthomorphism for SwapXor<Secret<$t>> {
+            type HalfBlock = Secret<$t>;
+            fn apply(half: Secret<$t>) -> Secret<$t> {
+                let (a, b) = (half >> $half, half << $half >> $half);
+                b << $half | (a ^ b)
+            }
+
+            fn invert(half: Secret<$t>) -> Secret<$t> {
+                let (b, a_xor_b) = (half >> $half, half << $half >> $half);
+                (a_xor_b ^ b) << $half | b
+            }
+        }
+    };
+}
+
+swap_xor_impl! { u32, 16 }
+swap_xor_impl! { u64, 32 }
+
+/// The Lai–Massey scheme, which like a Feistel network makes a cipher out of a keyed function
+/// that need not be invertible. Each round computes t = F(L ⊕ R) and outputs (σ(L ⊕ t), R ⊕ t), and
+/// since L ⊕ R survives the round up to σ, decryption can recompute t.
+pub struct LaiMassey<RoundFn, Sigma, Rounds: ArrayLength<RoundFn>> {
+    rounds: Array<RoundFn, Rounds>,
+    _marker: PhantomData<Sigma>
+}
+
+impl<HalfBlock, RoundFn: BlockFn<Block=HalfBlock>, Sigma: Orthomorphism<HalfBlock=HalfBlock>, Rounds: ArrayLength<RoundFn>> BlockFn for LaiMassey<RoundFn, Sigma, Rounds> where HalfBlock: Copy + BitXor<HalfBlock, Output=HalfBlock> {
+    type Block = (HalfBlock, HalfBlock);
+
+    fn encrypt(&self, block: Self::Block) -> Self::Block {
+        let (mut left, mut right) = block;
+
+        for round in self.rounds.iter() {
+            let t = round.encrypt(left ^ right);
+            left = Sigma::apply(left ^ t);
+            right = right ^ t;
+        }
+
+        (left, right)
+    }
+}
+
+impl<HalfBlock, RoundFn: BlockFn<Block=HalfBlock>, Sigma: Orthomorphism<HalfBlock=HalfBlock>, Rounds: ArrayLength<RoundFn>> BlockCipher for LaiMassey<RoundFn, Sigma, Rounds> where HalfBlock: Copy + BitXor<HalfBlock, Output=HalfBlock> {
+    fn decrypt(&self, block: Self::Block) -> Self::Block {
+        let (mut left, mut right) = block;
+
+        for round in self.rounds.iter().rev() {
+            left = Sigma::invert(left);
+            let t = round.encrypt(left ^ right);
+            left = left ^ t;
+            right = right ^ t;
+        }
+
+        (left, right)
+    }
+}
+
+impl<RoundFn: Keyed, Sigma, Rounds: ArrayLength<RoundFn> + ArrayLength<RoundFn::Key>> Keyed for LaiMassey<RoundFn, Sigma, Rounds> {
+    type Key = Array<RoundFn::Key, Rounds>;
+    fn from_key(key: Self::Key) -> Self {
+        LaiMassey {
+            rounds: key.map(RoundFn::from_key),
+            _marker: PhantomData
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Identity, LaiMassey, Orthomorphism, SwapXor};
+
+    use array::Array;
+    use cipher::block::{BlockFn, BlockCipher};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::traits::rotate::RotateLeft;
+    use utils::traits::wrapping::WrappingMul;
+
+    use typenum::consts::U8;
+
+    // A cheap round function that is far from invertible
+    struct Squash {
+        key: Secret<u64>
+    }
+
+    impl BlockFn for Squash {
+        type Block = Secret<u64>;
+        fn encrypt(&self, half: Secret<u64>) -> Secret<u64> {
+            (half ^ self.key).wrapping_mul(half | Secret::new(1)).rotate_left(29) & Secret::new(0xFFFF00FF00FFFFFF)
+        }
+    }
+
+    impl Keyed for Squash {
+        type Key = Secret<u64>;
+        fn from_key(key: Secret<u64>) -> Squash {
+            Squash {
+                key: key
+            }
+        }
+    }
+
+    #[test]
+    fn swap_xor() {
+        type Sigma32 = SwapXor<Secret<u32>>;
+        let x = Secret::new(0x12345678);
+        assert_eq!(Sigma32::apply(x).expose(), 0x5678444C);
+        assert_eq!(Sigma32::invert(Sigma32::apply(x)).expose(), 0x12345678);
+
+        type Sigma64 = SwapXor<Secret<u64>>;
+        let x = Secret::new(0x0123456789ABCDEF);
+        assert_eq!(Sigma64::apply(x).expose(), 0x89ABCDEF88888888);
+        assert_eq!(Sigma64::invert(Sigma64::apply(x)).expose(), 0x0123456789ABCDEF);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let key = || Array::from_fn(|i| Secret::new(0x9E3779B97F4A7C15u64.wrapping_mul(i as u64 + 1)));
+        let plain: LaiMassey<Squash, Identity<Secret<u64>>, U8> = LaiMassey::from_key(key());
+        let fox_like: LaiMassey<Squash, SwapXor<Secret<u64>>, U8> = LaiMassey::from_key(key());
+        for &(left, right) in [(0, 0), (1, 2), (0xFFFFFFFFFFFFFFFF, 0x0123456789ABCDEF)].iter() {
+            let block = (Secret::new(left), Secret::new(right));
+
+            let (l, r) = plain.decrypt(plain.encrypt(block));
+            assert_eq!((l.expose(), r.expose()), (left, right));
+            let (l, r) = fox_like.decrypt(fox_like.encrypt(block));
+            assert_eq!((l.expose(), r.expose()), (left, right));
+
+            // Without an orthomorphism, L ⊕ R is invariant.
+            let (l, r) = plain.encrypt(block);
+            assert_eq!((l ^ r).expose(), left ^ right);
+        }
+    }
+}
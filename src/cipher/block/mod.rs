@@ -1,5 +1,24 @@
 pub mod feistel;
+pub mod lai_massey;
+pub mod spn;
 pub mod des;
+pub mod aes;
+pub mod blowfish;
+pub mod twofish;
+pub mod camellia;
+pub mod serpent;
+pub mod tea;
+pub mod simon;
+pub mod speck;
+pub mod rc5;
+pub mod rc6;
+pub mod idea;
+pub mod present;
+pub mod gift;
+pub mod backend;
+pub mod sbox;
+pub mod word;
+pub mod even_mansour;
 
 pub trait BlockFn {
     type Block: Clone;
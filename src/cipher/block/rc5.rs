@@ -0,0 +1,219 @@
+use std::cmp;
+use std::marker::PhantomData;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::word::Word;
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::rotate::{RotateLeft, RotateRight};
+use utils::traits::truncate::Truncate;
+
+use typenum::consts::{U12, U16};
+use typenum::uint::Unsigned;
+
+/// A word size that RC5 and RC6 are defined for. Both rotate by amounts taken from the data, so
+/// words also need the constant-time rotations by a secret amount.
+pub trait Rc5Word: Word + RotateLeft<Secret<u32>, Output=Self> + RotateRight<Secret<u32>, Output=Self> {
+    /// The magic constants P_w and Q_w, from e and the golden ratio
+    fn magic() -> (u64, u64);
+}
+
+impl Rc5Word for Secret<u16> {
+    fn magic() -> (u64, u64) {
+        (0xB7E1, 0x9E37)
+    }
+}
+
+impl Rc5Word for Secret<u32> {
+    fn magic() -> (u64, u64) {
+        (0xB7E15163, 0x9E3779B9)
+    }
+}
+
+impl Rc5Word for Secret<u64> {
+    fn magic() -> (u64, u64) {
+        (0xB7E151628AED2A6B, 0x9E3779B97F4A7C15)
+    }
+}
+
+/// The rotation amount given by a word. Only the low bits matter, and the rotations take the
+/// amount modulo the width themselves.
+pub(crate) fn amount<W: Word>(word: W) -> Secret<u32> {
+    word.to_u64().truncate()
+}
+
+/// The key expansion shared by RC5 and RC6, filling a table of `t` words from a key of up to 255
+/// bytes.
+pub(crate) fn expand_key<W: Rc5Word>(key: &[Secret<u8>], t: usize) -> Vec<W> {
+    assert!(key.len() <= 255, "RC5 and RC6 keys are at most 255 bytes long");
+
+    // The key as little-endian words, with at least one word even for an empty key
+    let bytes_per_word = W::bits() as usize / 8;
+    let mut l: Vec<W> = key.chunks(bytes_per_word).map(|chunk| {
+        W::from_u64(chunk.iter().rev().fold(Secret::new(0), |word, &byte| word << 8 | Secret::<u64>::from(byte)))
+    }).collect();
+    if l.is_empty() {
+        l.push(W::from_u64(Secret::new(0)));
+    }
+
+    let (p, q) = W::magic();
+    let q = W::from_u64(Secret::new(q));
+    let mut s = vec![W::from_u64(Secret::new(p))];
+    while s.len() < t {
+        let last = s[s.len() - 1];
+        s.push(last.wrapping_add(q));
+    }
+
+    let c = l.len();
+    let mut a = W::from_u64(Secret::new(0));
+    let mut b = a;
+    for k in 0..3 * cmp::max(t, c) {
+        a = s[k % t].wrapping_add(a).wrapping_add(b).rotate_left(3u32);
+        s[k % t] = a;
+        let sum = a.wrapping_add(b);
+        b = l[k % c].wrapping_add(sum).rotate_left(amount(sum));
+        l[k % c] = b;
+    }
+    s
+}
+
+/// RC5-w/r/b from Rivest, "The RC5 Encryption Algorithm", on words of type `W` with `Rounds`
+/// rounds and a key of `KeyBytes` bytes.
+///
+/// Blocks are the pair of words (A, B), which the byte-oriented descriptions load little-endian.
+pub struct Rc5<W, Rounds, KeyBytes> {
+    s: Vec<W>,
+    _marker: PhantomData<(Rounds, KeyBytes)>
+}
+
+/// The parameters Rivest suggested as a default
+pub type Rc5_32_12_16 = Rc5<Secret<u32>, U12, U16>;
+
+impl<W: Rc5Word, Rounds: Unsigned, KeyBytes> BlockFn for Rc5<W, Rounds, KeyBytes> {
+    type Block = [W; 2];
+    fn encrypt(&self, block: [W; 2]) -> [W; 2] {
+        let mut a = block[0].wrapping_add(self.s[0]);
+        let mut b = block[1].wrapping_add(self.s[1]);
+        for i in 1..Rounds::to_usize() + 1 {
+            a = (a ^ b).rotate_left(amount(b)).wrapping_add(self.s[2 * i]);
+            b = (b ^ a).rotate_left(amount(a)).wrapping_add(self.s[2 * i + 1]);
+        }
+        [a, b]
+    }
+}
+
+impl<W: Rc5Word, Rounds: Unsigned, KeyBytes> BlockCipher for Rc5<W, Rounds, KeyBytes> {
+    fn decrypt(&self, block: [W; 2]) -> [W; 2] {
+        let [mut a, mut b] = block;
+        for i in (1..Rounds::to_usize() + 1).rev() {
+            b = b.wrapping_sub(self.s[2 * i + 1]).rotate_right(amount(a)) ^ a;
+            a = a.wrapping_sub(self.s[2 * i]).rotate_right(amount(b)) ^ b;
+        }
+        [a.wrapping_sub(self.s[0]), b.wrapping_sub(self.s[1])]
+    }
+}
+
+impl<W: Rc5Word, Rounds: Unsigned, KeyBytes: ArrayLength<Secret<u8>>> Keyed for Rc5<W, Rounds, KeyBytes> {
+    type Key = Array<Secret<u8>, KeyBytes>;
+    fn from_key(key: Array<Secret<u8>, KeyBytes>) -> Rc5<W, Rounds, KeyBytes> {
+        Rc5 {
+            s: expand_key(&key, 2 * Rounds::to_usize() + 2),
+            _marker: PhantomData
+        }
+    }
+}
+
+// Little-endian conversions between bytes and words, for the RC5 and RC6 tests
+#[cfg(test)]
+pub(crate) fn to_words<W: Rc5Word>(bytes: &[u8]) -> Vec<W> {
+    bytes.chunks(W::bits() as usize / 8).map(|chunk| {
+        W::from_u64(Secret::new(chunk.iter().rev().fold(0, |word, &byte| word << 8 | u64::from(byte))))
+    }).collect()
+}
+
+#[cfg(test)]
+pub(crate) fn from_words<W: Rc5Word>(words: &[W]) -> Vec<u8> {
+    words.iter().flat_map(|word| {
+        let word = word.to_u64().expose();
+        (0..W::bits() / 8).map(move |i| (word >> (8 * i)) as u8)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Rc5, Rc5Word, Rc5_32_12_16, from_words, to_words};
+
+    use array::{Array, ArrayLength};
+    use cipher::block::{BlockFn, BlockCipher};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+
+    use typenum::consts::{U0, U1, U2, U4, U5, U8, U12, U16, U20, U24};
+    use typenum::uint::Unsigned;
+
+    use self::test::Bencher;
+
+    fn check<W: Rc5Word, Rounds: Unsigned, KeyBytes: ArrayLength<Secret<u8>>>(key: &str, plaintext: &str, ciphertext: &str) {
+        let key = hex::decode(key);
+        let cipher = Rc5::<W, Rounds, KeyBytes>::from_key(Array::from_fn(|i| Secret::new(key[i])));
+        let plaintext = to_words::<W>(&hex::decode(plaintext));
+        let encrypted = cipher.encrypt([plaintext[0], plaintext[1]]);
+        assert_eq!(from_words(&encrypted), hex::decode(ciphertext));
+        let decrypted = cipher.decrypt(encrypted);
+        assert_eq!(from_words(&decrypted), from_words(&plaintext));
+    }
+
+    // The examples at the end of Rivest's paper, where each plaintext is the previous ciphertext
+    #[test]
+    fn rc5_32_12_16() {
+        check::<Secret<u32>, U12, U16>("00000000000000000000000000000000", "0000000000000000", "21A5DBEE154B8F6D");
+        check::<Secret<u32>, U12, U16>("915F4619BE41B2516355A50110A9CE91", "21A5DBEE154B8F6D", "F7C013AC5B2B8952");
+        check::<Secret<u32>, U12, U16>("783348E75AEB0F2FD7B169BB8DC16787", "F7C013AC5B2B8952", "2F42B3B70369FC92");
+        check::<Secret<u32>, U12, U16>("DC49DB1375A5584F6485B413B5F12BAF", "2F42B3B70369FC92", "65C178B284D197CC");
+        check::<Secret<u32>, U12, U16>("5269F149D41BA0152497574D7F153125", "65C178B284D197CC", "EB44E415DA319824");
+    }
+
+    // The RC5-CBC vectors from RFC 2040 are single blocks, so each plaintext here is the RFC's
+    // plaintext XORed with its IV.
+    #[test]
+    fn rfc2040() {
+        check::<Secret<u32>, U0, U1>("00", "0000000000000000", "7A7BBA4D79111D1E");
+        check::<Secret<u32>, U0, U1>("00", "FFFFFFFFFFFFFFFF", "797BBA4D78111D1E");
+        check::<Secret<u32>, U0, U1>("00", "0000000000000001", "7A7BBA4D79111D1F");
+        check::<Secret<u32>, U0, U1>("00", "1122334455667788", "8B9DED91CE7794A6");
+        check::<Secret<u32>, U1, U1>("11", "0000000000000000", "2F759FE7AD86A378");
+        check::<Secret<u32>, U2, U1>("00", "0000000000000000", "DCA2694BF40E0788");
+        check::<Secret<u32>, U2, U4>("00000000", "0000000000000000", "DCA2694BF40E0788");
+        check::<Secret<u32>, U8, U1>("00", "1122334455667788", "9646FB77638F9CA8");
+        check::<Secret<u32>, U12, U1>("00", "1122334455667788", "B2B3209DB6594DA4");
+        check::<Secret<u32>, U8, U8>("0102030405060708", "1122334455667788", "5C4C041E0F217AC3");
+        check::<Secret<u32>, U12, U8>("0102030405060708", "1122334455667788", "921F12485373B4F7");
+        check::<Secret<u32>, U16, U8>("0102030405060708", "1122334455667788", "5BA0CA6BBE7F5FAD");
+        check::<Secret<u32>, U8, U16>("01020304050607081020304050607080", "1122334455667788", "C533771CD0110E63");
+        check::<Secret<u32>, U12, U16>("01020304050607081020304050607080", "1122334455667788", "294DDB46B3278D60");
+        check::<Secret<u32>, U12, U5>("0102030405", "FFFFFFFFFFFFFFFF", "97E0787837ED317F");
+    }
+
+    // Other word sizes, from Krovetz's RC5 and RC6 test vectors
+    #[test]
+    fn other_word_sizes() {
+        check::<Secret<u16>, U16, U8>("0001020304050607", "00010203", "23A8D72E");
+        check::<Secret<u32>, U20, U16>("000102030405060708090A0B0C0D0E0F", "0001020304050607", "2A0EDC0E9431FF73");
+        check::<Secret<u64>, U24, U24>("000102030405060708090A0B0C0D0E0F1011121314151617",
+                                       "000102030405060708090A0B0C0D0E0F", "A46772820EDBCE0235ABEA32AE7178DA");
+    }
+
+    #[bench]
+    fn bench_encrypt(bencher: &mut Bencher) {
+        let cipher = Rc5_32_12_16::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = [Secret::new(0); 2];
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
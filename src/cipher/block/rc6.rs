@@ -0,0 +1,150 @@
+use std::marker::PhantomData;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::rc5::{Rc5Word, amount, expand_key};
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::wrapping::WrappingMul;
+
+use typenum::consts::{U16, U20, U24, U32};
+use typenum::uint::Unsigned;
+
+// f(x) = x(2x + 1) rotated left by lg w bits
+fn f<W: Rc5Word + WrappingMul<W, Output=W>>(x: W) -> W {
+    let one = W::from_u64(Secret::new(1));
+    x.wrapping_mul(x.wrapping_add(x).wrapping_add(one)).rotate_left(W::bits().trailing_zeros())
+}
+
+/// RC6-w/r/b from Rivest, Robshaw, Sidney and Yin, "The RC6 Block Cipher", on words of type `W`
+/// with `Rounds` rounds and a key of `KeyBytes` bytes. It shares RC5's key schedule and its
+/// rotations by data-dependent amounts.
+///
+/// Blocks are the four words (A, B, C, D), which the byte-oriented descriptions load
+/// little-endian.
+pub struct Rc6<W, Rounds, KeyBytes> {
+    s: Vec<W>,
+    _marker: PhantomData<(Rounds, KeyBytes)>
+}
+
+/// The AES candidate, RC6-32/20/b with b = 16, 24 or 32
+pub type Rc6_128 = Rc6<Secret<u32>, U20, U16>;
+pub type Rc6_192 = Rc6<Secret<u32>, U20, U24>;
+pub type Rc6_256 = Rc6<Secret<u32>, U20, U32>;
+
+impl<W: Rc5Word + WrappingMul<W, Output=W>, Rounds: Unsigned, KeyBytes> BlockFn for Rc6<W, Rounds, KeyBytes> {
+    type Block = [W; 4];
+    fn encrypt(&self, block: [W; 4]) -> [W; 4] {
+        let rounds = Rounds::to_usize();
+        let [mut a, mut b, mut c, mut d] = block;
+        b = b.wrapping_add(self.s[0]);
+        d = d.wrapping_add(self.s[1]);
+        for i in 1..rounds + 1 {
+            let t = f(b);
+            let u = f(d);
+            let new_d = (a ^ t).rotate_left(amount(u)).wrapping_add(self.s[2 * i]);
+            a = b;
+            b = (c ^ u).rotate_left(amount(t)).wrapping_add(self.s[2 * i + 1]);
+            c = d;
+            d = new_d;
+        }
+        [a.wrapping_add(self.s[2 * rounds + 2]), b, c.wrapping_add(self.s[2 * rounds + 3]), d]
+    }
+}
+
+impl<W: Rc5Word + WrappingMul<W, Output=W>, Rounds: Unsigned, KeyBytes> BlockCipher for Rc6<W, Rounds, KeyBytes> {
+    fn decrypt(&self, block: [W; 4]) -> [W; 4] {
+        let rounds = Rounds::to_usize();
+        let [mut a, mut b, mut c, mut d] = block;
+        c = c.wrapping_sub(self.s[2 * rounds + 3]);
+        a = a.wrapping_sub(self.s[2 * rounds + 2]);
+        for i in (1..rounds + 1).rev() {
+            let u = f(c);
+            let t = f(a);
+            let new_a = d.wrapping_sub(self.s[2 * i]).rotate_right(amount(u)) ^ t;
+            d = c;
+            c = b.wrapping_sub(self.s[2 * i + 1]).rotate_right(amount(t)) ^ u;
+            b = a;
+            a = new_a;
+        }
+        [a, b.wrapping_sub(self.s[0]), c, d.wrapping_sub(self.s[1])]
+    }
+}
+
+impl<W: Rc5Word, Rounds: Unsigned, KeyBytes: ArrayLength<Secret<u8>>> Keyed for Rc6<W, Rounds, KeyBytes> {
+    type Key = Array<Secret<u8>, KeyBytes>;
+    fn from_key(key: Array<Secret<u8>, KeyBytes>) -> Rc6<W, Rounds, KeyBytes> {
+        Rc6 {
+            s: expand_key(&key, 2 * Rounds::to_usize() + 4),
+            _marker: PhantomData
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Rc6, Rc6_128};
+
+    use array::{Array, ArrayLength};
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::rc5::{Rc5Word, from_words, to_words};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+    use utils::traits::wrapping::WrappingMul;
+
+    use typenum::consts::{U8, U16, U20, U24, U32};
+    use typenum::uint::Unsigned;
+
+    use self::test::Bencher;
+
+    fn check<W, Rounds, KeyBytes>(key: &str, plaintext: &str, ciphertext: &str)
+        where W: Rc5Word + WrappingMul<W, Output=W>, Rounds: Unsigned, KeyBytes: ArrayLength<Secret<u8>>
+    {
+        let key = hex::decode(key);
+        let cipher = Rc6::<W, Rounds, KeyBytes>::from_key(Array::from_fn(|i| Secret::new(key[i])));
+        let plaintext = to_words::<W>(&hex::decode(plaintext));
+        let encrypted = cipher.encrypt([plaintext[0], plaintext[1], plaintext[2], plaintext[3]]);
+        assert_eq!(from_words(&encrypted), hex::decode(ciphertext));
+        let decrypted = cipher.decrypt(encrypted);
+        assert_eq!(from_words(&decrypted), from_words(&plaintext));
+    }
+
+    // The test vectors from the AES submission
+    #[test]
+    fn submission_vectors() {
+        check::<Secret<u32>, U20, U16>("00000000000000000000000000000000",
+                                       "00000000000000000000000000000000", "8FC3A53656B1F778C129DF4E9848A41E");
+        check::<Secret<u32>, U20, U16>("0123456789ABCDEF0112233445566778",
+                                       "02132435465768798A9BACBDCEDFE0F1", "524E192F4715C6231F51F6367EA43F18");
+        check::<Secret<u32>, U20, U24>("000000000000000000000000000000000000000000000000",
+                                       "00000000000000000000000000000000", "6CD61BCB190B30384E8A3F168690AE82");
+        check::<Secret<u32>, U20, U24>("0123456789ABCDEF0112233445566778899AABBCCDDEEFF0",
+                                       "02132435465768798A9BACBDCEDFE0F1", "688329D019E505041E52E92AF95291D4");
+        check::<Secret<u32>, U20, U32>("0000000000000000000000000000000000000000000000000000000000000000",
+                                       "00000000000000000000000000000000", "8F5FBD0510D15FA893FA3FDA6E857EC2");
+        check::<Secret<u32>, U20, U32>("0123456789ABCDEF0112233445566778899AABBCCDDEEFF01032547698BADCFE",
+                                       "02132435465768798A9BACBDCEDFE0F1", "C8241816F0D7E48920AD16A1674E5D48");
+    }
+
+    // Other word sizes, from Krovetz's RC5 and RC6 test vectors
+    #[test]
+    fn other_word_sizes() {
+        check::<Secret<u16>, U16, U8>("0001020304050607", "0001020304050607", "2FF0B68EAEFFAD5B");
+        check::<Secret<u64>, U24, U24>("000102030405060708090A0B0C0D0E0F1011121314151617",
+                                       "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F",
+                                       "C002DE050BD55E5D36864AB9853338E6DC4A1326C6BDAAEB1BC9E4FD67886617");
+    }
+
+    #[bench]
+    fn bench_encrypt(bencher: &mut Bencher) {
+        let cipher = Rc6_128::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = [Secret::new(0); 4];
+        bencher.bytes = 16;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
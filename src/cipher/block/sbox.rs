@@ -0,0 +1,104 @@
+use std::ops::{BitAnd, BitOr, BitXorAssign, Not, Shl, Shr};
+
+use secret::Secret;
+use utils::traits::wrapping::WrappingSub;
+
+/// A secret integer that can be held in a table read through `TableAccess`
+pub trait TableEntry: Copy + BitAnd<Output=Self> + BitOr<Output=Self> + Not<Output=Self> + Shr<u32, Output=Self> +
+                      WrappingSub<Self, Output=Self> + From<Secret<u8>> {
+    const BITS: u32;
+}
+
+impl TableEntry for Secret<u8> {
+    const BITS: u32 = 8;
+}
+
+impl TableEntry for Secret<u32> {
+    const BITS: u32 = 32;
+}
+
+/// All ones if `value` is `index`, and zero otherwise, without branching on `value` or using it as
+/// an index
+pub fn equal_mask<T: TableEntry>(value: Secret<u8>, index: u8) -> T {
+    // The top bit of d | -d is set unless d is 0, so it is 1 exactly when the two differ.
+    let difference = T::from(value ^ index);
+    let differs = (difference | T::from(Secret::new(0)).wrapping_sub(difference)) >> (T::BITS - 1);
+    differs.wrapping_sub(T::from(Secret::new(1)))
+}
+
+/// How a cipher reads tables that depend on the key, such as Blowfish's S-boxes or the RC4 state,
+/// at secret positions
+pub trait TableAccess {
+    fn lookup<T: TableEntry>(table: &[T; 256], index: Secret<u8>) -> T;
+    fn write<T: TableEntry>(table: &mut [T; 256], index: Secret<u8>, value: T);
+}
+
+/// Scans the whole table on every access, masking out all entries except the one wanted, so
+/// that the memory access pattern does not depend on any secrets.
+pub struct Masked;
+
+/// Indexes the tables directly. This is far faster than `Masked`, but the secret indices leak
+/// through the cache.
+pub struct Indexed;
+
+impl TableAccess for Masked {
+    fn lookup<T: TableEntry>(table: &[T; 256], index: Secret<u8>) -> T {
+        let mut out = T::from(Secret::new(0));
+        for (i, &entry) in table.iter().enumerate() {
+            out = out | (equal_mask::<T>(index, i as u8) & entry);
+        }
+        out
+    }
+
+    fn write<T: TableEntry>(table: &mut [T; 256], index: Secret<u8>, value: T) {
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mask = equal_mask::<T>(index, i as u8);
+            *entry = (*entry & !mask) | (value & mask);
+        }
+    }
+}
+
+impl TableAccess for Indexed {
+    fn lookup<T: TableEntry>(table: &[T; 256], index: Secret<u8>) -> T {
+        table[index.expose() as usize]
+    }
+
+    fn write<T: TableEntry>(table: &mut [T; 256], index: Secret<u8>, value: T) {
+        table[index.expose() as usize] = value;
+    }
+}
+
+/// Applies a 4-bit S-box to every column of `x` at once, where bit i of a column is taken from
+/// x[i] and `ones` has every column set. Bit s of `anf[j]` is set when output bit j of the S-box
+/// includes the product of the input bits in s, so this is a circuit of ANDs and XORs: every
+/// product of the inputs, then the XOR of the products that each output needs.
+pub fn substitute_anf<T>(anf: &[u16; 4], x: [T; 4], ones: T) -> [T; 4]
+    where T: Copy + BitAnd<T, Output=T> + BitXorAssign<T> + Not<Output=T>
+{
+    let mut products = [ones; 16];
+    for subset in 1..16usize {
+        let lowest = subset.trailing_zeros() as usize;
+        products[subset] = products[subset & (subset - 1)] & x[lowest];
+    }
+
+    let zero = ones & !ones;
+    let mut y = [zero; 4];
+    for (out, &coefficients) in y.iter_mut().zip(anf.iter()) {
+        for (subset, &product) in products.iter().enumerate() {
+            if coefficients >> subset & 1 == 1 {
+                *out ^= product;
+            }
+        }
+    }
+    y
+}
+
+/// Applies a 4-bit S-box, given as for `substitute_anf`, to the nibbles of `x` whose lowest bit is
+/// set in `low_bits`. Every other bit of the result is 0.
+pub fn substitute_nibbles<T>(anf: &[u16; 4], x: T, low_bits: T) -> T
+    where T: Copy + BitAnd<T, Output=T> + BitOr<T, Output=T> + BitXorAssign<T> + Not<Output=T> + Shl<u32, Output=T> +
+             Shr<u32, Output=T>
+{
+    let y = substitute_anf(anf, [x & low_bits, x >> 1 & low_bits, x >> 2 & low_bits, x >> 3 & low_bits], low_bits);
+    y[0] | y[1] << 1 | y[2] << 2 | y[3] << 3
+}
@@ -0,0 +1,285 @@
+use std::marker::PhantomData;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::sbox;
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::rotate::{RotateLeft, RotateRight};
+use utils::traits::truncate::Truncate;
+
+use typenum::consts::{U16, U24, U32};
+
+const ROUNDS: usize = 32;
+
+// The fractional part of the golden ratio, used in the key schedule
+const PHI: u32 = 0x9E3779B9;
+
+// The S-boxes S0 to S7 in algebraic normal form. Bit s of `ANF[i][j]` is set when output bit j
+// of Si includes the product of the input bits in s, where input bit 0 is the least significant.
+const ANF: [[u16; 4]; 8] = [
+    [0x61FB, 0x64E3, 0x45AC, 0x0316],
+    [0x7247, 0x6D3B, 0x011D, 0x6B25],
+    [0x0134, 0x3AD6, 0x3D46, 0x0497],
+    [0x7346, 0x3A26, 0x0D9A, 0x31BE],
+    [0x071D, 0x7562, 0x5CDA, 0x0E56],
+    [0x071D, 0x1D1B, 0x7925, 0x2397],
+    [0x49F7, 0x0215, 0x5CDB, 0x51BC],
+    [0x7619, 0x2B7C, 0x4F96, 0x02B6]
+];
+
+// The same for the inverse S-boxes
+const INVERSE_ANF: [[u16; 4]; 8] = [
+    [0x7E59, 0x6436, 0x011F, 0x7943],
+    [0x648F, 0x6794, 0x21E7, 0x0512],
+    [0x0456, 0x3A1C, 0x2F1B, 0x21C9],
+    [0x4752, 0x63D4, 0x3E68, 0x1AB6],
+    [0x3B17, 0x2338, 0x0DBF, 0x1A1C],
+    [0x0942, 0x0BE6, 0x2C1A, 0x029D],
+    [0x49EB, 0x0135, 0x5C47, 0x5BDD],
+    [0x5C47, 0x6753, 0x3924, 0x0E98]
+];
+
+// The S-boxes as tables, from Anderson, Biham and Knudsen, "Serpent: A Proposal for the Advanced
+// Encryption Standard"
+#[cfg(test)]
+const SBOX: [[u8; 16]; 8] = [
+    [3, 8, 15, 1, 10, 6, 5, 11, 14, 13, 4, 2, 7, 0, 9, 12],
+    [15, 12, 2, 7, 9, 0, 5, 10, 1, 11, 14, 8, 6, 13, 3, 4],
+    [8, 6, 7, 9, 3, 12, 10, 15, 13, 1, 14, 4, 0, 11, 5, 2],
+    [0, 15, 11, 8, 12, 9, 6, 3, 13, 1, 2, 4, 10, 7, 5, 14],
+    [1, 15, 8, 3, 12, 0, 11, 6, 2, 5, 4, 10, 9, 14, 7, 13],
+    [15, 5, 2, 11, 4, 10, 9, 12, 0, 3, 14, 8, 13, 6, 7, 1],
+    [7, 2, 12, 5, 8, 4, 6, 11, 14, 9, 1, 15, 13, 3, 10, 0],
+    [1, 13, 15, 0, 14, 8, 2, 11, 7, 4, 12, 10, 9, 3, 5, 6]
+];
+
+type Words = [Secret<u32>; 4];
+
+// Applies an S-box to all 32 columns of `x` at once, where bit i of column c is bit c of x[i]
+fn substitute(anf: &[u16; 4], x: Words) -> Words {
+    sbox::substitute_anf(anf, x, Secret::new(!0))
+}
+
+fn linear_transform(x: Words) -> Words {
+    let [mut x0, mut x1, mut x2, mut x3] = x;
+    x0 = x0.rotate_left(13);
+    x2 = x2.rotate_left(3);
+    x1 ^= x0 ^ x2;
+    x3 ^= x2 ^ (x0 << 3);
+    x1 = x1.rotate_left(1);
+    x3 = x3.rotate_left(7);
+    x0 ^= x1 ^ x3;
+    x2 ^= x3 ^ (x1 << 7);
+    x0 = x0.rotate_left(5);
+    x2 = x2.rotate_left(22);
+    [x0, x1, x2, x3]
+}
+
+fn inverse_linear_transform(x: Words) -> Words {
+    let [mut x0, mut x1, mut x2, mut x3] = x;
+    x2 = x2.rotate_right(22);
+    x0 = x0.rotate_right(5);
+    x2 ^= x3 ^ (x1 << 7);
+    x0 ^= x1 ^ x3;
+    x3 = x3.rotate_right(7);
+    x1 = x1.rotate_right(1);
+    x3 ^= x2 ^ (x0 << 3);
+    x1 ^= x0 ^ x2;
+    x2 = x2.rotate_right(3);
+    x0 = x0.rotate_right(13);
+    [x0, x1, x2, x3]
+}
+
+fn xor(x: Words, key: &Words) -> Words {
+    [x[0] ^ key[0], x[1] ^ key[1], x[2] ^ key[2], x[3] ^ key[3]]
+}
+
+// Blocks and keys are byte strings made of little-endian words, as in NESSIE and most
+// implementations other than the reference code of the AES submission.
+fn to_words(block: Secret<u128>) -> Words {
+    let mut words = [Secret::new(0); 4];
+    for (i, word) in words.iter_mut().enumerate() {
+        for j in 0..4 {
+            let byte: Secret<u8> = (block >> (120 - 8 * (4 * i + j) as u32)).truncate();
+            *word |= Secret::<u32>::from(byte) << (8 * j as u32);
+        }
+    }
+    words
+}
+
+fn from_words(words: Words) -> Secret<u128> {
+    let mut block = Secret::new(0);
+    for &word in words.iter() {
+        for j in 0..4 {
+            let byte: Secret<u8> = (word >> (8 * j as u32)).truncate();
+            block = (block << 8) | Secret::<u128>::from(byte);
+        }
+    }
+    block
+}
+
+/// The Serpent block cipher, with a key of `KeyBytes` bytes.
+///
+/// This is the bitsliced form from the AES submission, without the initial and final
+/// permutations: the four words of the block are the inputs of 32 S-boxes at once, so nothing is
+/// ever looked up in a table. Blocks are big-endian `u128`s of the byte strings in the NESSIE
+/// test vectors.
+pub struct Serpent<KeyBytes> {
+    round_keys: Vec<Words>,
+    _marker: PhantomData<KeyBytes>
+}
+
+pub type Serpent128 = Serpent<U16>;
+pub type Serpent192 = Serpent<U24>;
+pub type Serpent256 = Serpent<U32>;
+
+impl<KeyBytes> BlockFn for Serpent<KeyBytes> {
+    type Block = Secret<u128>;
+    fn encrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        let mut x = to_words(block);
+        for round in 0..ROUNDS {
+            x = substitute(&ANF[round % 8], xor(x, &self.round_keys[round]));
+            x = if round < ROUNDS - 1 {
+                linear_transform(x)
+            } else {
+                xor(x, &self.round_keys[ROUNDS])
+            };
+        }
+        from_words(x)
+    }
+}
+
+impl<KeyBytes> BlockCipher for Serpent<KeyBytes> {
+    fn decrypt(&self, block: Secret<u128>) -> Secret<u128> {
+        let mut x = to_words(block);
+        for round in (0..ROUNDS).rev() {
+            x = if round < ROUNDS - 1 {
+                inverse_linear_transform(x)
+            } else {
+                xor(x, &self.round_keys[ROUNDS])
+            };
+            x = xor(substitute(&INVERSE_ANF[round % 8], x), &self.round_keys[round]);
+        }
+        from_words(x)
+    }
+}
+
+impl<KeyBytes: ArrayLength<Secret<u8>>> Keyed for Serpent<KeyBytes> {
+    type Key = Array<Secret<u8>, KeyBytes>;
+    fn from_key(key: Array<Secret<u8>, KeyBytes>) -> Serpent<KeyBytes> {
+        assert!(key.len() == 16 || key.len() == 24 || key.len() == 32, "Serpent keys are 128, 192 or 256 bits long");
+
+        // Shorter keys are padded to 256 bits with a single one bit and then zeros.
+        let mut padded = [Secret::new(0); 32];
+        padded[..key.len()].copy_from_slice(&key);
+        if key.len() < 32 {
+            padded[key.len()] = Secret::new(1);
+        }
+
+        let mut prekeys = Vec::with_capacity(8 + 4 * (ROUNDS + 1));
+        for chunk in padded.chunks(4) {
+            prekeys.push((0..4).fold(Secret::new(0), |word, j| word | Secret::<u32>::from(chunk[j]) << (8 * j as u32)));
+        }
+        for i in 0..4 * (ROUNDS + 1) {
+            let n = prekeys.len();
+            let word = prekeys[n - 8] ^ prekeys[n - 5] ^ prekeys[n - 3] ^ prekeys[n - 1] ^ PHI ^ i as u32;
+            prekeys.push(word.rotate_left(11));
+        }
+
+        // Round key i goes through S-box 3 - i, modulo 8.
+        let round_keys = prekeys[8..].chunks(4).enumerate().map(|(i, chunk)| {
+            substitute(&ANF[(ROUNDS + 3 - i) % 8], [chunk[0], chunk[1], chunk[2], chunk[3]])
+        }).collect();
+
+        Serpent {
+            round_keys: round_keys,
+            _marker: PhantomData
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{ANF, INVERSE_ANF, SBOX, Serpent, Serpent128, substitute};
+
+    use array::{Array, ArrayLength};
+    use cipher::block::{BlockFn, BlockCipher};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+
+    use typenum::consts::{U16, U24, U32};
+
+    use self::test::Bencher;
+
+    // Runs all 16 inputs through an S-box at once, in columns 0 to 15.
+    fn sbox_outputs(anf: &[u16; 4]) -> Vec<u8> {
+        let mut x = [Secret::new(0); 4];
+        for (i, word) in x.iter_mut().enumerate() {
+            *word = Secret::new((0..16).fold(0, |word, input: u32| word | (input >> i & 1) << input));
+        }
+        let y = substitute(anf, x);
+        (0..16).map(|column| (0..4).fold(0, |out, i| out | ((y[i].expose() >> column & 1) << i) as u8)).collect()
+    }
+
+    #[test]
+    fn anf_matches_tables() {
+        for (i, sbox) in SBOX.iter().enumerate() {
+            assert_eq!(&sbox_outputs(&ANF[i])[..], &sbox[..]);
+            let inverse = sbox_outputs(&INVERSE_ANF[i]);
+            for input in 0..16 {
+                assert_eq!(inverse[sbox[input] as usize] as usize, input);
+            }
+        }
+    }
+
+    fn check<N: ArrayLength<Secret<u8>>>(key: &str, plaintext: u128, ciphertext: u128) {
+        let key = hex::decode(key);
+        let cipher = Serpent::<N>::from_key(Array::from_fn(|i| Secret::new(key[i])));
+        assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+        assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+    }
+
+    #[test]
+    fn nessie_128() {
+        // Set 1, vectors 0 and 127; set 2, vector 0; set 3, vector 1; set 4, vector 0
+        check::<U16>("80000000000000000000000000000000", 0, 0x264E5481EFF42A4606ABDA06C0BFDA3D);
+        check::<U16>("00000000000000000000000000000001", 0, 0xF668C7091F81B2827DA77DD419B708E1);
+        check::<U16>("00000000000000000000000000000000", 1 << 127, 0xA3B35DE7C358DDD82644678C64B8BCBB);
+        check::<U16>("01010101010101010101010101010101", 0x01010101010101010101010101010101, 0x5107E36DBE81D9996D1EF7F3656FFC63);
+        check::<U16>("000102030405060708090A0B0C0D0E0F", 0x00112233445566778899AABBCCDDEEFF, 0x563E2CF8740A27C164804560391E9B27);
+    }
+
+    #[test]
+    fn nessie_192() {
+        // Set 1, vectors 0 and 191; set 2, vector 0; set 3, vector 1; set 4, vector 0
+        check::<U24>("800000000000000000000000000000000000000000000000", 0, 0x9E274EAD9B737BB21EFCFCA548602689);
+        check::<U24>("000000000000000000000000000000000000000000000001", 0, 0x5D058517AC7CC5AFD5C33253D4703B46);
+        check::<U24>("000000000000000000000000000000000000000000000000", 1 << 127, 0x23F5F432AD687E0D4574C16459618ABB);
+        check::<U24>("010101010101010101010101010101010101010101010101", 0x01010101010101010101010101010101, 0x3C30CC53B1408BF333B85DD1C6632A29);
+        check::<U24>("000102030405060708090A0B0C0D0E0F1011121314151617", 0x00112233445566778899AABBCCDDEEFF, 0x6AB816C82DE53B93005008AFA2246A02);
+    }
+
+    #[test]
+    fn nessie_256() {
+        // Set 1, vectors 0 and 255; set 2, vector 0; set 3, vector 1; set 4, vector 0
+        check::<U32>("8000000000000000000000000000000000000000000000000000000000000000", 0, 0xA223AA1288463C0E2BE38EBD825616C0);
+        check::<U32>("0000000000000000000000000000000000000000000000000000000000000001", 0, 0x9858FD31C9C6B54AC0C99CC52324ED34);
+        check::<U32>("0000000000000000000000000000000000000000000000000000000000000000", 1 << 127, 0x8314675E8AD5C3ECD83D852BCF7F566E);
+        check::<U32>("0101010101010101010101010101010101010101010101010101010101010101", 0x01010101010101010101010101010101, 0xEC9723B15B2A6489F84C4524FFFC2748);
+        check::<U32>("000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F", 0x00112233445566778899AABBCCDDEEFF, 0x2868B7A2D28ECD5E4FDEFAC3C4330074);
+    }
+
+    #[bench]
+    fn bench_encrypt(bencher: &mut Bencher) {
+        let cipher = Serpent128::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = Secret::new(0);
+        bencher.bytes = 16;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
@@ -0,0 +1,201 @@
+use std::marker::PhantomData;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::word::{Word, Word24, Word48};
+use keyed::Keyed;
+use secret::Secret;
+
+use typenum::consts::{U2, U3, U4};
+
+// The constant sequences z0 to z4, with z_j[i] in bit i
+const Z: [u64; 5] = [
+    0b0001100111000011010100100010111110110011100001101010010001011111,
+    0b0001011010000110010011111011100010101101000011001001111101110001,
+    0b0011001101101001111110001000010100011001001011000000111011110101,
+    0b0011110000101100111001010001001000000111101001100011010111011011,
+    0b0011110111001001010011000011101000000100011011010110011110001011
+];
+
+// The number of rounds and the constant sequence for each block and key size, from Table 3.1 of
+// Beaulieu et al., "The SIMON and SPECK Families of Lightweight Block Ciphers"
+fn parameters(bits: u32, key_words: usize) -> (usize, usize) {
+    match (bits, key_words) {
+        (16, 4) => (32, 0),
+        (24, 3) => (36, 0),
+        (24, 4) => (36, 1),
+        (32, 3) => (42, 2),
+        (32, 4) => (44, 3),
+        (48, 2) => (52, 2),
+        (48, 3) => (54, 3),
+        (64, 2) => (68, 2),
+        (64, 3) => (69, 3),
+        (64, 4) => (72, 4),
+        _ => panic!("Simon is not defined with {}-bit words and {} key words", bits, key_words)
+    }
+}
+
+fn f<W: Word>(x: W) -> W {
+    x.rotate_left(1) & x.rotate_left(8) ^ x.rotate_left(2)
+}
+
+/// The Simon block cipher on words of type `W`, with a key of `KeyWords` words.
+///
+/// Blocks are the pair of words (x, y) and keys are the words (k_{m-1}, ..., k_0), in the order
+/// the test vectors in the paper are written.
+pub struct Simon<W, KeyWords> {
+    round_keys: Vec<W>,
+    _marker: PhantomData<KeyWords>
+}
+
+pub type Simon32_64 = Simon<Secret<u16>, U4>;
+pub type Simon48_72 = Simon<Word24, U3>;
+pub type Simon48_96 = Simon<Word24, U4>;
+pub type Simon64_96 = Simon<Secret<u32>, U3>;
+pub type Simon64_128 = Simon<Secret<u32>, U4>;
+pub type Simon96_96 = Simon<Word48, U2>;
+pub type Simon96_144 = Simon<Word48, U3>;
+pub type Simon128_128 = Simon<Secret<u64>, U2>;
+pub type Simon128_192 = Simon<Secret<u64>, U3>;
+pub type Simon128_256 = Simon<Secret<u64>, U4>;
+
+impl<W: Word, KeyWords> BlockFn for Simon<W, KeyWords> {
+    type Block = [W; 2];
+    fn encrypt(&self, block: [W; 2]) -> [W; 2] {
+        let [mut x, mut y] = block;
+        for &key in self.round_keys.iter() {
+            let new_x = y ^ f(x) ^ key;
+            y = x;
+            x = new_x;
+        }
+        [x, y]
+    }
+}
+
+impl<W: Word, KeyWords> BlockCipher for Simon<W, KeyWords> {
+    fn decrypt(&self, block: [W; 2]) -> [W; 2] {
+        let [mut x, mut y] = block;
+        for &key in self.round_keys.iter().rev() {
+            let new_y = x ^ f(y) ^ key;
+            x = y;
+            y = new_y;
+        }
+        [x, y]
+    }
+}
+
+impl<W: Word, KeyWords: ArrayLength<W>> Keyed for Simon<W, KeyWords> {
+    type Key = Array<W, KeyWords>;
+    fn from_key(key: Array<W, KeyWords>) -> Simon<W, KeyWords> {
+        let m = key.len();
+        let (rounds, j) = parameters(W::bits(), m);
+
+        // 2^n - 4, which folds the complement of k_{i-m} into the constant
+        let c = W::from_u64(Secret::new(!3));
+
+        let mut round_keys: Vec<W> = key.iter().rev().cloned().collect();
+        for i in m..rounds {
+            let mut tmp = round_keys[i - 1].rotate_right(3);
+            if m == 4 {
+                tmp = tmp ^ round_keys[i - 3];
+            }
+            tmp = tmp ^ tmp.rotate_right(1);
+            let z = W::from_u64(Secret::new(Z[j] >> ((i - m) % 62) & 1));
+            round_keys.push(c ^ z ^ round_keys[i - m] ^ tmp);
+        }
+
+        Simon {
+            round_keys: round_keys,
+            _marker: PhantomData
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Simon, Simon32_64, Simon64_128};
+
+    use array::{Array, ArrayLength};
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::word::{Word, Word24, Word48};
+    use keyed::Keyed;
+    use secret::Secret;
+
+    use typenum::consts::{U2, U3, U4};
+
+    use self::test::Bencher;
+
+    fn word<W: Word>(value: u64) -> W {
+        W::from_u64(Secret::new(value))
+    }
+
+    fn check<W: Word, KeyWords: ArrayLength<W>>(key: &[u64], plaintext: [u64; 2], ciphertext: [u64; 2]) {
+        let cipher = Simon::<W, KeyWords>::from_key(Array::from_fn(|i| word(key[i])));
+        let encrypted = cipher.encrypt([word(plaintext[0]), word(plaintext[1])]);
+        assert_eq!([encrypted[0].to_u64().expose(), encrypted[1].to_u64().expose()], ciphertext);
+        let decrypted = cipher.decrypt([word(ciphertext[0]), word(ciphertext[1])]);
+        assert_eq!([decrypted[0].to_u64().expose(), decrypted[1].to_u64().expose()], plaintext);
+    }
+
+    // The test vectors from Appendix B of the paper
+    #[test]
+    fn simon32() {
+        check::<Secret<u16>, U4>(&[0x1918, 0x1110, 0x0908, 0x0100], [0x6565, 0x6877], [0xC69B, 0xE9BB]);
+    }
+
+    #[test]
+    fn simon48() {
+        check::<Word24, U3>(&[0x121110, 0x0A0908, 0x020100], [0x612067, 0x6E696C], [0xDAE5AC, 0x292CAC]);
+        check::<Word24, U4>(&[0x1A1918, 0x121110, 0x0A0908, 0x020100], [0x726963, 0x20646E], [0x6E06A5, 0xACF156]);
+    }
+
+    #[test]
+    fn simon64() {
+        check::<Secret<u32>, U3>(&[0x13121110, 0x0B0A0908, 0x03020100], [0x6F722067, 0x6E696C63], [0x5CA2E27F, 0x111A8FC8]);
+        check::<Secret<u32>, U4>(&[0x1B1A1918, 0x13121110, 0x0B0A0908, 0x03020100], [0x656B696C, 0x20646E75], [0x44C8FC20, 0xB9DFA07A]);
+    }
+
+    #[test]
+    fn simon96() {
+        check::<Word48, U2>(&[0x0D0C0B0A0908, 0x050403020100], [0x2072616C6C69, 0x702065687420], [0x602807A462B4, 0x69063D8FF082]);
+        check::<Word48, U3>(&[0x151413121110, 0x0D0C0B0A0908, 0x050403020100], [0x746168742074, 0x73756420666F], [0xECAD1C6C451E, 0x3F59C5DB1AE9]);
+    }
+
+    #[test]
+    fn simon128() {
+        check::<Secret<u64>, U2>(&[0x0F0E0D0C0B0A0908, 0x0706050403020100],
+                                 [0x6373656420737265, 0x6C6C657661727420], [0x49681B1E1E54FE3F, 0x65AA832AF84E0BBC]);
+        check::<Secret<u64>, U3>(&[0x1716151413121110, 0x0F0E0D0C0B0A0908, 0x0706050403020100],
+                                 [0x206572656874206E, 0x6568772065626972], [0xC4AC61EFFCDC0D4F, 0x6C9C8D6E2597B85B]);
+        check::<Secret<u64>, U4>(&[0x1F1E1D1C1B1A1918, 0x1716151413121110, 0x0F0E0D0C0B0A0908, 0x0706050403020100],
+                                 [0x74206E69206D6F6F, 0x6D69732061207369], [0x8D2B5579AFC8A3A0, 0x3BF72A87EFE7B868]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn undefined_size() {
+        Simon::<Secret<u16>, U2>::from_key(Array::from_fn(|_| Secret::new(0)));
+    }
+
+    #[bench]
+    fn bench_simon32_64(bencher: &mut Bencher) {
+        let cipher = Simon32_64::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = [Secret::new(0); 2];
+        bencher.bytes = 4;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+
+    #[bench]
+    fn bench_simon64_128(bencher: &mut Bencher) {
+        let cipher = Simon64_128::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = [Secret::new(0); 2];
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
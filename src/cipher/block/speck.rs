@@ -0,0 +1,199 @@
+use std::marker::PhantomData;
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use cipher::block::word::{Word, Word24, Word48};
+use keyed::Keyed;
+use secret::Secret;
+
+use typenum::consts::{U2, U3, U4};
+
+// The number of rounds for each block and key size, from Table 4.1 of Beaulieu et al., "The SIMON
+// and SPECK Families of Lightweight Block Ciphers"
+fn rounds(bits: u32, key_words: usize) -> usize {
+    match (bits, key_words) {
+        (16, 4) => 22,
+        (24, 3) => 22,
+        (24, 4) => 23,
+        (32, 3) => 26,
+        (32, 4) => 27,
+        (48, 2) => 28,
+        (48, 3) => 29,
+        (64, 2) => 32,
+        (64, 3) => 33,
+        (64, 4) => 34,
+        _ => panic!("Speck is not defined with {}-bit words and {} key words", bits, key_words)
+    }
+}
+
+// The rotation amounts alpha and beta
+fn rotations<W: Word>() -> (u32, u32) {
+    if W::bits() == 16 {
+        (7, 2)
+    } else {
+        (8, 3)
+    }
+}
+
+fn round<W: Word>(x: W, y: W, key: W) -> (W, W) {
+    let (alpha, beta) = rotations::<W>();
+    let x = x.rotate_right(alpha).wrapping_add(y) ^ key;
+    (x, y.rotate_left(beta) ^ x)
+}
+
+/// The Speck block cipher on words of type `W`, with a key of `KeyWords` words.
+///
+/// Blocks are the pair of words (x, y) and keys are the words (l_{m-2}, ..., l_0, k_0), in the
+/// order the test vectors in the paper are written.
+pub struct Speck<W, KeyWords> {
+    round_keys: Vec<W>,
+    _marker: PhantomData<KeyWords>
+}
+
+pub type Speck32_64 = Speck<Secret<u16>, U4>;
+pub type Speck48_72 = Speck<Word24, U3>;
+pub type Speck48_96 = Speck<Word24, U4>;
+pub type Speck64_96 = Speck<Secret<u32>, U3>;
+pub type Speck64_128 = Speck<Secret<u32>, U4>;
+pub type Speck96_96 = Speck<Word48, U2>;
+pub type Speck96_144 = Speck<Word48, U3>;
+pub type Speck128_128 = Speck<Secret<u64>, U2>;
+pub type Speck128_192 = Speck<Secret<u64>, U3>;
+pub type Speck128_256 = Speck<Secret<u64>, U4>;
+
+impl<W: Word, KeyWords> BlockFn for Speck<W, KeyWords> {
+    type Block = [W; 2];
+    fn encrypt(&self, block: [W; 2]) -> [W; 2] {
+        let [mut x, mut y] = block;
+        for &key in self.round_keys.iter() {
+            let (new_x, new_y) = round(x, y, key);
+            x = new_x;
+            y = new_y;
+        }
+        [x, y]
+    }
+}
+
+impl<W: Word, KeyWords> BlockCipher for Speck<W, KeyWords> {
+    fn decrypt(&self, block: [W; 2]) -> [W; 2] {
+        let (alpha, beta) = rotations::<W>();
+        let [mut x, mut y] = block;
+        for &key in self.round_keys.iter().rev() {
+            y = (x ^ y).rotate_right(beta);
+            x = (x ^ key).wrapping_sub(y).rotate_left(alpha);
+        }
+        [x, y]
+    }
+}
+
+impl<W: Word, KeyWords: ArrayLength<W>> Keyed for Speck<W, KeyWords> {
+    type Key = Array<W, KeyWords>;
+    fn from_key(key: Array<W, KeyWords>) -> Speck<W, KeyWords> {
+        let m = key.len();
+        let rounds = rounds(W::bits(), m);
+
+        // The key schedule is the round function itself, with the round number as the key.
+        let mut l: Vec<W> = key[..m - 1].iter().rev().cloned().collect();
+        let mut round_keys = Vec::with_capacity(rounds);
+        round_keys.push(key[m - 1]);
+        for i in 0..rounds - 1 {
+            let (new_l, new_k) = round(l[i], round_keys[i], W::from_u64(Secret::new(i as u64)));
+            l.push(new_l);
+            round_keys.push(new_k);
+        }
+
+        Speck {
+            round_keys: round_keys,
+            _marker: PhantomData
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Speck, Speck32_64, Speck64_128};
+
+    use array::{Array, ArrayLength};
+    use cipher::block::{BlockFn, BlockCipher};
+    use cipher::block::word::{Word, Word24, Word48};
+    use keyed::Keyed;
+    use secret::Secret;
+
+    use typenum::consts::{U2, U3, U4};
+
+    use self::test::Bencher;
+
+    fn word<W: Word>(value: u64) -> W {
+        W::from_u64(Secret::new(value))
+    }
+
+    fn check<W: Word, KeyWords: ArrayLength<W>>(key: &[u64], plaintext: [u64; 2], ciphertext: [u64; 2]) {
+        let cipher = Speck::<W, KeyWords>::from_key(Array::from_fn(|i| word(key[i])));
+        let encrypted = cipher.encrypt([word(plaintext[0]), word(plaintext[1])]);
+        assert_eq!([encrypted[0].to_u64().expose(), encrypted[1].to_u64().expose()], ciphertext);
+        let decrypted = cipher.decrypt([word(ciphertext[0]), word(ciphertext[1])]);
+        assert_eq!([decrypted[0].to_u64().expose(), decrypted[1].to_u64().expose()], plaintext);
+    }
+
+    // The test vectors from Appendix C of the paper
+    #[test]
+    fn speck32() {
+        check::<Secret<u16>, U4>(&[0x1918, 0x1110, 0x0908, 0x0100], [0x6574, 0x694C], [0xA868, 0x42F2]);
+    }
+
+    #[test]
+    fn speck48() {
+        check::<Word24, U3>(&[0x121110, 0x0A0908, 0x020100], [0x20796C, 0x6C6172], [0xC049A5, 0x385ADC]);
+        check::<Word24, U4>(&[0x1A1918, 0x121110, 0x0A0908, 0x020100], [0x6D2073, 0x696874], [0x735E10, 0xB6445D]);
+    }
+
+    #[test]
+    fn speck64() {
+        check::<Secret<u32>, U3>(&[0x13121110, 0x0B0A0908, 0x03020100], [0x74614620, 0x736E6165], [0x9F7952EC, 0x4175946C]);
+        check::<Secret<u32>, U4>(&[0x1B1A1918, 0x13121110, 0x0B0A0908, 0x03020100], [0x3B726574, 0x7475432D], [0x8C6FA548, 0x454E028B]);
+    }
+
+    #[test]
+    fn speck96() {
+        check::<Word48, U2>(&[0x0D0C0B0A0908, 0x050403020100], [0x65776F68202C, 0x656761737520], [0x9E4D09AB7178, 0x62BDDE8F79AA]);
+        check::<Word48, U3>(&[0x151413121110, 0x0D0C0B0A0908, 0x050403020100], [0x656D6974206E, 0x69202C726576], [0x2BF31072228A, 0x7AE440252EE6]);
+    }
+
+    #[test]
+    fn speck128() {
+        check::<Secret<u64>, U2>(&[0x0F0E0D0C0B0A0908, 0x0706050403020100],
+                                 [0x6C61766975716520, 0x7469206564616D20], [0xA65D985179783265, 0x7860FEDF5C570D18]);
+        check::<Secret<u64>, U3>(&[0x1716151413121110, 0x0F0E0D0C0B0A0908, 0x0706050403020100],
+                                 [0x7261482066656968, 0x43206F7420746E65], [0x1BE4CF3A13135566, 0xF9BC185DE03C1886]);
+        check::<Secret<u64>, U4>(&[0x1F1E1D1C1B1A1918, 0x1716151413121110, 0x0F0E0D0C0B0A0908, 0x0706050403020100],
+                                 [0x65736F6874206E49, 0x202E72656E6F6F70], [0x4109010405C0F53E, 0x4EEEB48D9C188F43]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn undefined_size() {
+        Speck::<Secret<u16>, U2>::from_key(Array::from_fn(|_| Secret::new(0)));
+    }
+
+    #[bench]
+    fn bench_speck32_64(bencher: &mut Bencher) {
+        let cipher = Speck32_64::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = [Secret::new(0); 2];
+        bencher.bytes = 4;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+
+    #[bench]
+    fn bench_speck64_128(bencher: &mut Bencher) {
+        let cipher = Speck64_128::from_key(Array::from_fn(|_| Secret::new(0)));
+        let mut block = [Secret::new(0); 2];
+        bencher.bytes = 8;
+        bencher.iter(|| {
+            block = cipher.encrypt(block);
+        });
+    }
+}
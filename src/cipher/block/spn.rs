@@ -0,0 +1,154 @@
+use std::marker::PhantomData;
+use std::ops::{BitAnd, BitOr, BitXor, Shl, Shr};
+
+use array::{Array, ArrayLength};
+use cipher::block::{BlockFn, BlockCipher};
+use keyed::Keyed;
+
+/// A fixed permutation of the block, such as the S-box or the linear layer of a
+/// substitution-permutation network
+pub trait Layer {
+    type Block;
+    fn apply(block: Self::Block) -> Self::Block;
+}
+
+/// A layer that can be undone. A network whose layers are both invertible can decrypt.
+pub trait InvertibleLayer: Layer {
+    fn invert(block: Self::Block) -> Self::Block;
+}
+
+/// Moves bit i of `block` to bit `position(i)`, for the `bits` bits of the block. The positions
+/// are public, so this runs in the same time for every block.
+pub fn permute_bits<T, F>(block: T, one: T, bits: u32, position: F) -> T
+    where T: Copy + BitAnd<T, Output=T> + BitOr<T, Output=T> + Shl<u32, Output=T> + Shr<u32, Output=T>,
+          F: Fn(u32) -> u32
+{
+    (1..bits).fold((block & one) << position(0), |out, i| out | (block >> i & one) << position(i))
+}
+
+/// The inverse of `permute_bits` with the same `position`, moving bit `position(i)` back to bit i
+pub fn inverse_permute_bits<T, F>(block: T, one: T, bits: u32, position: F) -> T
+    where T: Copy + BitAnd<T, Output=T> + BitOr<T, Output=T> + Shl<u32, Output=T> + Shr<u32, Output=T>,
+          F: Fn(u32) -> u32
+{
+    (1..bits).fold(block >> position(0) & one, |out, i| out | (block >> position(i) & one) << i)
+}
+
+/// A substitution-permutation network of `Rounds` rounds, each of which adds a round key and then
+/// applies `SBoxLayer` followed by `LinearLayer`, and a final key addition. The key is the round
+/// keys along with the final key, so key schedules stay with each cipher.
+///
+/// Ciphers that add the key at the end of each round instead, such as GIFT, fit this shape with a
+/// first round key of 0.
+pub struct Spn<SBoxLayer: Layer, LinearLayer, Rounds: ArrayLength<SBoxLayer::Block>> {
+    round_keys: Array<SBoxLayer::Block, Rounds>,
+    final_key: SBoxLayer::Block,
+    _marker: PhantomData<LinearLayer>
+}
+
+impl<Block, SBoxLayer, LinearLayer, Rounds> BlockFn for Spn<SBoxLayer, LinearLayer, Rounds>
+    where Block: Copy + BitXor<Block, Output=Block>,
+          SBoxLayer: Layer<Block=Block>,
+          LinearLayer: Layer<Block=Block>,
+          Rounds: ArrayLength<Block>
+{
+    type Block = Block;
+
+    fn encrypt(&self, block: Block) -> Block {
+        let block = self.round_keys.iter().fold(block, |block, &key| {
+            LinearLayer::apply(SBoxLayer::apply(block ^ key))
+        });
+        block ^ self.final_key
+    }
+}
+
+impl<Block, SBoxLayer, LinearLayer, Rounds> BlockCipher for Spn<SBoxLayer, LinearLayer, Rounds>
+    where Block: Copy + BitXor<Block, Output=Block>,
+          SBoxLayer: InvertibleLayer<Block=Block>,
+          LinearLayer: InvertibleLayer<Block=Block>,
+          Rounds: ArrayLength<Block>
+{
+    fn decrypt(&self, block: Block) -> Block {
+        self.round_keys.iter().rev().fold(block ^ self.final_key, |block, &key| {
+            SBoxLayer::invert(LinearLayer::invert(block)) ^ key
+        })
+    }
+}
+
+impl<SBoxLayer: Layer, LinearLayer, Rounds: ArrayLength<SBoxLayer::Block>> Keyed for Spn<SBoxLayer, LinearLayer, Rounds> {
+    type Key = (Array<SBoxLayer::Block, Rounds>, SBoxLayer::Block);
+    fn from_key(key: Self::Key) -> Self {
+        Spn {
+            round_keys: key.0,
+            final_key: key.1,
+            _marker: PhantomData
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InvertibleLayer, Layer, Spn, inverse_permute_bits, permute_bits};
+
+    use array::Array;
+    use cipher::block::{BlockFn, BlockCipher};
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::traits::rotate::{RotateLeft, RotateRight};
+    use utils::traits::wrapping::{WrappingAdd, WrappingSub};
+
+    use typenum::consts::U8;
+
+    // Cheap invertible layers, far from secure
+    struct AddRotate;
+
+    impl Layer for AddRotate {
+        type Block = Secret<u64>;
+        fn apply(block: Secret<u64>) -> Secret<u64> {
+            block.wrapping_add(0x9E3779B97F4A7C15).rotate_left(13)
+        }
+    }
+
+    impl InvertibleLayer for AddRotate {
+        fn invert(block: Secret<u64>) -> Secret<u64> {
+            block.rotate_right(13).wrapping_sub(0x9E3779B97F4A7C15)
+        }
+    }
+
+    struct ShiftXor;
+
+    impl Layer for ShiftXor {
+        type Block = Secret<u64>;
+        fn apply(block: Secret<u64>) -> Secret<u64> {
+            block ^ block << 17
+        }
+    }
+
+    impl InvertibleLayer for ShiftXor {
+        fn invert(block: Secret<u64>) -> Secret<u64> {
+            block ^ block << 17 ^ block << 34 ^ block << 51
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let round_keys = Array::from_fn(|i| Secret::new(0x0123456789ABCDEFu64.rotate_left(8 * i as u32)));
+        let spn: Spn<AddRotate, ShiftXor, U8> = Spn::from_key((round_keys, Secret::new(0xFEDCBA9876543210)));
+        for &block in [0, 1, 0xFFFFFFFFFFFFFFFF, 0x0011223344556677].iter() {
+            let encrypted = spn.encrypt(Secret::new(block));
+            assert!(encrypted.expose() != block);
+            assert_eq!(spn.decrypt(encrypted).expose(), block);
+        }
+    }
+
+    #[test]
+    fn bit_permutations() {
+        let block = Secret::new(0x0123456789ABCDEFu64);
+        let reversed = permute_bits(block, Secret::new(1), 64, |i| 63 - i);
+        assert_eq!(reversed.expose(), 0x0123456789ABCDEFu64.reverse_bits());
+
+        let position = |i| i * 5 % 64;
+        let permuted = permute_bits(block, Secret::new(1), 64, position);
+        assert_eq!(inverse_permute_bits(permuted, Secret::new(1), 64, position).expose(), 0x0123456789ABCDEF);
+    }
+}
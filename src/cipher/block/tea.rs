@@ -0,0 +1,304 @@
+use std::marker::PhantomData;
+
+use array::Array;
+use cipher::block::{BlockFn, BlockCipher};
+use keyed::Keyed;
+use secret::Secret;
+use utils::traits::truncate::Truncate;
+use utils::traits::wrapping::{WrappingAdd, WrappingSub};
+
+use typenum::consts::{U16, U32, U64};
+use typenum::uint::Unsigned;
+
+// The key schedule constant, 2^32 divided by the golden ratio
+const DELTA: u32 = 0x9E3779B9;
+
+// Keys are four big-endian words, as in the original papers' test code and most libraries.
+fn key_words(key: &Array<Secret<u8>, U16>) -> [Secret<u32>; 4] {
+    let mut words = [Secret::new(0); 4];
+    for (word, bytes) in words.iter_mut().zip(key.chunks(4)) {
+        *word = bytes.iter().fold(Secret::new(0), |word, &byte| word << 8 | Secret::<u32>::from(byte));
+    }
+    words
+}
+
+// The block is the big-endian pair of words (v0, v1).
+fn split(block: Secret<u64>) -> (Secret<u32>, Secret<u32>) {
+    ((block >> 32).truncate(), block.truncate())
+}
+
+fn join(v0: Secret<u32>, v1: Secret<u32>) -> Secret<u64> {
+    Secret::<u64>::from(v0) << 32 | Secret::<u64>::from(v1)
+}
+
+/// TEA, from Wheeler and Needham, "TEA, a Tiny Encryption Algorithm", running `Cycles` cycles of
+/// two Feistel rounds each. The paper recommends 32 cycles and suggests that 16 may be enough.
+///
+/// TEA has equivalent keys, so it makes a poor hash function; prefer XTEA for new designs.
+pub struct Tea<Cycles> {
+    key: [Secret<u32>; 4],
+    _marker: PhantomData<Cycles>
+}
+
+pub type Tea16 = Tea<U16>;
+pub type Tea32 = Tea<U32>;
+
+impl<Cycles> Keyed for Tea<Cycles> {
+    type Key = Array<Secret<u8>, U16>;
+    fn from_key(key: Array<Secret<u8>, U16>) -> Tea<Cycles> {
+        Tea {
+            key: key_words(&key),
+            _marker: PhantomData
+        }
+    }
+}
+
+impl<Cycles> Tea<Cycles> {
+    fn mix(&self, x: Secret<u32>, sum: u32, k0: usize) -> Secret<u32> {
+        (x << 4).wrapping_add(self.key[k0]) ^ x.wrapping_add(sum) ^ (x >> 5).wrapping_add(self.key[k0 + 1])
+    }
+}
+
+impl<Cycles: Unsigned> BlockFn for Tea<Cycles> {
+    type Block = Secret<u64>;
+    fn encrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let (mut v0, mut v1) = split(block);
+        let mut sum = 0u32;
+        for _ in 0..Cycles::to_u32() {
+            sum = sum.wrapping_add(DELTA);
+            v0 = v0.wrapping_add(self.mix(v1, sum, 0));
+            v1 = v1.wrapping_add(self.mix(v0, sum, 2));
+        }
+        join(v0, v1)
+    }
+}
+
+impl<Cycles: Unsigned> BlockCipher for Tea<Cycles> {
+    fn decrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let (mut v0, mut v1) = split(block);
+        let mut sum = DELTA.wrapping_mul(Cycles::to_u32());
+        for _ in 0..Cycles::to_u32() {
+            v1 = v1.wrapping_sub(self.mix(v0, sum, 2));
+            v0 = v0.wrapping_sub(self.mix(v1, sum, 0));
+            sum = sum.wrapping_sub(DELTA);
+        }
+        join(v0, v1)
+    }
+}
+
+/// XTEA, from Needham and Wheeler, "Tea extensions", running `Cycles` cycles of two Feistel
+/// rounds each. The usual choice is 32 cycles, or 64 rounds.
+///
+/// The key words used in each round depend only on the round number, so indexing them is safe.
+pub struct Xtea<Cycles> {
+    key: [Secret<u32>; 4],
+    _marker: PhantomData<Cycles>
+}
+
+pub type Xtea32 = Xtea<U32>;
+pub type Xtea64 = Xtea<U64>;
+
+impl<Cycles> Keyed for Xtea<Cycles> {
+    type Key = Array<Secret<u8>, U16>;
+    fn from_key(key: Array<Secret<u8>, U16>) -> Xtea<Cycles> {
+        Xtea {
+            key: key_words(&key),
+            _marker: PhantomData
+        }
+    }
+}
+
+impl<Cycles> Xtea<Cycles> {
+    fn mix(&self, x: Secret<u32>, sum: u32, k: usize) -> Secret<u32> {
+        (x << 4 ^ x >> 5).wrapping_add(x) ^ self.key[k].wrapping_add(sum)
+    }
+}
+
+impl<Cycles: Unsigned> BlockFn for Xtea<Cycles> {
+    type Block = Secret<u64>;
+    fn encrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let (mut v0, mut v1) = split(block);
+        let mut sum = 0u32;
+        for _ in 0..Cycles::to_u32() {
+            v0 = v0.wrapping_add(self.mix(v1, sum, (sum & 3) as usize));
+            sum = sum.wrapping_add(DELTA);
+            v1 = v1.wrapping_add(self.mix(v0, sum, (sum >> 11 & 3) as usize));
+        }
+        join(v0, v1)
+    }
+}
+
+impl<Cycles: Unsigned> BlockCipher for Xtea<Cycles> {
+    fn decrypt(&self, block: Secret<u64>) -> Secret<u64> {
+        let (mut v0, mut v1) = split(block);
+        let mut sum = DELTA.wrapping_mul(Cycles::to_u32());
+        for _ in 0..Cycles::to_u32() {
+            v1 = v1.wrapping_sub(self.mix(v0, sum, (sum >> 11 & 3) as usize));
+            sum = sum.wrapping_sub(DELTA);
+            v0 = v0.wrapping_sub(self.mix(v1, sum, (sum & 3) as usize));
+        }
+        join(v0, v1)
+    }
+}
+
+/// XXTEA, or Corrected Block TEA, from Wheeler and Needham, "Correction to xtea". This enciphers
+/// a whole message of two or more words as one block, in place, with 6 + 52/n cycles over the
+/// n words.
+///
+/// Since the block size varies, this does not implement `BlockCipher`.
+pub struct Xxtea {
+    key: [Secret<u32>; 4]
+}
+
+impl Keyed for Xxtea {
+    type Key = Array<Secret<u8>, U16>;
+    fn from_key(key: Array<Secret<u8>, U16>) -> Xxtea {
+        Xxtea {
+            key: key_words(&key)
+        }
+    }
+}
+
+impl Xxtea {
+    fn mix(&self, y: Secret<u32>, z: Secret<u32>, sum: u32, k: usize) -> Secret<u32> {
+        (z >> 5 ^ y << 2).wrapping_add(y >> 3 ^ z << 4) ^ (y ^ sum).wrapping_add(self.key[k] ^ z)
+    }
+
+    fn cycles(words: &[Secret<u32>]) -> u32 {
+        assert!(words.len() >= 2, "XXTEA needs at least two words");
+        6 + 52 / words.len() as u32
+    }
+
+    pub fn encrypt(&self, words: &mut [Secret<u32>]) {
+        let n = words.len();
+        let mut sum = 0u32;
+        for _ in 0..Xxtea::cycles(words) {
+            sum = sum.wrapping_add(DELTA);
+            let e = (sum >> 2 & 3) as usize;
+            for p in 0..n {
+                let (y, z) = (words[(p + 1) % n], words[(p + n - 1) % n]);
+                words[p] = words[p].wrapping_add(self.mix(y, z, sum, p & 3 ^ e));
+            }
+        }
+    }
+
+    pub fn decrypt(&self, words: &mut [Secret<u32>]) {
+        let n = words.len();
+        let cycles = Xxtea::cycles(words);
+        let mut sum = DELTA.wrapping_mul(cycles);
+        for _ in 0..cycles {
+            let e = (sum >> 2 & 3) as usize;
+            for p in (0..n).rev() {
+                let (y, z) = (words[(p + 1) % n], words[(p + n - 1) % n]);
+                words[p] = words[p].wrapping_sub(self.mix(y, z, sum, p & 3 ^ e));
+            }
+            sum = sum.wrapping_sub(DELTA);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Tea16, Tea32, Xtea32, Xtea64, Xxtea};
+
+    use array::Array;
+    use cipher::block::BlockCipher;
+    use keyed::Keyed;
+    use secret::Secret;
+    use utils::hex;
+    use utils::testing::{bench_encrypt, make_key};
+
+    use typenum::consts::U16;
+
+    use self::test::Bencher;
+
+    fn with_key<K: Keyed<Key = Array<Secret<u8>, U16>>>(key: &str) -> K {
+        K::from_key(make_key(&hex::decode(key)))
+    }
+
+    fn check<C: BlockCipher<Block = Secret<u64>>>(cipher: &C, plaintext: u64, ciphertext: u64) {
+        assert_eq!(cipher.encrypt(Secret::new(plaintext)).expose(), ciphertext);
+        assert_eq!(cipher.decrypt(Secret::new(ciphertext)).expose(), plaintext);
+    }
+
+    // No published TEA vectors were at hand, so these are the output of a separate transcription of
+    // the C code in the paper.
+    #[test]
+    fn tea_vectors() {
+        let zero: Tea32 = with_key("00000000000000000000000000000000");
+        check(&zero, 0, 0x41EA3A0A94BAA940);
+
+        let key = "00112233445566778899AABBCCDDEEFF";
+        check(&with_key::<Tea32>(key), 0x0123456789ABCDEF, 0x126C6B92C0653A3E);
+        check(&with_key::<Tea16>(key), 0x0123456789ABCDEF, 0x7CF6C0032C4AF316);
+    }
+
+    // From the XTEA tests of Mbed TLS, which also reads keys and blocks as big-endian words
+    #[test]
+    fn xtea_vectors() {
+        let vectors = [
+            ("000102030405060708090A0B0C0D0E0F", 0x4142434445464748, 0x497DF3D072612CB5),
+            ("000102030405060708090A0B0C0D0E0F", 0x4141414141414141, 0xE78F2D13744341D8),
+            ("000102030405060708090A0B0C0D0E0F", 0x5A5B6E278948D77F, 0x4141414141414141),
+            ("00000000000000000000000000000000", 0x4142434445464748, 0xA0390589F8B8EFA5),
+            ("00000000000000000000000000000000", 0x4141414141414141, 0xED23375A821A8C2D),
+            ("00000000000000000000000000000000", 0x70E1225D6E4E7655, 0x4141414141414141)
+        ];
+        for &(key, plaintext, ciphertext) in vectors.iter() {
+            check(&with_key::<Xtea32>(key), plaintext, ciphertext);
+        }
+    }
+
+    // Mbed TLS only runs 32 cycles, so this is the output of a separate transcription of the C
+    // code in the paper, which reproduces the vectors above.
+    #[test]
+    fn xtea64() {
+        check(&with_key::<Xtea64>("00112233445566778899AABBCCDDEEFF"), 0x0123456789ABCDEF, 0x22464FEB44FD9CE6);
+    }
+
+    fn check_xxtea(key: &str, plaintext: &[u32], ciphertext: &[u32]) {
+        let cipher: Xxtea = with_key(key);
+        let mut words: Vec<_> = plaintext.iter().map(|&word| Secret::new(word)).collect();
+        cipher.encrypt(&mut words);
+        assert_eq!(words.iter().map(|word| word.expose()).collect::<Vec<_>>(), ciphertext);
+        cipher.decrypt(&mut words);
+        assert_eq!(words.iter().map(|word| word.expose()).collect::<Vec<_>>(), plaintext);
+    }
+
+    // As for TEA, the output of a separate transcription of the C code in the paper
+    #[test]
+    fn xxtea_vectors() {
+        check_xxtea("00000000000000000000000000000000", &[0, 0], &[0x053704AB, 0x575D8C80]);
+
+        let key = "00112233445566778899AABBCCDDEEFF";
+        check_xxtea(key, &[0x01234567, 0x89ABCDEF], &[0x34354989, 0xDD7D1A7A]);
+        check_xxtea(key, &[0x00000000, 0x11111111, 0x22222222], &[0x3898690C, 0xB6C27604, 0xF35192CC]);
+        check_xxtea("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", &[1, 2, 3, 4, 5, 6, 7],
+                    &[0x41D3A580, 0x04F82F0F, 0xBECB7233, 0x521EEF5B, 0xBCB58AEB, 0xD09AC977, 0xD5439DE3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn xxtea_single_word() {
+        let cipher: Xxtea = with_key("00000000000000000000000000000000");
+        cipher.encrypt(&mut [Secret::new(0)]);
+    }
+
+    #[bench]
+    fn bench_xtea(bencher: &mut Bencher) {
+        let xtea: Xtea32 = with_key("00000000000000000000000000000000");
+        bench_encrypt(bencher, &xtea, Secret::new(0), 8);
+    }
+
+    #[bench]
+    fn bench_xxtea(bencher: &mut Bencher) {
+        let xxtea: Xxtea = with_key("00000000000000000000000000000000");
+        let mut words = vec![Secret::new(0); 256];
+        bencher.bytes = 1024;
+        bencher.iter(|| {
+            xxtea.encrypt(&mut words);
+        });
+    }
+}
@@ -0,0 +1,182 @@
+use std::ops::{BitAnd, BitXor, Not};
+
+use secret::Secret;
+use utils::traits::rotate::{RotateLeft, RotateRight};
+use utils::traits::truncate::Truncate;
+use utils::traits::wrapping::{WrappingAdd, WrappingSub};
+
+/// A secret machine word for ciphers like Simon and Speck that are defined for several word
+/// sizes. Besides the native integers, this covers sizes like 24 and 48 bits that are kept in the
+/// low bits of a wider integer.
+pub trait Word: Copy + BitAnd<Self, Output=Self> + BitXor<Self, Output=Self> + Not<Output=Self> + RotateLeft<u32, Output=Self> + RotateRight<u32, Output=Self> + WrappingAdd<Self, Output=Self> + WrappingSub<Self, Output=Self> {
+    fn bits() -> u32;
+
+    /// The word made of the low `bits()` bits of `value`
+    fn from_u64(value: Secret<u64>) -> Self;
+
+    fn to_u64(self) -> Secret<u64>;
+}
+
+macro_rules! native_word {
+    { $t:ty } => {
+        impl Word for Secret<$t> {
+            fn bits() -> u32 {
+                8 * ::std::mem::size_of::<$t>() as u32
+            }
+
+            fn from_u64(value: Secret<u64>) -> Secret<$t> {
+                value.truncate()
+            }
+
+            fn to_u64(self) -> Secret<u64> {
+                Secret::from(self)
+            }
+        }
+    };
+}
+
+native_word! { u16 }
+native_word! { u32 }
+
+impl Word for Secret<u64> {
+    fn bits() -> u32 {
+        64
+    }
+
+    fn from_u64(value: Secret<u64>) -> Secret<u64> {
+        value
+    }
+
+    fn to_u64(self) -> Secret<u64> {
+        self
+    }
+}
+
+macro_rules! narrow_word {
+    { $(#[$attr:meta])* $name:ident: $bits:expr, $t:ty } => {
+        $(#[$attr])*
+        #[derive(Clone, Copy)]
+        pub struct $name(Secret<$t>);
+
+        impl $name {
+            const MASK: $t = (1 << $bits) - 1;
+
+            /// The word made of the low bits of `value`
+            pub fn new(value: Secret<$t>) -> $name {
+                $name(value & $name::MASK)
+            }
+
+            pub fn into_inner(self) -> Secret<$t> {
+                self.0
+            }
+        }
+
+        impl BitAnd<$name> for $name {
+            type Output = $name;
+            fn bitand(self, rhs: $name) -> $name {
+                $name(self.0 & rhs.0)
+            }
+        }
+
+        impl BitXor<$name> for $name {
+            type Output = $name;
+            fn bitxor(self, rhs: $name) -> $name {
+                $name(self.0 ^ rhs.0)
+            }
+        }
+
+        impl Not for $name {
+            type Output = $name;
+            fn not(self) -> $name {
+                $name(!self.0 & $name::MASK)
+            }
+        }
+
+        // The bits shifted out of the top of the word land in the unused high bits, where the
+        // mask clears them. A rotation by 0 shifts right by the whole width, which is still
+        // defined because the inner integer is wider.
+        impl RotateLeft<u32> for $name {
+            type Output = $name;
+            fn rotate_left(self, rhs: u32) -> $name {
+                let rhs = rhs % $bits;
+                $name((self.0 << rhs | self.0 >> ($bits - rhs)) & $name::MASK)
+            }
+        }
+
+        impl RotateRight<u32> for $name {
+            type Output = $name;
+            fn rotate_right(self, rhs: u32) -> $name {
+                self.rotate_left($bits - rhs % $bits)
+            }
+        }
+
+        impl WrappingAdd<$name> for $name {
+            type Output = $name;
+            fn wrapping_add(self, rhs: $name) -> $name {
+                $name(self.0.wrapping_add(rhs.0) & $name::MASK)
+            }
+        }
+
+        impl WrappingSub<$name> for $name {
+            type Output = $name;
+            fn wrapping_sub(self, rhs: $name) -> $name {
+                $name(self.0.wrapping_sub(rhs.0) & $name::MASK)
+            }
+        }
+
+        impl Word for $name {
+            fn bits() -> u32 {
+                $bits
+            }
+
+            fn from_u64(value: Secret<u64>) -> $name {
+                $name::new(<Secret<$t> as Word>::from_u64(value))
+            }
+
+            fn to_u64(self) -> Secret<u64> {
+                Secret::from(self.0)
+            }
+        }
+    };
+}
+
+narrow_word! {
+    /// A 24-bit word, kept in the low bits of a `u32`
+    Word24: 24, u32
+}
+
+narrow_word! {
+    /// A 48-bit word, kept in the low bits of a `u64`
+    Word48: 48, u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Word, Word24, Word48};
+
+    use secret::Secret;
+    use utils::traits::rotate::{RotateLeft, RotateRight};
+    use utils::traits::wrapping::{WrappingAdd, WrappingSub};
+
+    #[test]
+    fn word24() {
+        let x = Word24::new(Secret::new(0xFF800001));
+        assert_eq!(x.into_inner().expose(), 0x800001);
+        assert_eq!(x.rotate_left(1).into_inner().expose(), 0x000003);
+        assert_eq!(x.rotate_right(1).into_inner().expose(), 0xC00000);
+        assert_eq!(x.rotate_left(0).into_inner().expose(), 0x800001);
+        assert_eq!(x.rotate_left(24).into_inner().expose(), 0x800001);
+        assert_eq!((!x).into_inner().expose(), 0x7FFFFE);
+        assert_eq!(x.wrapping_add(x).into_inner().expose(), 0x000002);
+        assert_eq!(Word24::new(Secret::new(0)).wrapping_sub(x).into_inner().expose(), 0x7FFFFF);
+    }
+
+    #[test]
+    fn word48() {
+        let x = Word48::from_u64(Secret::new(0x1234_8000_0000_0001));
+        assert_eq!(x.to_u64().expose(), 0x8000_0000_0001);
+        assert_eq!(x.rotate_left(4).to_u64().expose(), 0x0000_0000_0018);
+        assert_eq!(x.rotate_right(4).to_u64().expose(), 0x1800_0000_0000);
+        assert_eq!(x.wrapping_add(x).to_u64().expose(), 0x0000_0000_0002);
+    }
+}
@@ -0,0 +1,99 @@
+use cipher::lfsr::{FibonacciLfsr, ShiftRegister, majority};
+use secret::Secret;
+use utils::traits::wrapping::WrappingSub;
+
+// The feedback polynomials of the three registers and the bits that decide their clocking
+const POLYNOMIALS: [u64; 3] = [
+    0x000E4001, // x^19 + x^18 + x^17 + x^14 + 1
+    0x00600001, // x^22 + x^21 + 1
+    0x00E00101  // x^23 + x^22 + x^21 + x^8 + 1
+];
+const CLOCKING_BITS: [u32; 3] = [8, 10, 10];
+
+/// The number of keystream bits for each direction of a GSM burst
+pub const BURST_BITS: usize = 114;
+
+/// The A5/1 keystream generator used to encrypt GSM, which is thoroughly broken.
+pub struct A51 {
+    registers: [FibonacciLfsr; 3]
+}
+
+impl A51 {
+    /// Loads the 64-bit session key and the 22-bit frame number, then runs the 100 mixing
+    /// clocks whose output is discarded.
+    pub fn new(key: &[u8; 8], frame: u32) -> A51 {
+        let mut registers = [
+            FibonacciLfsr::new(POLYNOMIALS[0], Secret::new(0)),
+            FibonacciLfsr::new(POLYNOMIALS[1], Secret::new(0)),
+            FibonacciLfsr::new(POLYNOMIALS[2], Secret::new(0))
+        ];
+
+        // Key bits go in least significant bit of each byte first, with every register clocked.
+        let key_bits = (0..64).map(|i| Secret::new(((key[i / 8] >> (i % 8)) & 1) as u64));
+        let frame_bits = (0..22).map(|i| Secret::new(((frame >> i) & 1) as u64));
+        for bit in key_bits.chain(frame_bits) {
+            for register in registers.iter_mut() {
+                register.clock();
+                let state = register.state();
+                register.set_state(state ^ bit);
+            }
+        }
+
+        let mut generator = A51 {
+            registers: registers
+        };
+        for _ in 0..100 {
+            generator.next_bit();
+        }
+        generator
+    }
+
+    /// Clocks the registers whose clocking bit agrees with the majority, then outputs the XOR
+    /// of their top bits.
+    pub fn next_bit(&mut self) -> Secret<u64> {
+        let bits = [
+            self.registers[0].bit(CLOCKING_BITS[0]),
+            self.registers[1].bit(CLOCKING_BITS[1]),
+            self.registers[2].bit(CLOCKING_BITS[2])
+        ];
+        let vote = majority(bits[0], bits[1], bits[2]);
+        for (register, &bit) in self.registers.iter_mut().zip(bits.iter()) {
+            // Clock when the bit equals the vote, i.e. when their XOR is 0.
+            register.clock_if(Secret::new(1).wrapping_sub(bit ^ vote));
+        }
+
+        let mut output = Secret::new(0);
+        for register in self.registers.iter() {
+            output ^= register.bit(register.len() - 1);
+        }
+        output
+    }
+
+    /// The keystreams for the two directions of a burst, each packed most significant bit
+    /// first, as in the reference implementation by Briceno, Goldberg and Wagner.
+    pub fn burst(key: &[u8; 8], frame: u32) -> ([u8; 15], [u8; 15]) {
+        let mut generator = A51::new(key, frame);
+        let mut streams = ([0; 15], [0; 15]);
+        for i in 0..2 * BURST_BITS {
+            let bit = generator.next_bit().expose() as u8;
+            let j = i % BURST_BITS;
+            let stream = if i < BURST_BITS { &mut streams.0 } else { &mut streams.1 };
+            stream[j / 8] |= bit << (7 - j % 8);
+        }
+        streams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::A51;
+
+    #[test]
+    fn reference_vector() {
+        // The test vector from "A pedagogical implementation of A5/1"
+        let key = [0x12, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF];
+        let (a_to_b, b_to_a) = A51::burst(&key, 0x134);
+        assert_eq!(a_to_b, [0x53, 0x4E, 0xAA, 0x58, 0x2F, 0xE8, 0x15, 0x1A, 0xB6, 0xE1, 0x85, 0x5A, 0x72, 0x8C, 0x00]);
+        assert_eq!(b_to_a, [0x24, 0xFD, 0x35, 0xA3, 0x5D, 0x5F, 0xB6, 0x52, 0x6D, 0x32, 0xF9, 0x06, 0xDF, 0x1A, 0xC0]);
+    }
+}
@@ -0,0 +1,172 @@
+use cipher::lfsr::{FibonacciLfsr, ShiftRegister};
+use secret::Secret;
+use utils::traits::wrapping::WrappingAdd;
+
+// The feedback polynomials of the four registers and the bits they feed into the combiner. See
+// the Bluetooth Core Specification, Volume 2, Part H, Section 4.
+const POLYNOMIALS: [u64; 4] = [
+    (1 << 25) | (1 << 20) | (1 << 12) | (1 << 8) | 1,
+    (1 << 31) | (1 << 24) | (1 << 16) | (1 << 12) | 1,
+    (1 << 33) | (1 << 28) | (1 << 24) | (1 << 4) | 1,
+    (1 << 39) | (1 << 36) | (1 << 28) | (1 << 4) | 1
+];
+const OUTPUT_BITS: [u32; 4] = [24, 24, 32, 32];
+
+/// The core of the keystream generator of the Bluetooth E0 cipher: four LFSRs combined by a
+/// summation combiner with two bits of memory, carried over two steps.
+///
+/// This is the generator alone, started from explicit register and combiner states. The
+/// Bluetooth key loading from Kc', BD_ADDR and the clock, which runs the generator twice, is not
+/// included. It has not been checked against the sample data of the Bluetooth Core
+/// Specification, so the tap positions, bit order and combiner are unverified.
+pub struct E0 {
+    registers: [FibonacciLfsr; 4],
+    // The combiner state c[t] and c[t - 1], each 2 bits
+    carry: Secret<u64>,
+    previous_carry: Secret<u64>
+}
+
+impl E0 {
+    pub fn new(states: [Secret<u64>; 4], carry: Secret<u64>, previous_carry: Secret<u64>) -> E0 {
+        E0 {
+            registers: [
+                FibonacciLfsr::new(POLYNOMIALS[0], states[0]),
+                FibonacciLfsr::new(POLYNOMIALS[1], states[1]),
+                FibonacciLfsr::new(POLYNOMIALS[2], states[2]),
+                FibonacciLfsr::new(POLYNOMIALS[3], states[3])
+            ],
+            carry: carry & 3,
+            previous_carry: previous_carry & 3
+        }
+    }
+
+    pub fn next_bit(&mut self) -> Secret<u64> {
+        let mut output = self.carry & 1;
+        let mut sum = Secret::new(0);
+        for (register, &index) in self.registers.iter().zip(OUTPUT_BITS.iter()) {
+            let bit = register.bit(index);
+            output ^= bit;
+            sum = sum.wrapping_add(bit);
+        }
+
+        // c[t + 1] = s[t + 1] ^ T1(c[t]) ^ T2(c[t - 1]), where s[t + 1] = (y[t] + c[t]) / 2, T1 is
+        // the identity and T2 maps (x1, x0) to (x0, x1 ^ x0).
+        let next = sum.wrapping_add(self.carry) >> 1;
+        let previous = self.previous_carry;
+        let mixed = ((previous & 1) << 1) | ((previous ^ (previous >> 1)) & 1);
+        self.previous_carry = self.carry;
+        self.carry = next ^ self.carry ^ mixed;
+
+        for register in self.registers.iter_mut() {
+            register.clock();
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{E0, POLYNOMIALS, OUTPUT_BITS};
+
+    use secret::Secret;
+
+    // The sequence of bits leaving a register, from the recurrence of its polynomial: s[n] is
+    // the sum of s[n - k] over the terms x^k. The state holds s[0] in its top bit.
+    fn sequence(polynomial: u64, state: u64, len: usize) -> Vec<u64> {
+        let degree = 63 - polynomial.leading_zeros() as usize;
+        let mut bits: Vec<u64> = (0..degree).map(|k| state >> (degree - 1 - k) & 1).collect();
+        while bits.len() < len {
+            let n = bits.len();
+            let next = (1..degree + 1).filter(|&k| polynomial >> k & 1 == 1).fold(0, |bit, k| bit ^ bits[n - k]);
+            bits.push(next);
+        }
+        bits
+    }
+
+    #[test]
+    fn combiner() {
+        // Follows the specification's equations bit by bit, where the output tap of a register of
+        // n bits is the bit that leaves it n - 1 - tap clocks later.
+        let states = [0x1234567, 0x23456789, 0x123456789, 0x3456789ABC];
+        let mut generator = E0::new([Secret::new(states[0]), Secret::new(states[1]), Secret::new(states[2]), Secret::new(states[3])],
+                                    Secret::new(2), Secret::new(1));
+        let taps: Vec<Vec<u64>> = (0..4).map(|i| {
+            let delay = (63 - POLYNOMIALS[i].leading_zeros() - 1 - OUTPUT_BITS[i]) as usize;
+            sequence(POLYNOMIALS[i], states[i], 1000 + delay)[delay..].to_vec()
+        }).collect();
+
+        // c[t] = (c1, c0) and c[t - 1] = (p1, p0)
+        let (mut c1, mut c0, mut p1, mut p0) = (1, 0, 0, 1);
+        for t in 0..1000 {
+            let x: Vec<u64> = taps.iter().map(|bits| bits[t]).collect();
+            let z = x[0] ^ x[1] ^ x[2] ^ x[3] ^ c0;
+            assert_eq!(generator.next_bit().expose(), z);
+
+            let s = (x[0] + x[1] + x[2] + x[3] + 2 * c1 + c0) / 2;
+            let (s1, s0) = (s >> 1, s & 1);
+            let next = (s1 ^ c1 ^ p0, s0 ^ c0 ^ p1 ^ p0);
+            p1 = c1;
+            p0 = c0;
+            c1 = next.0;
+            c0 = next.1;
+        }
+    }
+
+    // x^exponent modulo a polynomial of the given degree over GF(2)
+    fn power_of_x(exponent: u64, polynomial: u64, degree: u32) -> u64 {
+        let multiply = |mut a: u64, b: u64| {
+            let mut product = 0;
+            for i in 0..degree {
+                if b >> i & 1 == 1 {
+                    product ^= a;
+                }
+                a <<= 1;
+                if a >> degree & 1 == 1 {
+                    a ^= polynomial;
+                }
+            }
+            product
+        };
+        let (mut result, mut base, mut exponent) = (1, 2, exponent);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = multiply(result, base);
+            }
+            base = multiply(base, base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    fn prime_factors(mut n: u64) -> Vec<u64> {
+        let mut factors = Vec::new();
+        let mut d = 2;
+        while d * d <= n {
+            if n.is_multiple_of(d) {
+                factors.push(d);
+                while n.is_multiple_of(d) {
+                    n /= d;
+                }
+            }
+            d += 1;
+        }
+        if n > 1 {
+            factors.push(n);
+        }
+        factors
+    }
+
+    #[test]
+    fn polynomials_are_primitive() {
+        // A register of n bits has period 2^n - 1 when its polynomial is primitive, that is when x
+        // has order exactly 2^n - 1 modulo it.
+        for &polynomial in POLYNOMIALS.iter() {
+            let degree = 63 - polynomial.leading_zeros();
+            let order = (1 << degree) - 1;
+            assert_eq!(power_of_x(order, polynomial, degree), 1);
+            for factor in prime_factors(order) {
+                assert!(power_of_x(order / factor, polynomial, degree) != 1);
+            }
+        }
+    }
+}
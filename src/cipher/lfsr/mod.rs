@@ -0,0 +1,266 @@
+use secret::Secret;
+use utils::traits::wrapping::WrappingSub;
+
+pub mod a51;
+pub mod e0;
+
+/// The XOR of all the bits of `value`, as 0 or 1
+pub fn parity(value: Secret<u64>) -> Secret<u64> {
+    let mut value = value;
+    value ^= value >> 32;
+    value ^= value >> 16;
+    value ^= value >> 8;
+    value ^= value >> 4;
+    value ^= value >> 2;
+    value ^= value >> 1;
+    value & 1
+}
+
+/// The bitwise majority of three values, as used to clock A5/1 irregularly
+pub fn majority(a: Secret<u64>, b: Secret<u64>, c: Secret<u64>) -> Secret<u64> {
+    (a & b) | (a & c) | (b & c)
+}
+
+// All ones if the low bit of `bit` is set, and zero otherwise.
+fn bit_mask(bit: Secret<u64>) -> Secret<u64> {
+    Secret::new(0).wrapping_sub(bit & 1)
+}
+
+// The degree of a feedback polynomial, which must have a constant term.
+fn degree(polynomial: u64) -> u32 {
+    assert!(polynomial & 1 == 1 && polynomial > 1);
+    63 - polynomial.leading_zeros()
+}
+
+fn length_mask(len: u32) -> u64 {
+    if len == 64 { !0 } else { (1 << len) - 1 }
+}
+
+/// A binary shift register of at most 64 bits that outputs one bit per clock.
+///
+/// Bits enter at bit 0 and leave from bit `len() - 1`, unless the register says otherwise.
+pub trait ShiftRegister {
+    fn len(&self) -> u32;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn state(&self) -> Secret<u64>;
+
+    fn set_state(&mut self, state: Secret<u64>);
+
+    /// Steps the register once, returning the bit that was output.
+    fn clock(&mut self) -> Secret<u64>;
+
+    /// Bit `index` of the state, as 0 or 1
+    fn bit(&self, index: u32) -> Secret<u64> {
+        assert!(index < self.len());
+        (self.state() >> index) & 1
+    }
+
+    /// Steps the register if the low bit of `enable` is set, without branching on it. This is
+    /// the building block for irregular clocking.
+    fn clock_if(&mut self, enable: Secret<u64>) {
+        let before = self.state();
+        self.clock();
+        let after = self.state();
+        self.set_state(before ^ ((before ^ after) & bit_mask(enable)));
+    }
+}
+
+/// A linear feedback shift register in Fibonacci form: the new bit is the parity of the tapped
+/// bits.
+///
+/// A feedback polynomial of degree `len` gives a register of `len` bits, and each term x^k taps
+/// bit k - 1. For example, x^19 + x^18 + x^17 + x^14 + 1 taps bits 18, 17, 16 and 13. The output
+/// sequence then satisfies s[n] = sum of s[n - k] over the terms x^k, and has period 2^len - 1
+/// from any nonzero state if the polynomial is primitive.
+pub struct FibonacciLfsr {
+    state: Secret<u64>,
+    taps: u64,
+    len: u32
+}
+
+impl FibonacciLfsr {
+    pub fn new(polynomial: u64, state: Secret<u64>) -> FibonacciLfsr {
+        let len = degree(polynomial);
+        FibonacciLfsr {
+            state: state & length_mask(len),
+            taps: polynomial >> 1,
+            len: len
+        }
+    }
+}
+
+impl ShiftRegister for FibonacciLfsr {
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn state(&self) -> Secret<u64> {
+        self.state
+    }
+
+    fn set_state(&mut self, state: Secret<u64>) {
+        self.state = state & length_mask(self.len);
+    }
+
+    fn clock(&mut self) -> Secret<u64> {
+        let output = (self.state >> (self.len - 1)) & 1;
+        let feedback = parity(self.state & self.taps);
+        self.state = ((self.state << 1) | feedback) & length_mask(self.len);
+        output
+    }
+}
+
+/// A linear feedback shift register in Galois form: the register shifts towards bit 0, which is
+/// output and, when set, toggles the tapped bits.
+///
+/// Bits k - 1 are tapped for each term x^k of the polynomial, as for `FibonacciLfsr`, and the
+/// output sequence satisfies the same recurrence, though the two registers pass through
+/// different states.
+pub struct GaloisLfsr {
+    state: Secret<u64>,
+    taps: u64,
+    len: u32
+}
+
+impl GaloisLfsr {
+    pub fn new(polynomial: u64, state: Secret<u64>) -> GaloisLfsr {
+        let len = degree(polynomial);
+        GaloisLfsr {
+            state: state & length_mask(len),
+            taps: polynomial >> 1,
+            len: len
+        }
+    }
+}
+
+impl ShiftRegister for GaloisLfsr {
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn state(&self) -> Secret<u64> {
+        self.state
+    }
+
+    fn set_state(&mut self, state: Secret<u64>) {
+        self.state = state & length_mask(self.len);
+    }
+
+    fn clock(&mut self) -> Secret<u64> {
+        let output = self.state & 1;
+        self.state = (self.state >> 1) ^ (bit_mask(output) & self.taps);
+        output
+    }
+}
+
+/// A nonlinear feedback shift register, whose new bit is the low bit of an arbitrary function of
+/// the state. The function should run in constant time.
+pub struct Nlfsr<F> {
+    state: Secret<u64>,
+    len: u32,
+    feedback: F
+}
+
+impl<F: Fn(Secret<u64>) -> Secret<u64>> Nlfsr<F> {
+    pub fn new(len: u32, feedback: F, state: Secret<u64>) -> Nlfsr<F> {
+        assert!(len > 0 && len <= 64);
+        Nlfsr {
+            state: state & length_mask(len),
+            len: len,
+            feedback: feedback
+        }
+    }
+}
+
+impl<F: Fn(Secret<u64>) -> Secret<u64>> ShiftRegister for Nlfsr<F> {
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn state(&self) -> Secret<u64> {
+        self.state
+    }
+
+    fn set_state(&mut self, state: Secret<u64>) {
+        self.state = state & length_mask(self.len);
+    }
+
+    fn clock(&mut self) -> Secret<u64> {
+        let output = (self.state >> (self.len - 1)) & 1;
+        let feedback = (self.feedback)(self.state) & 1;
+        self.state = ((self.state << 1) | feedback) & length_mask(self.len);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FibonacciLfsr, GaloisLfsr, Nlfsr, ShiftRegister, parity};
+
+    use secret::Secret;
+
+    // x^19 + x^18 + x^17 + x^14 + 1, which is primitive
+    const POLYNOMIAL: u64 = 0xE4001;
+
+    fn period<R: ShiftRegister>(register: &mut R) -> u64 {
+        let start = register.state().expose();
+        let mut steps = 0;
+        loop {
+            register.clock();
+            steps += 1;
+            if register.state().expose() == start {
+                return steps;
+            }
+        }
+    }
+
+    #[test]
+    fn maximal_period() {
+        assert_eq!(period(&mut FibonacciLfsr::new(POLYNOMIAL, Secret::new(1))), (1 << 19) - 1);
+        assert_eq!(period(&mut GaloisLfsr::new(POLYNOMIAL, Secret::new(1))), (1 << 19) - 1);
+    }
+
+    #[test]
+    fn same_recurrence() {
+        let terms = [14, 17, 18, 19];
+        let mut fibonacci = FibonacciLfsr::new(POLYNOMIAL, Secret::new(0x12345));
+        let mut galois = GaloisLfsr::new(POLYNOMIAL, Secret::new(0x6789A));
+        let fibonacci: Vec<u64> = (0..200).map(|_| fibonacci.clock().expose()).collect();
+        let galois: Vec<u64> = (0..200).map(|_| galois.clock().expose()).collect();
+        for sequence in [fibonacci, galois].iter() {
+            for n in 19..200 {
+                let sum = terms.iter().fold(0, |sum, &k| sum ^ sequence[n - k]);
+                assert_eq!(sequence[n], sum);
+            }
+        }
+    }
+
+    #[test]
+    fn clock_if() {
+        let mut clocked = FibonacciLfsr::new(POLYNOMIAL, Secret::new(0x5A5A5));
+        let mut register = FibonacciLfsr::new(POLYNOMIAL, Secret::new(0x5A5A5));
+        for i in 0..100u64 {
+            let enable = (i * 7 / 3) & 1;
+            if enable == 1 {
+                clocked.clock();
+            }
+            register.clock_if(Secret::new(enable));
+            assert_eq!(register.state().expose(), clocked.state().expose());
+        }
+    }
+
+    #[test]
+    fn nlfsr_linear_feedback() {
+        // With linear feedback, an NLFSR is just a Fibonacci LFSR.
+        let taps = POLYNOMIAL >> 1;
+        let mut nonlinear = Nlfsr::new(19, |state| parity(state & taps), Secret::new(0x31337));
+        let mut linear = FibonacciLfsr::new(POLYNOMIAL, Secret::new(0x31337));
+        for _ in 0..100 {
+            assert_eq!(nonlinear.clock().expose(), linear.clock().expose());
+        }
+    }
+}
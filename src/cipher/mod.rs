@@ -1 +1,3 @@
 pub mod block;
+pub mod stream;
+pub mod lfsr;
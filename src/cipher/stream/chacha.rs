@@ -0,0 +1,279 @@
+use std::marker::PhantomData;
+
+use cipher::stream::{BlockBuffer, SeekableStreamCipher, StreamCipher};
+use permutation::{extract_lane_bytes, xor_lane_bytes};
+use secret::Secret;
+use utils::traits::rotate::RotateLeft;
+use utils::traits::wrapping::WrappingAdd;
+
+use typenum::consts::{U8, U12, U20};
+use typenum::uint::Unsigned;
+
+// "expand 32-byte k". See RFC 8439, Section 2.3.
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646E, 0x79622D32, 0x6B206574];
+
+fn quarter_round(s: &mut [Secret<u32>; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// Applies `rounds` rounds of ChaCha, alternating column and diagonal rounds. This does not
+/// include the final addition of the input.
+pub fn chacha_rounds(state: &mut [Secret<u32>; 16], rounds: usize) {
+    assert!(rounds.is_multiple_of(2));
+    for _ in 0..rounds / 2 {
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 1, 5, 9, 13);
+        quarter_round(state, 2, 6, 10, 14);
+        quarter_round(state, 3, 7, 11, 15);
+
+        quarter_round(state, 0, 5, 10, 15);
+        quarter_round(state, 1, 6, 11, 12);
+        quarter_round(state, 2, 7, 8, 13);
+        quarter_round(state, 3, 4, 9, 14);
+    }
+}
+
+// Lays out the constants, the key and 16 bytes of counter and nonce.
+fn initial_state(key: &[u8; 32], input: &[u8; 16]) -> [Secret<u32>; 16] {
+    let mut state = [Secret::new(0); 16];
+    for (word, &constant) in state.iter_mut().zip(CONSTANTS.iter()) {
+        *word = Secret::new(constant);
+    }
+    xor_lane_bytes(&mut state[4..12], 0, key);
+    xor_lane_bytes(&mut state[12..], 0, input);
+    state
+}
+
+/// The ChaCha stream cipher with `Rounds` rounds, in the RFC 8439 variant with a 32-bit block
+/// counter and a 96-bit nonce.
+///
+/// The keystream is limited to 2^32 blocks of 64 bytes, counted from the initial counter. Never
+/// use the same key and nonce for two messages.
+pub struct ChaCha<Rounds> {
+    state: [Secret<u32>; 16],
+    buffer: BlockBuffer,
+    _marker: PhantomData<Rounds>
+}
+
+pub type ChaCha8 = ChaCha<U8>;
+pub type ChaCha12 = ChaCha<U12>;
+pub type ChaCha20 = ChaCha<U20>;
+
+impl<Rounds: Unsigned> ChaCha<Rounds> {
+    /// Creates a cipher whose keystream starts at block `counter`.
+    pub fn new(key: &[u8; 32], nonce: &[u8; 12], counter: u32) -> ChaCha<Rounds> {
+        assert!(Rounds::to_usize() % 2 == 0);
+
+        let mut input = [0; 16];
+        input[..4].copy_from_slice(&[counter as u8, (counter >> 8) as u8, (counter >> 16) as u8, (counter >> 24) as u8]);
+        input[4..].copy_from_slice(nonce);
+        ChaCha {
+            state: initial_state(key, &input),
+            buffer: BlockBuffer::new(),
+            _marker: PhantomData
+        }
+    }
+
+    /// Creates the extended-nonce variant (XChaCha), which derives a subkey from the key and the
+    /// first 16 bytes of the nonce, then uses the last 8 bytes as the nonce. Random nonces are
+    /// safe to use with this variant.
+    ///
+    /// See draft-irtf-cfrg-xchacha-03, Section 2.3.
+    pub fn new_extended(key: &[u8; 32], nonce: &[u8; 24]) -> ChaCha<Rounds> {
+        let mut hnonce = [0; 16];
+        hnonce.copy_from_slice(&nonce[..16]);
+        let subkey = Self::hchacha(key, &hnonce);
+
+        let mut short_nonce = [0; 12];
+        short_nonce[4..].copy_from_slice(&nonce[16..]);
+        ChaCha::new(&subkey, &short_nonce, 0)
+    }
+
+    /// The HChaCha function, which derives a 256-bit subkey from a key and a 128-bit nonce.
+    ///
+    /// See draft-irtf-cfrg-xchacha-03, Section 2.2.
+    pub fn hchacha(key: &[u8; 32], nonce: &[u8; 16]) -> [u8; 32] {
+        let mut state = initial_state(key, nonce);
+        chacha_rounds(&mut state, Rounds::to_usize());
+
+        let mut subkey = [0; 32];
+        extract_lane_bytes(&state[..4], 0, &mut subkey[..16]);
+        extract_lane_bytes(&state[12..], 0, &mut subkey[16..]);
+        subkey
+    }
+}
+
+impl<Rounds: Unsigned> StreamCipher for ChaCha<Rounds> {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        let state = &self.state;
+        self.buffer.apply_keystream(data, |block, output| chacha_block(state, Rounds::to_usize(), block, output));
+    }
+
+    fn seek(&mut self, position: u64) {
+        let state = &self.state;
+        self.buffer.seek(position, |block, output| chacha_block(state, Rounds::to_usize(), block, output));
+    }
+
+    fn current_pos(&self) -> u64 {
+        self.buffer.position()
+    }
+
+    fn keystream_len(&self) -> Option<u64> {
+        Some(((1 << 32) - self.state[12].expose() as u64) * 64)
+    }
+}
+
+impl<Rounds: Unsigned> SeekableStreamCipher for ChaCha<Rounds> { }
+
+// Generates keystream block `block`, counting from the initial counter in `state`.
+fn chacha_block(state: &[Secret<u32>; 16], rounds: usize, block: u64, output: &mut [u8; 64]) {
+    let counter = state[12].expose() as u64 + block;
+    assert!(counter <= u32::MAX as u64, "ChaCha keystream exhausted");
+
+    let mut input = *state;
+    input[12] = Secret::new(counter as u32);
+    let mut words = input;
+    chacha_rounds(&mut words, rounds);
+    for (word, &original) in words.iter_mut().zip(input.iter()) {
+        *word = word.wrapping_add(original);
+    }
+    extract_lane_bytes(&words, 0, output);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::ChaCha20;
+
+    use cipher::stream::StreamCipher;
+    use utils::hex;
+
+    use self::test::Bencher;
+
+    const SUNSCREEN: &'static [u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+    fn sequential_key() -> [u8; 32] {
+        let mut key = [0; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        key
+    }
+
+    #[test]
+    fn block_function() {
+        // RFC 8439, Section 2.3.2
+        let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4A, 0, 0, 0, 0];
+        let mut cipher = ChaCha20::new(&sequential_key(), &nonce, 1);
+        let mut block = [0; 64];
+        cipher.apply_keystream(&mut block);
+        assert_eq!(&block[..], &hex::decode("
+            10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e
+            d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e
+        ")[..]);
+    }
+
+    #[test]
+    fn encryption() {
+        // RFC 8439, Section 2.4.2
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4A, 0, 0, 0, 0];
+        let mut cipher = ChaCha20::new(&sequential_key(), &nonce, 1);
+        let mut buffer = SUNSCREEN.to_vec();
+        cipher.apply_keystream(&mut buffer);
+        assert_eq!(buffer, hex::decode("
+            6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b
+            f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8
+            07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736
+            5af90bbf74a35be6b40b8eedf2785e42874d
+        "));
+
+        let mut cipher = ChaCha20::new(&sequential_key(), &nonce, 1);
+        cipher.apply_keystream(&mut buffer);
+        assert_eq!(&buffer[..], SUNSCREEN);
+    }
+
+    #[test]
+    fn hchacha20() {
+        // draft-irtf-cfrg-xchacha-03, Section 2.2.1
+        let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4A, 0, 0, 0, 0, 0x31, 0x41, 0x59, 0x27];
+        let subkey = ChaCha20::hchacha(&sequential_key(), &nonce);
+        assert_eq!(&subkey[..], &hex::decode("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc")[..]);
+    }
+
+    #[test]
+    fn xchacha20() {
+        // draft-irtf-cfrg-xchacha-03, Appendix A.3.2
+        let mut key = [0; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = 0x80 + i as u8;
+        }
+        let mut nonce = [0; 24];
+        nonce.copy_from_slice(&hex::decode("404142434445464748494a4b4c4d4e4f5051525354555658"));
+        let mut buffer = b"The dhole (pronounced \"dole\") is also known as the Asiatic wild dog, red dog, and \
+                           whistling dog. It is about the size of a German shepherd but looks more like a \
+                           long-legged fox. This highly elusive and skilled jumper is classified with wolves, \
+                           coyotes, jackals, and foxes in the taxonomic family Canidae.".to_vec();
+        ChaCha20::new_extended(&key, &nonce).apply_keystream(&mut buffer);
+        assert_eq!(buffer, hex::decode("
+            4559abba4e48c16102e8bb2c05e6947f50a786de162f9b0b7e592a9b53d0d4e9
+            8d8d6410d540a1a6375b26d80dace4fab52384c731acbf16a5923c0c48d3575d
+            4d0d2c673b666faa731061277701093a6bf7a158a8864292a41c48e3a9b4c0da
+            ece0f8d98d0d7e05b37a307bbb66333164ec9e1b24ea0d6c3ffddcec4f68e744
+            3056193a03c810e11344ca06d8ed8a2bfb1e8d48cfa6bc0eb4e2464b74814240
+            7c9f431aee769960e15ba8b96890466ef2457599852385c661f752ce20f9da0c
+            09ab6b19df74e76a95967446f8d0fd415e7bee2a12a114c20eb5292ae7a349ae
+            577820d5520a1f3fb62a17ce6a7e68fa7c79111d8860920bc048ef43fe84486c
+            cb87c25f0ae045f0cce1e7989a9aa220a28bdd4827e751a24a6d5c62d790a663
+            93b93111c1a55dd7421a10184974c7c5
+        "));
+    }
+
+    #[test]
+    fn seek() {
+        let key = sequential_key();
+        let nonce = [7; 12];
+        let mut keystream = [0; 300];
+        ChaCha20::new(&key, &nonce, 5).apply_keystream(&mut keystream);
+
+        let mut cipher = ChaCha20::new(&key, &nonce, 5);
+        for &(start, len) in [(0, 1), (1, 63), (64, 70), (200, 100), (37, 3), (128, 0), (299, 1)].iter() {
+            cipher.seek(start as u64);
+            let mut piece = vec![0; len];
+            cipher.apply_keystream(&mut piece);
+            assert_eq!(&piece[..], &keystream[start..start + len]);
+            assert_eq!(cipher.current_pos(), (start + len) as u64);
+        }
+    }
+
+    #[test]
+    fn last_block() {
+        let mut cipher = ChaCha20::new(&[0; 32], &[0; 12], 0xFFFFFFFF);
+        cipher.apply_keystream(&mut [0; 64]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn exhausted() {
+        let mut cipher = ChaCha20::new(&[0; 32], &[0; 12], 0xFFFFFFFF);
+        cipher.apply_keystream(&mut [0; 65]);
+    }
+
+    #[bench]
+    fn bench_chacha20(bencher: &mut Bencher) {
+        let mut cipher = ChaCha20::new(&[0; 32], &[0; 12], 0);
+        let mut buffer = [0; 1024];
+        bencher.bytes = buffer.len() as u64;
+        bencher.iter(|| {
+            cipher.seek(0);
+            cipher.apply_keystream(&mut buffer);
+        });
+    }
+}
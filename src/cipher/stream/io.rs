@@ -0,0 +1,299 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use cipher::stream::{SeekableStreamCipher, StreamCipher};
+
+// The most data encrypted per call to `write`
+const CHUNK_LEN: usize = 4096;
+
+fn exhausted() -> io::Error {
+    io::Error::other("keystream exhausted")
+}
+
+// How many of the `wanted` bytes the keystream has left for, failing if it has none left.
+fn available<C: StreamCipher>(cipher: &C, wanted: usize) -> io::Result<usize> {
+    match cipher.keystream_len() {
+        Some(len) if wanted > 0 => {
+            let left = len - cipher.current_pos();
+            if left == 0 {
+                Err(exhausted())
+            } else {
+                Ok(cmp::min(wanted as u64, left) as usize)
+            }
+        },
+        _ => Ok(wanted)
+    }
+}
+
+// Moves `inner` to `pos` and the keystream along with it, unless that is past the end of the
+// keystream, in which case `inner` goes back to where it was.
+fn seek_both<C: SeekableStreamCipher, S: Seek>(cipher: &mut C, inner: &mut S, pos: SeekFrom) -> io::Result<u64> {
+    let previous = inner.stream_position()?;
+    let position = inner.seek(pos)?;
+    if cipher.keystream_len().is_some_and(|len| position > len) {
+        inner.seek(SeekFrom::Start(previous))?;
+        return Err(exhausted());
+    }
+    cipher.seek(position);
+    Ok(position)
+}
+
+/// A reader that applies the keystream to everything read from the inner reader, decrypting or
+/// encrypting it on the fly.
+///
+/// Seeking moves the keystream to the same position as the inner reader, so byte 0 of the
+/// keystream must line up with byte 0 of the inner reader.
+pub struct StreamReader<C, R> {
+    cipher: C,
+    inner: R
+}
+
+impl<C: StreamCipher, R: Read> StreamReader<C, R> {
+    pub fn new(cipher: C, inner: R) -> StreamReader<C, R> {
+        StreamReader {
+            cipher: cipher,
+            inner: inner
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<C: StreamCipher, R: Read> Read for StreamReader<C, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = available(&self.cipher, buf.len())?;
+        let len = self.inner.read(&mut buf[..len])?;
+        self.cipher.apply_keystream(&mut buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<C: SeekableStreamCipher, R: Read + Seek> Seek for StreamReader<C, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        seek_both(&mut self.cipher, &mut self.inner, pos)
+    }
+}
+
+/// A writer that applies the keystream to everything written before passing it to the inner
+/// writer.
+///
+/// Data is encrypted as soon as it is accepted, so any of it that the inner writer has not yet
+/// taken is held until the next write or flush. It is also written when the `StreamWriter` is
+/// dropped, but errors are ignored then, so call `flush` or `into_inner` to see them.
+pub struct StreamWriter<C, W: Write> {
+    cipher: C,
+    inner: Option<W>,
+    pending: Vec<u8>
+}
+
+impl<C: StreamCipher, W: Write> StreamWriter<C, W> {
+    pub fn new(cipher: C, inner: W) -> StreamWriter<C, W> {
+        StreamWriter {
+            cipher: cipher,
+            inner: Some(inner),
+            pending: Vec::new()
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Writes out any pending data and returns the inner writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.write_pending()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        let mut result = Ok(());
+        {
+            let inner = self.inner.as_mut().unwrap();
+            while written < self.pending.len() {
+                match inner.write(&self.pending[written..]) {
+                    Ok(0) => {
+                        result = Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write encrypted data"));
+                        break;
+                    },
+                    Ok(len) => written += len,
+                    Err(ref error) if error.kind() == io::ErrorKind::Interrupted => { },
+                    Err(error) => {
+                        result = Err(error);
+                        break;
+                    }
+                }
+            }
+        }
+        self.pending.drain(..written);
+        result
+    }
+}
+
+impl<C: StreamCipher, W: Write> Write for StreamWriter<C, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_pending()?;
+
+        let len = available(&self.cipher, cmp::min(buf.len(), CHUNK_LEN))?;
+        let start = self.pending.len();
+        self.pending.extend_from_slice(&buf[..len]);
+        self.cipher.apply_keystream(&mut self.pending[start..]);
+
+        // The data is already accepted, so a failure here is reported by the next call instead.
+        let _ = self.write_pending();
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<C: SeekableStreamCipher, W: Write + Seek> Seek for StreamWriter<C, W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.write_pending()?;
+        seek_both(&mut self.cipher, self.inner.as_mut().unwrap(), pos)
+    }
+}
+
+impl<C, W: Write> Drop for StreamWriter<C, W> {
+    fn drop(&mut self) {
+        if let Some(ref mut inner) = self.inner {
+            let _ = inner.write_all(&self.pending);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+    use super::{StreamReader, StreamWriter};
+
+    use cipher::stream::StreamCipher;
+    use cipher::stream::chacha::ChaCha20;
+
+    fn cipher() -> ChaCha20 {
+        ChaCha20::new(&[0x11; 32], &[0x22; 12], 0)
+    }
+
+    fn plaintext() -> Vec<u8> {
+        (0..10000).map(|i| (i * 13 + i / 256) as u8).collect()
+    }
+
+    fn ciphertext() -> Vec<u8> {
+        let mut data = plaintext();
+        cipher().apply_keystream(&mut data);
+        data
+    }
+
+    // Accepts at most a few bytes per call, failing every other call
+    struct Trickle {
+        data: Vec<u8>,
+        fail: bool
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.fail = !self.fail;
+            if self.fail {
+                return Err(io::Error::other("try again"));
+            }
+            let len = if buf.len() < 7 { buf.len() } else { 7 };
+            self.data.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn read() {
+        let mut reader = StreamReader::new(cipher(), Cursor::new(ciphertext()));
+        let mut output = Vec::new();
+        let mut buffer = [0; 333];
+        loop {
+            let len = reader.read(&mut buffer).unwrap();
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..len]);
+        }
+        assert_eq!(output, plaintext());
+    }
+
+    #[test]
+    fn read_seek() {
+        let mut reader = StreamReader::new(cipher(), Cursor::new(ciphertext()));
+        let mut buffer = [0; 100];
+        for &start in [5000, 17, 9900, 0].iter() {
+            assert_eq!(reader.seek(SeekFrom::Start(start)).unwrap(), start);
+            reader.read_exact(&mut buffer).unwrap();
+            assert_eq!(&buffer[..], &plaintext()[start as usize..start as usize + 100]);
+        }
+    }
+
+    #[test]
+    fn write() {
+        let mut writer = StreamWriter::new(cipher(), Vec::new());
+        let data = plaintext();
+        for chunk in data.chunks(999) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(writer.into_inner().unwrap(), ciphertext());
+    }
+
+    #[test]
+    fn write_partial() {
+        let mut writer = StreamWriter::new(cipher(), Trickle { data: Vec::new(), fail: false });
+        let data = plaintext();
+        let mut written = 0;
+        while written < data.len() {
+            if let Ok(len) = writer.write(&data[written..]) {
+                written += len;
+            }
+        }
+        while writer.flush().is_err() { }
+        assert_eq!(writer.get_ref().data, ciphertext());
+    }
+
+    #[test]
+    fn write_seek() {
+        let mut writer = StreamWriter::new(cipher(), Cursor::new(vec![0; 10000]));
+        let data = plaintext();
+        writer.seek(SeekFrom::Start(6000)).unwrap();
+        writer.write_all(&data[6000..]).unwrap();
+        writer.seek(SeekFrom::Start(0)).unwrap();
+        writer.write_all(&data[..6000]).unwrap();
+        assert_eq!(writer.into_inner().unwrap().into_inner(), ciphertext());
+    }
+
+    #[test]
+    fn keystream_exhausted() {
+        // The last block of the keystream
+        let last_block = || ChaCha20::new(&[0x11; 32], &[0x22; 12], u32::MAX);
+        let mut expected = plaintext()[..64].to_vec();
+        last_block().apply_keystream(&mut expected);
+
+        let mut reader = StreamReader::new(last_block(), Cursor::new(plaintext()));
+        let mut buffer = [0; 100];
+        assert_eq!(reader.read(&mut buffer).unwrap(), 64);
+        assert_eq!(&buffer[..64], &expected[..]);
+        assert!(reader.read(&mut buffer).is_err());
+        assert!(reader.seek(SeekFrom::Start(65)).is_err());
+        assert_eq!(reader.get_ref().position(), 64);
+
+        let mut writer = StreamWriter::new(last_block(), Vec::new());
+        assert!(writer.write_all(&plaintext()[..100]).is_err());
+        assert_eq!(writer.into_inner().unwrap(), expected);
+    }
+}
@@ -0,0 +1,90 @@
+use std::cmp;
+
+pub mod chacha;
+pub mod salsa;
+pub mod rc4;
+pub mod trivium;
+pub mod io;
+
+/// A cipher that encrypts and decrypts by XORing a keystream into the data
+pub trait StreamCipher {
+    /// XORs the keystream into `data`, starting at the current position, and moves past it.
+    fn apply_keystream(&mut self, data: &mut [u8]);
+
+    /// Moves to byte `position` of the keystream. Ciphers that cannot seek directly generate and
+    /// discard keystream to move forwards, and panic if asked to move backwards.
+    fn seek(&mut self, position: u64);
+
+    /// The number of keystream bytes before the current position.
+    fn current_pos(&self) -> u64;
+
+    /// The length of the keystream in bytes, or `None` if it does not run out before the largest
+    /// position. Ciphers panic when asked for keystream past the end.
+    fn keystream_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A stream cipher that can move to any position in its keystream, in either direction, without
+/// generating the keystream in between
+pub trait SeekableStreamCipher: StreamCipher {
+    /// XORs the keystream starting at byte `position` into `data`.
+    fn apply_keystream_at(&mut self, position: u64, data: &mut [u8]) {
+        self.seek(position);
+        self.apply_keystream(data);
+    }
+}
+
+const BLOCK_LEN: usize = 64;
+
+/// The position in a keystream that is generated in 64-byte blocks, along with the block that
+/// contains that position.
+pub struct BlockBuffer {
+    position: u64,
+    block: [u8; BLOCK_LEN]
+}
+
+impl Default for BlockBuffer {
+    fn default() -> BlockBuffer {
+        BlockBuffer::new()
+    }
+}
+
+impl BlockBuffer {
+    pub fn new() -> BlockBuffer {
+        BlockBuffer {
+            position: 0,
+            block: [0; BLOCK_LEN]
+        }
+    }
+
+    /// XORs the keystream into `data`, calling `generate` with the index of each new block
+    /// needed.
+    pub fn apply_keystream<F: FnMut(u64, &mut [u8; BLOCK_LEN])>(&mut self, data: &mut [u8], mut generate: F) {
+        let mut done = 0;
+        while done < data.len() {
+            let offset = (self.position % BLOCK_LEN as u64) as usize;
+            if offset == 0 {
+                generate(self.position / BLOCK_LEN as u64, &mut self.block);
+            }
+            let len = cmp::min(BLOCK_LEN - offset, data.len() - done);
+            for (byte, &key) in data[done..done + len].iter_mut().zip(self.block[offset..].iter()) {
+                *byte ^= key;
+            }
+            done += len;
+            self.position += len as u64;
+        }
+    }
+
+    /// Moves to byte `position`, calling `generate` if that is partway through a block.
+    pub fn seek<F: FnMut(u64, &mut [u8; BLOCK_LEN])>(&mut self, position: u64, mut generate: F) {
+        self.position = position;
+        if !position.is_multiple_of(BLOCK_LEN as u64) {
+            generate(position / BLOCK_LEN as u64, &mut self.block);
+        }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
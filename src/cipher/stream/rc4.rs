@@ -0,0 +1,208 @@
+use std::marker::PhantomData;
+
+use cipher::block::sbox::TableAccess;
+use cipher::stream::StreamCipher;
+use secret::Secret;
+use utils::traits::wrapping::WrappingAdd;
+
+/// The RC4 stream cipher, reading and writing its state as chosen by `Access`.
+///
+/// RC4 is broken: its keystream is biased, especially at the start. This is here for analysing
+/// old protocols, and should not be used to protect anything.
+pub struct Rc4<Access> {
+    state: [Secret<u8>; 256],
+    // The public index i and the secret index j
+    i: u8,
+    j: Secret<u8>,
+    position: u64,
+    _marker: PhantomData<Access>
+}
+
+impl<Access: TableAccess> Rc4<Access> {
+    /// Runs the key schedule on a key of 1 to 256 bytes.
+    pub fn new(key: &[u8]) -> Rc4<Access> {
+        assert!(!key.is_empty() && key.len() <= 256);
+
+        let mut state = [Secret::new(0); 256];
+        for (i, entry) in state.iter_mut().enumerate() {
+            *entry = Secret::new(i as u8);
+        }
+
+        let mut j = Secret::new(0);
+        for i in 0..256 {
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            swap::<Access>(&mut state, i as u8, j);
+        }
+
+        Rc4 {
+            state: state,
+            i: 0,
+            j: Secret::new(0),
+            position: 0,
+            _marker: PhantomData
+        }
+    }
+
+    /// RC4-drop[`drop`], which discards the first `drop` bytes of keystream to skip the worst of
+    /// the biases. Positions are counted from the end of the discarded bytes.
+    pub fn new_drop(key: &[u8], drop: usize) -> Rc4<Access> {
+        let mut cipher = Rc4::new(key);
+        for _ in 0..drop {
+            cipher.next_byte();
+        }
+        cipher
+    }
+
+    fn next_byte(&mut self) -> Secret<u8> {
+        self.i = self.i.wrapping_add(1);
+        let s_i = self.state[self.i as usize];
+        self.j = self.j.wrapping_add(s_i);
+        let s_j = swap::<Access>(&mut self.state, self.i, self.j);
+        Access::lookup(&self.state, s_i.wrapping_add(s_j))
+    }
+}
+
+// Swaps entries `i` and `j`, returning the new entry at `i`.
+fn swap<Access: TableAccess>(state: &mut [Secret<u8>; 256], i: u8, j: Secret<u8>) -> Secret<u8> {
+    let s_i = state[i as usize];
+    let s_j = Access::lookup(state, j);
+    state[i as usize] = s_j;
+    Access::write(state, j, s_i);
+    s_j
+}
+
+impl<Access: TableAccess> StreamCipher for Rc4<Access> {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte ^= self.next_byte().expose();
+        }
+        self.position += data.len() as u64;
+    }
+
+    /// RC4 can only move forwards, by generating and discarding keystream.
+    fn seek(&mut self, position: u64) {
+        assert!(position >= self.position, "RC4 cannot seek backwards");
+        for _ in self.position..position {
+            self.next_byte();
+        }
+        self.position = position;
+    }
+
+    fn current_pos(&self) -> u64 {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::Rc4;
+
+    use cipher::block::sbox::{Indexed, Masked, TableAccess};
+    use cipher::stream::StreamCipher;
+    use utils::hex;
+
+    use self::test::Bencher;
+
+    // RFC 6229, Section 2: keystream at offsets 0, 16, 240, 256, 1520, 1536, 4080 and 4096
+    const OFFSETS: [u64; 8] = [0, 16, 240, 256, 1520, 1536, 4080, 4096];
+    const VECTORS: [(&'static str, [&'static str; 8]); 4] = [
+        ("0102030405", [
+            "b2396305f03dc027ccc3524a0a1118a8", "6982944f18fc82d589c403a47a0d0919",
+            "28cb1132c96ce286421dcaadb8b69eae", "1cfcf62b03eddb641d77dfcf7f8d8c93",
+            "3294f744d8f9790507e70f62e5bbceea", "d8729db41882259bee4f825325f5a130",
+            "068326a2118416d21f9d04b2cd1ca050", "ff25b58995996707e51fbdf08b34d875"
+        ]),
+        ("0102030405060708090a0b0c0d0e0f10", [
+            "9ac7cc9a609d1ef7b2932899cde41b97", "5248c4959014126a6e8a84f11d1a9e1c",
+            "065902e4b620f6cc36c8589f66432f2b", "d39d566bc6bce3010768151549f3873f",
+            "b40110c4190b5622a96116b0017ed297", "ffa0b514647ec04f6306b892ae661181",
+            "ff38265c1642c1abe8d3c2fe5e572bf8", "a36a4c301ae8ac13610ccbc12256cacc"
+        ]),
+        ("0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20", [
+            "eaa6bd25880bf93d3f5d1e4ca2611d91", "cfa45c9f7e714b54bdfa80027cb14380",
+            "114ae344ded71b35f2e60febad727fd8", "02e1e7056b0f623900496422943e97b6",
+            "40f250b26d1f096a4afd4c340a588815", "3e34135c79db010200767651cf263073",
+            "a13a7c79c7e119b5ab0296ab28c300b9", "f3e4c0a2e02d1d01f7f0a74618af2b48"
+        ]),
+        ("833222772a", [
+            "80ad97bdc973df8a2e879e92a497efda", "20f060c2f2e5126501d3d4fea10d5fc0",
+            "faa148e99046181fec6b2085f3b20ed9", "f0daf5bab3d596839857846f73fbfe5a",
+            "adb89cea9a15fbe617295bd04b8ca05c", "6251d87fd4aaae9a7e4ad5c217d3f300",
+            "6349d126a37afcba89794f9804914fdc", "bf42c3018c2f7c66bfde524975768115"
+        ])
+    ];
+
+    fn check_rfc6229<Access: TableAccess>() {
+        for &(key, ref expected) in VECTORS.iter() {
+            let mut cipher = Rc4::<Access>::new(&hex::decode(key));
+            for (&offset, &expected) in OFFSETS.iter().zip(expected.iter()) {
+                cipher.seek(offset);
+                let mut keystream = [0; 16];
+                cipher.apply_keystream(&mut keystream);
+                assert_eq!(&keystream[..], &hex::decode(expected)[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn rfc6229_masked() {
+        check_rfc6229::<Masked>();
+    }
+
+    #[test]
+    fn rfc6229_indexed() {
+        check_rfc6229::<Indexed>();
+    }
+
+    #[test]
+    fn drop() {
+        let key = hex::decode("833222772a");
+        let mut cipher = Rc4::<Masked>::new_drop(&key, 1536);
+        let mut keystream = [0; 16];
+        cipher.apply_keystream(&mut keystream);
+        assert_eq!(&keystream[..], &hex::decode("6251d87fd4aaae9a7e4ad5c217d3f300")[..]);
+        assert_eq!(cipher.current_pos(), 16);
+    }
+
+    #[test]
+    fn modes_agree() {
+        let key = b"a key of a different length";
+        let mut masked = Rc4::<Masked>::new(key);
+        let mut indexed = Rc4::<Indexed>::new(key);
+        let mut first = [0; 1000];
+        let mut second = [0; 1000];
+        masked.apply_keystream(&mut first);
+        indexed.apply_keystream(&mut second);
+        assert_eq!(&first[..], &second[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn seek_backwards() {
+        let mut cipher = Rc4::<Indexed>::new(b"Key");
+        cipher.seek(10);
+        cipher.seek(9);
+    }
+
+    #[bench]
+    fn bench_masked(bencher: &mut Bencher) {
+        let mut cipher = Rc4::<Masked>::new(b"Key");
+        let mut buffer = [0; 64];
+        bencher.bytes = buffer.len() as u64;
+        bencher.iter(|| {
+            cipher.apply_keystream(&mut buffer);
+        });
+    }
+
+    #[bench]
+    fn bench_indexed(bencher: &mut Bencher) {
+        let mut cipher = Rc4::<Indexed>::new(b"Key");
+        let mut buffer = [0; 1024];
+        bencher.bytes = buffer.len() as u64;
+        bencher.iter(|| {
+            cipher.apply_keystream(&mut buffer);
+        });
+    }
+}
@@ -0,0 +1,305 @@
+use std::marker::PhantomData;
+
+use cipher::stream::{BlockBuffer, SeekableStreamCipher, StreamCipher};
+use permutation::{extract_lane_bytes, xor_lane_bytes};
+use secret::Secret;
+use utils::traits::rotate::RotateLeft;
+use utils::traits::wrapping::WrappingAdd;
+
+use typenum::consts::{U8, U12, U20};
+use typenum::uint::Unsigned;
+
+// "expand 32-byte k", placed on the diagonal. See the Salsa20 specification, Section 9.
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646E, 0x79622D32, 0x6B206574];
+const DIAGONAL: [usize; 4] = [0, 5, 10, 15];
+
+fn quarter_round(s: &mut [Secret<u32>; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[b] ^= s[a].wrapping_add(s[d]).rotate_left(7);
+    s[c] ^= s[b].wrapping_add(s[a]).rotate_left(9);
+    s[d] ^= s[c].wrapping_add(s[b]).rotate_left(13);
+    s[a] ^= s[d].wrapping_add(s[c]).rotate_left(18);
+}
+
+/// Applies `rounds` rounds of Salsa20, alternating column and row rounds. This does not include
+/// the final addition of the input.
+pub fn salsa_rounds(state: &mut [Secret<u32>; 16], rounds: usize) {
+    assert!(rounds.is_multiple_of(2));
+    for _ in 0..rounds / 2 {
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 5, 9, 13, 1);
+        quarter_round(state, 10, 14, 2, 6);
+        quarter_round(state, 15, 3, 7, 11);
+
+        quarter_round(state, 0, 1, 2, 3);
+        quarter_round(state, 5, 6, 7, 4);
+        quarter_round(state, 10, 11, 8, 9);
+        quarter_round(state, 15, 12, 13, 14);
+    }
+}
+
+// Lays out the constants, the key and 16 bytes of nonce and counter.
+fn initial_state(key: &[u8; 32], input: &[u8; 16]) -> [Secret<u32>; 16] {
+    let mut state = [Secret::new(0); 16];
+    for (&index, &constant) in DIAGONAL.iter().zip(CONSTANTS.iter()) {
+        state[index] = Secret::new(constant);
+    }
+    xor_lane_bytes(&mut state[1..5], 0, &key[..16]);
+    xor_lane_bytes(&mut state[6..10], 0, input);
+    xor_lane_bytes(&mut state[11..15], 0, &key[16..]);
+    state
+}
+
+/// The Salsa20 stream cipher with `Rounds` rounds, a 256-bit key, a 64-bit nonce and a 64-bit
+/// block counter.
+///
+/// Never use the same key and nonce for two messages.
+pub struct Salsa<Rounds> {
+    state: [Secret<u32>; 16],
+    buffer: BlockBuffer,
+    _marker: PhantomData<Rounds>
+}
+
+pub type Salsa20_8 = Salsa<U8>;
+pub type Salsa20_12 = Salsa<U12>;
+pub type Salsa20 = Salsa<U20>;
+
+impl<Rounds: Unsigned> Salsa<Rounds> {
+    pub fn new(key: &[u8; 32], nonce: &[u8; 8]) -> Salsa<Rounds> {
+        assert!(Rounds::to_usize() % 2 == 0);
+
+        let mut input = [0; 16];
+        input[..8].copy_from_slice(nonce);
+        Salsa {
+            state: initial_state(key, &input),
+            buffer: BlockBuffer::new(),
+            _marker: PhantomData
+        }
+    }
+
+    /// Creates the extended-nonce variant (XSalsa20), which derives a subkey from the key and the
+    /// first 16 bytes of the nonce, then uses the last 8 bytes as the nonce. This is the cipher
+    /// used by NaCl's `crypto_stream` and `crypto_secretbox`.
+    ///
+    /// See "Extending the Salsa20 nonce", Section 2.
+    pub fn new_extended(key: &[u8; 32], nonce: &[u8; 24]) -> Salsa<Rounds> {
+        let mut hnonce = [0; 16];
+        hnonce.copy_from_slice(&nonce[..16]);
+        let subkey = Self::hsalsa(key, &hnonce);
+
+        let mut short_nonce = [0; 8];
+        short_nonce.copy_from_slice(&nonce[16..]);
+        Salsa::new(&subkey, &short_nonce)
+    }
+
+    /// The HSalsa function, which derives a 256-bit subkey from a key and a 128-bit nonce.
+    pub fn hsalsa(key: &[u8; 32], nonce: &[u8; 16]) -> [u8; 32] {
+        let mut state = initial_state(key, nonce);
+        salsa_rounds(&mut state, Rounds::to_usize());
+
+        let mut words = [Secret::new(0); 8];
+        for (i, &index) in DIAGONAL.iter().enumerate() {
+            words[i] = state[index];
+        }
+        words[4..].copy_from_slice(&state[6..10]);
+        let mut subkey = [0; 32];
+        extract_lane_bytes(&words, 0, &mut subkey);
+        subkey
+    }
+}
+
+impl<Rounds: Unsigned> StreamCipher for Salsa<Rounds> {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        let state = &self.state;
+        self.buffer.apply_keystream(data, |block, output| salsa_block(state, Rounds::to_usize(), block, output));
+    }
+
+    fn seek(&mut self, position: u64) {
+        let state = &self.state;
+        self.buffer.seek(position, |block, output| salsa_block(state, Rounds::to_usize(), block, output));
+    }
+
+    fn current_pos(&self) -> u64 {
+        self.buffer.position()
+    }
+}
+
+impl<Rounds: Unsigned> SeekableStreamCipher for Salsa<Rounds> { }
+
+// Generates keystream block `block`.
+fn salsa_block(state: &[Secret<u32>; 16], rounds: usize, block: u64, output: &mut [u8; 64]) {
+    let mut input = *state;
+    input[8] = Secret::new(block as u32);
+    input[9] = Secret::new((block >> 32) as u32);
+    let mut words = input;
+    salsa_rounds(&mut words, rounds);
+    for (word, &original) in words.iter_mut().zip(input.iter()) {
+        *word = word.wrapping_add(original);
+    }
+    extract_lane_bytes(&words, 0, output);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Salsa, Salsa20};
+
+    use cipher::stream::StreamCipher;
+    use utils::hex;
+
+    use typenum::consts::{U8, U12, U20};
+    use typenum::uint::Unsigned;
+
+    use self::test::Bencher;
+
+    fn rounds_keystream<Rounds: Unsigned>(key: &[u8; 32], nonce: &[u8; 8], len: usize) -> Vec<u8> {
+        let mut buffer = vec![0; len];
+        Salsa::<Rounds>::new(key, nonce).apply_keystream(&mut buffer);
+        buffer
+    }
+
+    fn keystream(key: &[u8; 32], nonce: &[u8; 8], len: usize) -> Vec<u8> {
+        rounds_keystream::<U20>(key, nonce, len)
+    }
+
+    #[test]
+    fn estream() {
+        // eSTREAM Salsa20 256-bit key, Set 1, vector 0
+        let mut key = [0; 32];
+        key[0] = 0x80;
+        assert_eq!(keystream(&key, &[0; 8], 64), hex::decode("
+            e3be8fdd8beca2e3ea8ef9475b29a6e7003951e1097a5c38d23b7a5fad9f6844
+            b22c97559e2723c7cbbd3fe4fc8d9a0744652a83e72a9c461876af4d7ef1a117
+        "));
+
+        // A zero key, with only the first or only the last nonce bit set
+        assert_eq!(keystream(&[0; 32], &[0x80, 0, 0, 0, 0, 0, 0, 0], 64), hex::decode("
+            2aba3dc45b4947007b14c851cd694456b303ad59a465662803006705673d6c3e
+            29f1d3510dfc0405463c03414e0e07e359f1f1816c68b2434a19d3eee0464873
+        "));
+        assert_eq!(keystream(&[0; 32], &[0, 0, 0, 0, 0, 0, 0, 1], 64), hex::decode("
+            b47f96aa96786135297a3c4ec56a613d0b80095324ff43239d684c57ffe42e1c
+            44f3cc011613db6cdc880999a1e65aed1287fcb11c839c37120765afa73e5075
+        "));
+    }
+
+    #[test]
+    fn reduced_rounds() {
+        // Salsa20/8 and Salsa20/12 with the key of eSTREAM's Set 1, vector 0. The expected
+        // stream[0..63] and stream[448..511] are the output of libsodium's crypto_stream_salsa208
+        // and crypto_stream_salsa2012.
+        let mut key = [0; 32];
+        key[0] = 0x80;
+        let salsa20_8 = rounds_keystream::<U8>(&key, &[0; 8], 512);
+        assert_eq!(&salsa20_8[..64], &hex::decode("
+            b1f599e9b0d96df436ae31f5ef589565b92d245db5a1d4c7a78e5e8d0146f8a4
+            9d326c1a3bf50c052c9c8f114dc74972c4469591e31c9ed11927aa9871f38583
+        ")[..]);
+        assert_eq!(&salsa20_8[448..], &hex::decode("
+            53bf865c66a344cfcd19177476a05aca5851cc45224b196abf3206d899e7fe3b
+            13b3f028fa849b5564561a9181ea69e512bc34da29180cdf6811e40a9a06a8d1
+        ")[..]);
+
+        let salsa20_12 = rounds_keystream::<U12>(&key, &[0; 8], 512);
+        assert_eq!(&salsa20_12[..64], &hex::decode("
+            afe411ed1c4e07e4d0cde3b33e31ec190fa4cc796a58bafb848ead8d07d02cd2
+            d4b6f9f30cb0b57007e3733895cc8d1060107975acaeeb689b6cf614ab64a3d6
+        ")[..]);
+        assert_eq!(&salsa20_12[448..], &hex::decode("
+            87a5191ec2e3c9049fa524cd8673e0677c77adcf8ab5328fd828c4acb3eccca5
+            49adeda04872518ecdf874adcb2420c7bd1ccfe561b074080224fa7176f0cb5f
+        ")[..]);
+    }
+
+    #[test]
+    fn several_blocks() {
+        // Four blocks, as output by libsodium's crypto_stream_salsa20
+        let mut key = [0; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8 + 1;
+        }
+        assert_eq!(keystream(&key, &[3, 1, 4, 1, 5, 9, 2, 6], 256), hex::decode("
+            6ebcbdbf76fccc64ab05542bee8a67cbc28fa2e141fbefbb3a2f9b221909c8d7
+            d4295258cb539770dd24d7ac3443769ffa27a50e60644264dc8b6b612683372e
+            085d0a12bf240b189ce2b78289862b56fdc9fcffc33bef9325a2e81b98fb3fb9
+            aa04cf434615ceffeb985c1cb08d8440e90b1d56ddeaea16d9e15affff1f698c
+            483c7a466af1fe062574adfd2b06a62b4d98440719ea776385c470349a7ed696
+            9583463ed5d26b8fefccb205da0f5bfa98c77812fe756b09eacc282aa42f4baf
+            a79633189046e2b20f35b3e0e54aa3b929e23c0f47dc7bcd4f928b2a9764be7d
+            4b8a50f980a50b35ad8087375e0c556ecbe6a7161e8653ce9391e1e6710ed4f1
+        "));
+    }
+
+    #[test]
+    fn hsalsa20() {
+        // NaCl core1: the first key derived in crypto_box, from the X25519 shared secret
+        let mut shared = [0; 32];
+        shared.copy_from_slice(&hex::decode("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742"));
+        let subkey = Salsa20::hsalsa(&shared, &[0; 16]);
+        assert_eq!(&subkey[..], &hex::decode("1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389")[..]);
+    }
+
+    #[test]
+    fn xsalsa20() {
+        // The Crypto++ XSalsa20 vector, which uses the key derived in NaCl's core1 test
+        let mut key = [0; 32];
+        key.copy_from_slice(&hex::decode("1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389"));
+        let mut nonce = [0; 24];
+        nonce.copy_from_slice(&hex::decode("69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37"));
+        let mut buffer = [0; 139];
+        Salsa20::new_extended(&key, &nonce).apply_keystream(&mut buffer);
+        assert_eq!(&buffer[..], &hex::decode("
+            eea6a7251c1e72916d11c2cb214d3c252539121d8e234e652d651fa4c8cff880
+            309e645a74e9e0a60d8243acd9177ab51a1beb8d5a2f5d700c093c5e55855796
+            25337bd3ab619d615760d8c5b224a85b1d0efe0eb8a7ee163abb0376529fcc09
+            bab506c618e13ce777d82c3ae9d1a6f972d4160287cbfe60bf2130fc0a6ff604
+            9d0a5c8a82f429231f0080
+        ")[..]);
+    }
+
+    #[test]
+    fn seek() {
+        let key = [0x55; 32];
+        let nonce = [7; 8];
+        let expected = keystream(&key, &nonce, 300);
+
+        let mut cipher = Salsa20::new(&key, &nonce);
+        for &(start, len) in [(0, 1), (1, 63), (64, 70), (200, 100), (37, 3), (128, 0), (299, 1)].iter() {
+            cipher.seek(start as u64);
+            let mut piece = vec![0; len];
+            cipher.apply_keystream(&mut piece);
+            assert_eq!(&piece[..], &expected[start..start + len]);
+            assert_eq!(cipher.current_pos(), (start + len) as u64);
+        }
+    }
+
+    #[test]
+    fn counter_carries() {
+        // The counter spans words 8 and 9, so crossing 2^32 blocks must carry into the high word.
+        let key = [0x55; 32];
+        let nonce = [7; 8];
+        let position = 64 << 32;
+        let mut cipher = Salsa20::new(&key, &nonce);
+        cipher.seek(position - 32);
+        let mut across = [0; 64];
+        cipher.apply_keystream(&mut across);
+
+        let mut cipher = Salsa20::new(&key, &nonce);
+        cipher.seek(position);
+        let mut after = [0; 32];
+        cipher.apply_keystream(&mut after);
+        assert_eq!(&across[32..], &after[..]);
+        assert!(across[..32] != after[..]);
+    }
+
+    #[bench]
+    fn bench_salsa20(bencher: &mut Bencher) {
+        let mut cipher = Salsa20::new(&[0; 32], &[0; 8]);
+        let mut buffer = [0; 1024];
+        bencher.bytes = buffer.len() as u64;
+        bencher.iter(|| {
+            cipher.seek(0);
+            cipher.apply_keystream(&mut buffer);
+        });
+    }
+}
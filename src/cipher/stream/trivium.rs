@@ -0,0 +1,203 @@
+use cipher::stream::StreamCipher;
+use secret::Secret;
+use utils::traits::truncate::Truncate;
+
+// The three registers hold bits s1..s93, s94..s177 and s178..s288 of the state. See the Trivium
+// specification by De Cannière and Preneel, submitted to eSTREAM.
+const LENGTHS: [u32; 3] = [93, 84, 111];
+const INIT_STEPS: usize = 4 * 288 / 64;
+
+/// One register of Trivium, stored as the sequence of bits that passes through it.
+///
+/// Bit i of the 128-bit window is the bit that will leave the register after i more clocks, so the
+/// newest bit is at `len - 1` and state bit s_k of the first register is at 93 - k. The smallest
+/// distance between a tap and the end where bits enter is 66, so the next 64 values of any tap
+/// are already in the window, and 64 clocks can be computed at once.
+#[derive(Copy, Clone)]
+struct Register {
+    words: [Secret<u64>; 2],
+    len: u32
+}
+
+impl Register {
+    // The next 64 values of the bit `offset` places from the leaving end
+    fn tap(&self, offset: u32) -> Secret<u64> {
+        if offset == 0 {
+            self.words[0]
+        } else {
+            (self.words[0] >> offset) | (self.words[1] << (64 - offset))
+        }
+    }
+
+    // Shifts in 64 new bits, the first in the least significant bit.
+    fn push(&mut self, bits: Secret<u64>) {
+        let kept = self.len - 64;
+        self.words[0] = (self.words[1] & ((1 << kept) - 1)) | (bits << kept);
+        self.words[1] = bits >> (64 - kept);
+    }
+}
+
+// The little-endian 80-bit value of `bytes`, shifted left by `shift`.
+fn load(bytes: &[u8; 10], shift: u32) -> [Secret<u64>; 2] {
+    let mut low = 0;
+    for (i, &byte) in bytes[..8].iter().enumerate() {
+        low |= (byte as u64) << (8 * i);
+    }
+    let high = (bytes[8] as u64) | ((bytes[9] as u64) << 8);
+    [Secret::new(low << shift), Secret::new((low >> (64 - shift)) | (high << shift))]
+}
+
+/// The Trivium stream cipher, with an 80-bit key and IV, computing 64 clocks at a time.
+///
+/// Bytes of the key, IV and keystream follow the eSTREAM test vectors: the first bit of the
+/// keystream is the least significant bit of the first byte, and the key and IV are read the
+/// same way from their last bit, which goes into s1 or s94.
+pub struct Trivium {
+    registers: [Register; 3],
+    keystream: [u8; 8],
+    position: u64
+}
+
+impl Trivium {
+    pub fn new(key: &[u8; 10], iv: &[u8; 10]) -> Trivium {
+        let mut cipher = Trivium {
+            registers: [
+                Register { words: load(key, LENGTHS[0] - 80), len: LENGTHS[0] },
+                Register { words: load(iv, LENGTHS[1] - 80), len: LENGTHS[1] },
+                Register { words: [Secret::new(7), Secret::new(0)], len: LENGTHS[2] }
+            ],
+            keystream: [0; 8],
+            position: 0
+        };
+        for _ in 0..INIT_STEPS {
+            cipher.step();
+        }
+        cipher
+    }
+
+    // Clocks 64 times, returning the 64 keystream bits.
+    fn step(&mut self) -> Secret<u64> {
+        let (a, b, c) = (self.registers[0], self.registers[1], self.registers[2]);
+        let t1 = a.tap(27) ^ a.tap(0);
+        let t2 = b.tap(15) ^ b.tap(0);
+        let t3 = c.tap(45) ^ c.tap(0);
+        let output = t1 ^ t2 ^ t3;
+
+        let t1 = t1 ^ (a.tap(2) & a.tap(1)) ^ b.tap(6);
+        let t2 = t2 ^ (b.tap(2) & b.tap(1)) ^ c.tap(24);
+        let t3 = t3 ^ (c.tap(2) & c.tap(1)) ^ a.tap(24);
+        self.registers[0].push(t3);
+        self.registers[1].push(t1);
+        self.registers[2].push(t2);
+        output
+    }
+}
+
+impl StreamCipher for Trivium {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let offset = (self.position % 8) as usize;
+            if offset == 0 {
+                let word = self.step();
+                for (i, key) in self.keystream.iter_mut().enumerate() {
+                    let byte: Secret<u8> = (word >> (8 * i as u32)).truncate();
+                    *key = byte.expose();
+                }
+            }
+            *byte ^= self.keystream[offset];
+            self.position += 1;
+        }
+    }
+
+    /// Trivium can only move forwards, by generating and discarding keystream.
+    fn seek(&mut self, position: u64) {
+        assert!(position >= self.position, "Trivium cannot seek backwards");
+        let mut discard = [0; 64];
+        while self.position < position {
+            let len = if position - self.position < 64 { (position - self.position) as usize } else { 64 };
+            self.apply_keystream(&mut discard[..len]);
+        }
+    }
+
+    fn current_pos(&self) -> u64 {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::Trivium;
+
+    use cipher::stream::StreamCipher;
+    use utils::hex;
+
+    use self::test::Bencher;
+
+    fn keystream(key: &str, iv: &str, len: usize) -> Vec<u8> {
+        let key = hex::decode(key);
+        let iv = hex::decode(iv);
+        let mut key_array = [0; 10];
+        let mut iv_array = [0; 10];
+        key_array.copy_from_slice(&key);
+        iv_array.copy_from_slice(&iv);
+        let mut cipher = Trivium::new(&key_array, &iv_array);
+        let mut output = vec![0; len];
+        cipher.apply_keystream(&mut output);
+        output
+    }
+
+    // The eSTREAM test vectors (verified.test-vectors), stream[0..63]
+    #[test]
+    fn set_1_vector_0() {
+        assert_eq!(keystream("80000000000000000000", "00000000000000000000", 64), hex::decode("
+            38EB86FF730D7A9CAF8DF13A4420540DBB7B651464C87501552041C249F29A64
+            D2FBF515610921EBE06C8F92CECF7F8098FF20CCCC6A62B97BE8EF7454FC80F9"));
+    }
+
+    #[test]
+    fn set_6_vector_0() {
+        assert_eq!(keystream("0053A6F94C9FF24598EB", "0D74DB42A91077DE45AC", 64), hex::decode("
+            F4CD954A717F26A7D6930830C4E7CF0819F80E03F25F342C64ADC66ABA7F8A8E
+            6EAA49F23632AE3CD41A7BD290A0132F81C6D4043B6E397D7388F3A03B5FE358"));
+    }
+
+    #[test]
+    fn zero_key_and_iv() {
+        assert_eq!(keystream("00000000000000000000", "00000000000000000000", 64), hex::decode("
+            FBE0BF265859051B517A2E4E239FC97F563203161907CF2DE7A8790FA1B2E9CD
+            F75292030268B7382B4C1A759AA2599A285549986E74805903801A4CB5A5D4F2"));
+    }
+
+    #[test]
+    fn seek() {
+        let expected = keystream("0053A6F94C9FF24598EB", "0D74DB42A91077DE45AC", 300);
+        let mut cipher = Trivium::new(&[0x00, 0x53, 0xA6, 0xF9, 0x4C, 0x9F, 0xF2, 0x45, 0x98, 0xEB],
+                                      &[0x0D, 0x74, 0xDB, 0x42, 0xA9, 0x10, 0x77, 0xDE, 0x45, 0xAC]);
+        cipher.apply_keystream(&mut [0; 3]);
+        cipher.seek(203);
+        let mut buffer = [0; 20];
+        cipher.apply_keystream(&mut buffer);
+        assert_eq!(&buffer[..], &expected[203..223]);
+        assert_eq!(cipher.current_pos(), 223);
+    }
+
+    #[test]
+    #[should_panic]
+    fn seek_backwards() {
+        let mut cipher = Trivium::new(&[0; 10], &[0; 10]);
+        cipher.seek(10);
+        cipher.seek(9);
+    }
+
+    #[bench]
+    fn bench(bencher: &mut Bencher) {
+        let mut cipher = Trivium::new(&[0; 10], &[0; 10]);
+        let mut buffer = [0; 1024];
+        bencher.bytes = buffer.len() as u64;
+        bencher.iter(|| {
+            cipher.apply_keystream(&mut buffer);
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use hash::{ExtendableOutput, FixedOutput, Update};
+use permutation::Permutation;
+use permutation::ascon::Ascon12;
+use permutation::sponge::Sponge;
+use secret::Secret;
+
+// See NIST Special Publication 800-232, Sections 5 and 6.
+const HASH256_IV: u64 = 0x0000080100CC0002;
+const XOF128_IV: u64 = 0x0000080000CC0003;
+const CXOF128_IV: u64 = 0x0000080000CC0004;
+
+const RATE: usize = 8;
+
+/// The longest customization string Ascon-CXOF128 accepts, in bytes
+pub const MAX_CUSTOMIZATION_LEN: usize = 256;
+
+fn initial_sponge(iv: u64) -> Sponge<Ascon12> {
+    let zero = Secret::new(0);
+    let mut state = Ascon12::new([Secret::new(iv), zero, zero, zero, zero]);
+    state.permute();
+    Sponge::from_state(state, RATE)
+}
+
+/// Ascon-Hash256
+#[derive(Clone)]
+pub struct AsconHash256 {
+    sponge: Sponge<Ascon12>
+}
+
+impl AsconHash256 {
+    pub fn new() -> AsconHash256 {
+        AsconHash256 {
+            sponge: initial_sponge(HASH256_IV)
+        }
+    }
+}
+
+impl Default for AsconHash256 {
+    fn default() -> AsconHash256 {
+        AsconHash256::new()
+    }
+}
+
+impl Update for AsconHash256 {
+    fn update(&mut self, input: &[u8]) {
+        self.sponge.absorb(input);
+    }
+}
+
+impl FixedOutput for AsconHash256 {
+    fn output_len() -> usize {
+        32
+    }
+
+    fn finalize(mut self, output: &mut [u8]) {
+        assert_eq!(output.len(), 32);
+        self.sponge.pad_simple();
+        self.sponge.squeeze(output);
+    }
+}
+
+/// Ascon-XOF128
+#[derive(Clone)]
+pub struct AsconXof128 {
+    sponge: Sponge<Ascon12>
+}
+
+impl AsconXof128 {
+    pub fn new() -> AsconXof128 {
+        AsconXof128 {
+            sponge: initial_sponge(XOF128_IV)
+        }
+    }
+}
+
+impl Default for AsconXof128 {
+    fn default() -> AsconXof128 {
+        AsconXof128::new()
+    }
+}
+
+impl Update for AsconXof128 {
+    fn update(&mut self, input: &[u8]) {
+        self.sponge.absorb(input);
+    }
+}
+
+impl ExtendableOutput for AsconXof128 {
+    type Reader = Sponge<Ascon12>;
+
+    fn finalize_xof(mut self) -> Sponge<Ascon12> {
+        self.sponge.pad_simple();
+        self.sponge
+    }
+}
+
+/// Ascon-CXOF128, the XOF with a customization string of up to `MAX_CUSTOMIZATION_LEN` bytes
+#[derive(Clone)]
+pub struct AsconCxof128 {
+    sponge: Sponge<Ascon12>
+}
+
+impl AsconCxof128 {
+    pub fn new(customization: &[u8]) -> AsconCxof128 {
+        assert!(customization.len() <= MAX_CUSTOMIZATION_LEN);
+
+        let mut sponge = initial_sponge(CXOF128_IV);
+        // The bit length of the string fills exactly one block, and the padded string follows.
+        let bits = 8 * customization.len() as u64;
+        let encoded: Vec<u8> = (0..8).map(|i| (bits >> (8 * i)) as u8).collect();
+        sponge.absorb(&encoded);
+        sponge.absorb(customization);
+        sponge.absorb(&[0x01]);
+        sponge.zero_pad();
+        AsconCxof128 {
+            sponge: sponge
+        }
+    }
+}
+
+impl Update for AsconCxof128 {
+    fn update(&mut self, input: &[u8]) {
+        self.sponge.absorb(input);
+    }
+}
+
+impl ExtendableOutput for AsconCxof128 {
+    type Reader = Sponge<Ascon12>;
+
+    fn finalize_xof(mut self) -> Sponge<Ascon12> {
+        self.sponge.pad_simple();
+        self.sponge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsconHash256, AsconXof128, AsconCxof128};
+
+    use hash::{ExtendableOutput, FixedOutput, Update, XofReader};
+    use utils::hex;
+
+    // Entries of the reference implementation's KAT files, where the message counts up from 00.
+    // The CXOF customization strings count up from 10.
+    fn message(len: usize) -> Vec<u8> {
+        (0..len as u8).collect()
+    }
+
+    #[test]
+    fn hash256_known_answers() {
+        let kats = [
+            (0, "0B3BE5850F2F6B98CAF29F8FDEA89B64A1FA70AA249B8F839BD53BAA304D92B2"),
+            (1, "0728621035AF3ED2BCA03BF6FDE900F9456F5330E4B5EE23E7F6A1E70291BC80"),
+            (8, "B88E497AE8E6FB641B87EF622EB8F2FCA0ED95383F7FFEBE167ACF1099BA764F"),
+            (9, "94269C30E0296E1EC86655041841823EFA1927F520FD58C8E9BCE6197878C1A6"),
+            (33, "A58665A2CB9530C502096A7957A76E428AF4AD044B4DA5C471F9DA6F7B3E5868")
+        ];
+        for &(len, expected) in kats.iter() {
+            let mut hasher = AsconHash256::new();
+            for byte in message(len).chunks(1) {
+                hasher.update(byte);
+            }
+            let mut output = [0; 32];
+            hasher.finalize(&mut output);
+            assert_eq!(&output[..], &hex::decode(expected)[..]);
+        }
+    }
+
+    #[test]
+    fn xof128_known_answers() {
+        let kats = [
+            (0, "473D5E6164F58B39DFD84AACDB8AE42EC2D91FED33388EE0D960D9B3993295C6"),
+            (1, "51430E0438ECDF642B393630D977625F5F337656BA58AB1E960784AC32A16E0D"),
+            (8, "8D1886F5D3EC4AF8D15B44BC62B74DA6EA91BC28FB82F9C34079B5ED6E38B6C9"),
+            (33, "FEF74B7EBD183BA1D87BF414000B29258D6A2233A2A03ED519C646B351BC0084")
+        ];
+        for &(len, expected) in kats.iter() {
+            let mut hasher = AsconXof128::new();
+            hasher.update(&message(len));
+            let mut reader = hasher.finalize_xof();
+            let mut output = [0; 32];
+            for chunk in output.chunks_mut(5) {
+                reader.read(chunk);
+            }
+            assert_eq!(&output[..], &hex::decode(expected)[..]);
+        }
+    }
+
+    #[test]
+    fn cxof128_known_answers() {
+        let kats = [
+            (0, 0, "4F50159EF70BB3DAD8807E034EAEBD44C4FA2CBBC8CF1F05511AB66CDCC52990"),
+            (0, 1, "0C93A483E7D574D49FE52CCE03EE646117977D57A8AA57704AB4DAF44B501430"),
+            (3, 8, "06F3D9FF8805B673BF10DA89F7E200F6E6B8CB4D35E855150C8F6DB23EC7F237"),
+            (33, 20, "4E450E0DF2D45297BE6D1D68949A00C4AAEED57A11890339071F1A300D584133")
+        ];
+        for &(len, customization_len, expected) in kats.iter() {
+            let customization: Vec<u8> = (0x10..0x10 + customization_len as u8).collect();
+            let mut hasher = AsconCxof128::new(&customization);
+            hasher.update(&message(len));
+            let mut output = [0; 32];
+            hasher.finalize_xof().read(&mut output);
+            assert_eq!(&output[..], &hex::decode(expected)[..]);
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use cipher::block::BlockFn;
+use cipher::block::blowfish::Blowfish;
+use cipher::block::sbox::Indexed;
+use secret::Secret;
+use utils::compare::constant_time_eq;
+
+/// The smallest cost that bcrypt hash strings allow
+pub const MIN_COST: u32 = 4;
+/// The largest cost, at which EksBlowfishSetup runs 2^31 times
+pub const MAX_COST: u32 = 31;
+
+// bcrypt's base64 alphabet, which is not the one from RFC 4648
+const ALPHABET: &'static [u8; 64] = b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+// The text that bcrypt encrypts 64 times
+const MAGIC: &'static [u8; 24] = b"OrpheanBeholderScryDoubt";
+
+// At most this many bytes of the password, counting its terminating zero byte, are used.
+const MAX_KEY_LEN: usize = 72;
+
+/// The variant of bcrypt that a hash string names.
+///
+/// OpenBSD introduced `$2b$` after finding that its `$2a$` implementation wrapped the length of
+/// passwords longer than 255 bytes, and crypt_blowfish uses `$2y$` for its fixed `$2a$`. All three
+/// are computed the same way here, with the password truncated to 72 bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Version {
+    TwoA,
+    TwoB,
+    TwoY
+}
+
+impl Version {
+    fn as_str(self) -> &'static str {
+        match self {
+            Version::TwoA => "2a",
+            Version::TwoB => "2b",
+            Version::TwoY => "2y"
+        }
+    }
+}
+
+/// The error returned when a string is not a valid bcrypt hash
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseError;
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid bcrypt hash string")
+    }
+}
+
+impl Error for ParseError { }
+
+/// bcrypt, from Provos and Mazières, "A Future-Adaptable Password Scheme", returning the 23
+/// bytes of output that hash strings keep.
+///
+/// The key is `password` followed by a zero byte, truncated to 72 bytes, so anything after the
+/// first 72 bytes of `password` is ignored. Zero bytes within `password` are kept, as in Go's and
+/// Python's bcrypt, whereas OpenBSD's takes a C string and so stops at the first one: a password
+/// containing a zero byte gets a different hash here than there. The S-boxes are indexed directly, as in every other
+/// implementation: scanning them in full would make any useful cost far too slow.
+pub fn bcrypt(cost: u32, salt: &[u8; 16], password: &[u8]) -> [u8; 23] {
+    let mut key: Vec<Secret<u8>> = password.iter().map(|&byte| Secret::new(byte)).collect();
+    key.push(Secret::new(0));
+    key.truncate(MAX_KEY_LEN);
+
+    let cipher = Blowfish::<Indexed>::eks_setup(cost, salt, &key);
+    let mut output = [0; 24];
+    for (chunk, out) in MAGIC.chunks(8).zip(output.chunks_mut(8)) {
+        let mut block = Secret::new(chunk.iter().fold(0, |block, &byte| block << 8 | u64::from(byte)));
+        for _ in 0..64 {
+            block = cipher.encrypt(block);
+        }
+        let block = block.expose();
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = (block >> (56 - 8 * i)) as u8;
+        }
+    }
+
+    let mut hash = [0; 23];
+    hash.copy_from_slice(&output[..23]);
+    hash
+}
+
+/// A bcrypt hash in the modular crypt format, such as
+/// `$2b$05$HlFShUxTu4ZHHfOLJwfmCeDj/kuKFKboanXtDJXxCC7aIPTUgxNDe`
+///
+/// Hashes are compared in constant time, like passwords in `verify`.
+#[derive(Clone, Debug)]
+pub struct Bcrypt {
+    version: Version,
+    cost: u32,
+    salt: [u8; 16],
+    hash: [u8; 23]
+}
+
+impl Bcrypt {
+    /// Hashes `password`. Panics unless `cost` is between `MIN_COST` and `MAX_COST`.
+    pub fn new(version: Version, cost: u32, salt: &[u8; 16], password: &[u8]) -> Bcrypt {
+        assert!((MIN_COST..=MAX_COST).contains(&cost), "the cost must be from 4 to 31");
+        Bcrypt {
+            version: version,
+            cost: cost,
+            salt: *salt,
+            hash: bcrypt(cost, salt, password)
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    pub fn salt(&self) -> &[u8; 16] {
+        &self.salt
+    }
+
+    /// Whether `password` has this hash. The hashes are compared in constant time.
+    pub fn verify(&self, password: &[u8]) -> bool {
+        constant_time_eq(&bcrypt(self.cost, &self.salt, password), &self.hash)
+    }
+}
+
+impl PartialEq for Bcrypt {
+    fn eq(&self, other: &Bcrypt) -> bool {
+        let hashes_equal = constant_time_eq(&self.hash, &other.hash);
+        self.version == other.version && self.cost == other.cost && self.salt == other.salt && hashes_equal
+    }
+}
+
+impl Eq for Bcrypt { }
+
+impl fmt::Display for Bcrypt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}${:02}${}{}", self.version.as_str(), self.cost, encode(&self.salt), encode(&self.hash))
+    }
+}
+
+impl FromStr for Bcrypt {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Bcrypt, ParseError> {
+        let fields: Vec<&str> = s.split('$').collect();
+        if fields.len() != 4 || !fields[0].is_empty() {
+            return Err(ParseError);
+        }
+
+        let version = match fields[1] {
+            "2a" => Version::TwoA,
+            "2b" => Version::TwoB,
+            "2y" => Version::TwoY,
+            _ => return Err(ParseError)
+        };
+
+        if fields[2].len() != 2 || !fields[2].bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseError);
+        }
+        let cost = fields[2].parse().map_err(|_| ParseError)?;
+        if !(MIN_COST..=MAX_COST).contains(&cost) {
+            return Err(ParseError);
+        }
+
+        let text = fields[3].as_bytes();
+        if text.len() != 53 {
+            return Err(ParseError);
+        }
+        let mut salt = [0; 16];
+        let mut hash = [0; 23];
+        decode(&text[..22], &mut salt)?;
+        decode(&text[22..], &mut hash)?;
+
+        Ok(Bcrypt {
+            version: version,
+            cost: cost,
+            salt: salt,
+            hash: hash
+        })
+    }
+}
+
+/// Checks `password` against a hash string, comparing the hashes in constant time.
+pub fn verify(password: &[u8], hash: &str) -> Result<bool, ParseError> {
+    Ok(hash.parse::<Bcrypt>()?.verify(password))
+}
+
+// Groups of three bytes become four characters, most significant bits first. There is no
+// padding, so a final group of one or two bytes becomes two or three characters.
+fn encode(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+        for i in 0..chunk.len() + 1 {
+            text.push(ALPHABET[(bits >> (18 - 6 * i)) as usize & 0x3F] as char);
+        }
+    }
+    text
+}
+
+// The inverse of `encode`, filling all of `bytes`. As in OpenBSD, the unused low bits of the last
+// character are ignored.
+fn decode(text: &[u8], bytes: &mut [u8]) -> Result<(), ParseError> {
+    if text.len() != (bytes.len() * 4).div_ceil(3) {
+        return Err(ParseError);
+    }
+    for (chunk, out) in text.chunks(4).zip(bytes.chunks_mut(3)) {
+        let mut bits = 0;
+        for (i, &character) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|&c| c == character).ok_or(ParseError)?;
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = (bits >> (16 - 8 * i)) as u8;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::{Bcrypt, ParseError, Version, verify};
+
+    use self::test::Bencher;
+
+    #[test]
+    fn openwall_vectors() {
+        // From the tests of crypt_blowfish by Solar Designer
+        let vectors = [
+            ("$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW", "U*U"),
+            ("$2a$05$CCCCCCCCCCCCCCCCCCCCC.VGOzA784oUp/Z0DY336zx7pLYAy0lwK", "U*U*"),
+            ("$2a$05$XXXXXXXXXXXXXXXXXXXXXOAcXxm9kjPGEMsLznoKqmqw7tc8WCx4a", "U*U*U"),
+            ("$2a$05$abcdefghijklmnopqrstuu5s2v8.iXieOjg/.AySBTTZIIVFJeBui",
+                "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789chars after 72 are ignored"),
+            ("$2a$05$CCCCCCCCCCCCCCCCCCCCC.7uG0VCzI2bS7j6ymqJi9CdcdxiRTWNy", "")
+        ];
+        for &(hash, password) in vectors.iter() {
+            assert_eq!(verify(password.as_bytes(), hash), Ok(true));
+        }
+    }
+
+    #[test]
+    fn other_implementations() {
+        // Generated with other implementations, including Go's and Python's bcrypt
+        let vectors = [
+            ("$2a$04$UuTkLRZZ6QofpDOlMz32MuuxEHA43WOemOYHPz6.SjsVsyO1tDU96", "password"),
+            ("$2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie", "correctbatteryhorsestapler"),
+            ("$2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi", "correctbatteryhorsestapler"),
+            ("$2y$12$L6Bc/AlTQHyd9liGgGEZyOFLPHNgyxeEPfgYfBCVxJ7JIlwxyVU3u", "hunter2")
+        ];
+        for &(hash, password) in vectors.iter() {
+            assert_eq!(verify(password.as_bytes(), hash), Ok(true));
+        }
+
+        let binary = [
+            29, 225, 195, 167, 223, 236, 85, 195, 114, 227, 7, 0, 209, 239, 189, 24,
+            51, 105, 124, 168, 151, 75, 144, 64, 198, 197, 196, 4, 241, 97, 110, 135
+        ];
+        assert_eq!(verify(&binary, "$2a$04$tjARW6ZON3PhrAIRW2LG/u9aDw5eFdstYLR8nFCNaOQmsH9XD23w."), Ok(true));
+    }
+
+    #[test]
+    fn formats() {
+        let salt = [38, 113, 212, 141, 108, 213, 195, 166, 201, 38, 20, 13, 47, 40, 104, 18];
+        let hash = Bcrypt::new(Version::TwoB, 5, &salt, b"My S3cre7 P@55w0rd!");
+        let text = "$2b$05$HlFShUxTu4ZHHfOLJwfmCeDj/kuKFKboanXtDJXxCC7aIPTUgxNDe";
+        assert_eq!(hash.to_string(), text);
+        assert_eq!(text.parse(), Ok(hash.clone()));
+        assert!(Bcrypt::new(Version::TwoB, 5, &salt, b"My S3cre7 P@55w0rd?") != hash);
+    }
+
+    #[test]
+    fn versions() {
+        let hash = Bcrypt::new(Version::TwoA, 4, &[0; 16], b"hunter2");
+        for &version in [Version::TwoA, Version::TwoB, Version::TwoY].iter() {
+            let text = Bcrypt { version: version, ..hash.clone() }.to_string();
+            let parsed: Bcrypt = text.parse().unwrap();
+            assert_eq!(parsed.version(), version);
+            assert!(parsed.verify(b"hunter2"));
+        }
+    }
+
+    #[test]
+    fn truncates_to_72_bytes() {
+        let long = vec![b'x'; 100];
+        let hash = "$2a$05$......................YgIDy4hFBdVlc/6LHnD9mX488r9cLd2";
+        assert_eq!(verify(&long, hash), Ok(true));
+        assert_eq!(verify(&long[..72], hash), Ok(true));
+        assert_eq!(verify(&long[..71], hash), Ok(false));
+    }
+
+    #[test]
+    fn zero_bytes() {
+        // Every byte of the password counts, unlike in OpenBSD, where these would all collide with
+        // the empty password, "passw0rd", "password" or "a".
+        let passwords: [&[u8]; 6] = [b"\0", b"passw0rd\0", b"password\0with tail", b"\0passw0rd", b"a", b"a\0b\0"];
+        for (i, first) in passwords.iter().enumerate() {
+            let hash = Bcrypt::new(Version::TwoB, 4, &[7; 16], first);
+            for (j, second) in passwords.iter().enumerate() {
+                assert_eq!(hash.verify(second), i == j);
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_password() {
+        let hash = "$2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie";
+        assert_eq!(verify(b"wrong", hash), Ok(false));
+    }
+
+    #[test]
+    fn malformed() {
+        let hashes = [
+            "",
+            "$2a$04$tjARW6ZON3PhrAIRW2LG/u9a.",
+            "$2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk$5bektyVVa5xnIi",
+            "$2a$ab$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+            "$2a$4$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+            "$2a$03$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+            "$2a$32$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+            "$2x$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+            "$2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnI!",
+            "$2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIerererereri",
+            "2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi$"
+        ];
+        for hash in hashes.iter() {
+            assert_eq!(verify(b"correctbatteryhorsestapler", hash), Err(ParseError));
+        }
+    }
+
+    #[bench]
+    fn bench_cost_4(bencher: &mut Bencher) {
+        bencher.iter(|| {
+            Bcrypt::new(Version::TwoB, 4, &[0; 16], b"password")
+        });
+    }
+}
@@ -0,0 +1,41 @@
+use permutation::{ByteState, Permutation};
+use permutation::sponge::Sponge;
+
+pub mod sha3;
+pub mod sp800_185;
+pub mod ascon;
+pub mod sha2;
+pub mod bcrypt;
+
+/// A hash function that can be fed its input incrementally
+pub trait Update {
+    fn update(&mut self, input: &[u8]);
+}
+
+/// A hash function with a fixed output length
+pub trait FixedOutput: Update {
+    /// The size of the digest in bytes
+    fn output_len() -> usize;
+
+    /// Writes the digest into `output`, which must be exactly `output_len()` bytes long.
+    fn finalize(self, output: &mut [u8]);
+}
+
+/// A hash function whose output can be extended to any length
+pub trait ExtendableOutput: Update {
+    type Reader: XofReader;
+
+    fn finalize_xof(self) -> Self::Reader;
+}
+
+/// The output stream of an extendable output function
+pub trait XofReader {
+    /// Fills `output` with the next bytes of the stream.
+    fn read(&mut self, output: &mut [u8]);
+}
+
+impl<P: Permutation + ByteState + Default> XofReader for Sponge<P> {
+    fn read(&mut self, output: &mut [u8]) {
+        self.squeeze(output);
+    }
+}
@@ -0,0 +1,337 @@
+use cipher::block::backend::{Backend, Feature};
+use hash::{FixedOutput, Update};
+use secret::Secret;
+use utils::traits::rotate::RotateRight;
+use utils::traits::wrapping::WrappingAdd;
+
+// See FIPS 180-4, Sections 4.2.2 and 5.3.3.
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+];
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+];
+const BLOCK_LEN: usize = 64;
+
+/// SHA-256, from FIPS 180-4, using the SHA extensions when the CPU has them
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [Secret<u32>; 8],
+    buffer: [u8; BLOCK_LEN],
+    buffered: usize,
+    len: u64,
+    backend: Backend
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        Sha256::with_backend(Backend::detect(Feature::Sha))
+    }
+
+    /// Panics if this CPU does not support `backend`.
+    pub fn with_backend(backend: Backend) -> Sha256 {
+        assert!(backend.is_supported(Feature::Sha), "the SHA extensions are not supported");
+        let mut state = [Secret::new(0); 8];
+        for (word, &initial) in state.iter_mut().zip(INITIAL_STATE.iter()) {
+            *word = Secret::new(initial);
+        }
+
+        Sha256 {
+            state: state,
+            buffer: [0; BLOCK_LEN],
+            buffered: 0,
+            len: 0,
+            backend: backend
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    // Compresses whole blocks.
+    fn compress(&mut self, blocks: &[u8]) {
+        match self.backend {
+            Backend::Portable => {
+                for block in blocks.chunks(BLOCK_LEN) {
+                    compress_portable(&mut self.state, block);
+                }
+            },
+            Backend::Hardware => compress_hardware(&mut self.state, blocks)
+        }
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Sha256 {
+        Sha256::new()
+    }
+}
+
+impl Update for Sha256 {
+    fn update(&mut self, input: &[u8]) {
+        self.len = self.len.wrapping_add(input.len() as u64);
+        let mut input = input;
+
+        if self.buffered > 0 {
+            let len = if input.len() < BLOCK_LEN - self.buffered { input.len() } else { BLOCK_LEN - self.buffered };
+            self.buffer[self.buffered..self.buffered + len].copy_from_slice(&input[..len]);
+            self.buffered += len;
+            input = &input[len..];
+            if self.buffered < BLOCK_LEN {
+                return;
+            }
+            let block = self.buffer;
+            self.compress(&block);
+            self.buffered = 0;
+        }
+
+        let whole = input.len() - input.len() % BLOCK_LEN;
+        self.compress(&input[..whole]);
+        self.buffer[..input.len() - whole].copy_from_slice(&input[whole..]);
+        self.buffered = input.len() - whole;
+    }
+}
+
+impl FixedOutput for Sha256 {
+    fn output_len() -> usize {
+        32
+    }
+
+    fn finalize(mut self, output: &mut [u8]) {
+        assert_eq!(output.len(), 32);
+        let bits = self.len.wrapping_mul(8);
+
+        // A 1 bit, then zeros up to 8 bytes before the end of a block, then the length in bits
+        let mut padding = [0; 2 * BLOCK_LEN];
+        padding[0] = 0x80;
+        let padding_len = if self.buffered < BLOCK_LEN - 8 { BLOCK_LEN - self.buffered } else { 2 * BLOCK_LEN - self.buffered };
+        for i in 0..8 {
+            padding[padding_len - 1 - i] = (bits >> (8 * i)) as u8;
+        }
+        self.update(&padding[..padding_len]);
+
+        for (chunk, &word) in output.chunks_mut(4).zip(self.state.iter()) {
+            let word = word.expose();
+            for (i, byte) in chunk.iter_mut().enumerate() {
+                *byte = (word >> (24 - 8 * i)) as u8;
+            }
+        }
+    }
+}
+
+fn compress_portable(state: &mut [Secret<u32>; 8], block: &[u8]) {
+    let mut schedule = [Secret::new(0); 64];
+    for (word, chunk) in schedule.iter_mut().zip(block.chunks(4)) {
+        *word = Secret::new(chunk.iter().fold(0, |word, &byte| (word << 8) | byte as u32));
+    }
+    for t in 16..64 {
+        let w2 = schedule[t - 2];
+        let w15 = schedule[t - 15];
+        let sigma1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+        let sigma0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+        schedule[t] = sigma1.wrapping_add(schedule[t - 7]).wrapping_add(sigma0).wrapping_add(schedule[t - 16]);
+    }
+
+    let mut v = *state;
+    for (&word, &constant) in schedule.iter().zip(ROUND_CONSTANTS.iter()) {
+        let big_sigma1 = v[4].rotate_right(6) ^ v[4].rotate_right(11) ^ v[4].rotate_right(25);
+        let choose = (v[4] & v[5]) ^ (!v[4] & v[6]);
+        let t1 = v[7].wrapping_add(big_sigma1).wrapping_add(choose).wrapping_add(constant).wrapping_add(word);
+        let big_sigma0 = v[0].rotate_right(2) ^ v[0].rotate_right(13) ^ v[0].rotate_right(22);
+        let majority = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+        let t2 = big_sigma0.wrapping_add(majority);
+        v = [t1.wrapping_add(t2), v[0], v[1], v[2], v[3].wrapping_add(t1), v[4], v[5], v[6]];
+    }
+
+    for (word, &new) in state.iter_mut().zip(v.iter()) {
+        *word = word.wrapping_add(new);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn compress_hardware(state: &mut [Secret<u32>; 8], blocks: &[u8]) {
+    let mut words = [0; 8];
+    for (word, &secret) in words.iter_mut().zip(state.iter()) {
+        *word = secret.expose();
+    }
+    unsafe { x86_64::compress(&mut words, blocks) };
+    for (secret, &word) in state.iter_mut().zip(words.iter()) {
+        *secret = Secret::new(word);
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn compress_hardware(_state: &mut [Secret<u32>; 8], _blocks: &[u8]) {
+    unreachable!()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use std::arch::x86_64::*;
+
+    use super::{BLOCK_LEN, ROUND_CONSTANTS};
+
+    // Four more words of the message schedule from the previous sixteen
+    #[inline(always)]
+    unsafe fn schedule(w0: __m128i, w1: __m128i, w2: __m128i, w3: __m128i) -> __m128i {
+        let w = _mm_add_epi32(_mm_sha256msg1_epu32(w0, w1), _mm_alignr_epi8(w3, w2, 4));
+        _mm_sha256msg2_epu32(w, w3)
+    }
+
+    /// Compresses whole blocks with SHA-NI. The eight state words are packed as ABEF and CDGH,
+    /// as `sha256rnds2` needs, and each call does two rounds.
+    #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+    pub unsafe fn compress(state: &mut [u32; 8], blocks: &[u8]) {
+        let byte_swap = _mm_set_epi64x(0x0C0D0E0F08090A0B, 0x0405060700010203);
+
+        let dcba = _mm_loadu_si128(state.as_ptr() as *const __m128i);
+        let hgfe = _mm_loadu_si128(state.as_ptr().offset(4) as *const __m128i);
+        let cdab = _mm_shuffle_epi32(dcba, 0xB1);
+        let efgh = _mm_shuffle_epi32(hgfe, 0x1B);
+        let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+        let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xF0);
+
+        for block in blocks.chunks(BLOCK_LEN) {
+            let (abef_saved, cdgh_saved) = (abef, cdgh);
+
+            let mut words = [_mm_setzero_si128(); 16];
+            for i in 0..4 {
+                let chunk = _mm_loadu_si128(block[16 * i..].as_ptr() as *const __m128i);
+                words[i] = _mm_shuffle_epi8(chunk, byte_swap);
+            }
+            for i in 4..16 {
+                words[i] = schedule(words[i - 4], words[i - 3], words[i - 2], words[i - 1]);
+            }
+
+            for (i, &w) in words.iter().enumerate() {
+                let k = &ROUND_CONSTANTS[4 * i..4 * i + 4];
+                let constants = _mm_set_epi32(k[3] as i32, k[2] as i32, k[1] as i32, k[0] as i32);
+                let input = _mm_add_epi32(w, constants);
+                cdgh = _mm_sha256rnds2_epu32(cdgh, abef, input);
+                abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(input, 0x0E));
+            }
+
+            abef = _mm_add_epi32(abef, abef_saved);
+            cdgh = _mm_add_epi32(cdgh, cdgh_saved);
+        }
+
+        let feba = _mm_shuffle_epi32(abef, 0x1B);
+        let dchg = _mm_shuffle_epi32(cdgh, 0xB1);
+        let dcba = _mm_blend_epi16(feba, dchg, 0xF0);
+        let hgef = _mm_alignr_epi8(dchg, feba, 8);
+        _mm_storeu_si128(state.as_mut_ptr() as *mut __m128i, dcba);
+        _mm_storeu_si128(state.as_mut_ptr().offset(4) as *mut __m128i, hgef);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    extern crate rand;
+
+    use super::Sha256;
+
+    use cipher::block::backend::{Backend, Feature, backends};
+    use hash::{FixedOutput, Update};
+    use utils::hex;
+
+    use self::test::Bencher;
+    use self::rand::{Rng, thread_rng};
+
+    fn digest(backend: Backend, input: &[u8]) -> Vec<u8> {
+        let mut hash = Sha256::with_backend(backend);
+        hash.update(input);
+        let mut output = vec![0; 32];
+        hash.finalize(&mut output);
+        output
+    }
+
+    #[test]
+    fn fips180_examples() {
+        // FIPS 180-2, Appendix B, and the empty string
+        let examples: [(&[u8], &str); 3] = [
+            (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+            (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        ];
+        for &backend in backends(Feature::Sha).iter() {
+            for &(input, expected) in examples.iter() {
+                assert_eq!(digest(backend, input), hex::decode(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn million_a() {
+        for &backend in backends(Feature::Sha).iter() {
+            let mut hash = Sha256::with_backend(backend);
+            let chunk = [b'a'; 1000];
+            for _ in 0..1000 {
+                hash.update(&chunk);
+            }
+            let mut output = [0; 32];
+            hash.finalize(&mut output);
+            assert_eq!(&output[..], &hex::decode("cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0")[..]);
+        }
+    }
+
+    #[test]
+    fn backends_agree() {
+        if !Backend::Hardware.is_supported(Feature::Sha) {
+            return;
+        }
+        let mut rng = thread_rng();
+        for len in 0..300 {
+            let input: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            assert_eq!(digest(Backend::Portable, &input), digest(Backend::Hardware, &input));
+        }
+    }
+
+    #[test]
+    fn split_updates() {
+        let input: Vec<u8> = (0..500).map(|i| i as u8).collect();
+        let expected = digest(Backend::Portable, &input);
+        for &split in [1, 63, 64, 65, 200].iter() {
+            let mut hash = Sha256::new();
+            for chunk in input.chunks(split) {
+                hash.update(chunk);
+            }
+            let mut output = vec![0; 32];
+            hash.finalize(&mut output);
+            assert_eq!(output, expected);
+        }
+    }
+
+    fn bench_backend(bencher: &mut Bencher, backend: Backend) {
+        if !backend.is_supported(Feature::Sha) {
+            return;
+        }
+        let input = [0; 1024];
+        bencher.bytes = input.len() as u64;
+        bencher.iter(|| {
+            let mut hash = Sha256::with_backend(backend);
+            hash.update(&input);
+            let mut output = [0; 32];
+            hash.finalize(&mut output);
+            output
+        });
+    }
+
+    #[bench]
+    fn bench_portable(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Portable);
+    }
+
+    #[bench]
+    fn bench_hardware(bencher: &mut Bencher) {
+        bench_backend(bencher, Backend::Hardware);
+    }
+}
@@ -0,0 +1,211 @@
+use hash::{ExtendableOutput, FixedOutput, Update};
+use permutation::keccak::KeccakF1600;
+use permutation::sponge::Sponge;
+
+// See FIPS Publication 202, Section 6. The domain bytes hold the suffixes 01 and 1111 followed by
+// the first bit of padding.
+const SHA3_DOMAIN: u8 = 0x06;
+const SHAKE_DOMAIN: u8 = 0x1F;
+
+macro_rules! sha3_impl {
+    { $name:ident, $rate:expr, $output_len:expr } => {
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge<KeccakF1600>
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    sponge: Sponge::new($rate)
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name::new()
+            }
+        }
+
+        impl Update for $name {
+            fn update(&mut self, input: &[u8]) {
+                self.sponge.absorb(input);
+            }
+        }
+
+        impl FixedOutput for $name {
+            fn output_len() -> usize {
+                $output_len
+            }
+
+            fn finalize(mut self, output: &mut [u8]) {
+                assert_eq!(output.len(), $output_len);
+                self.sponge.pad(SHA3_DOMAIN);
+                self.sponge.squeeze(output);
+            }
+        }
+    };
+}
+
+sha3_impl! { Sha3_224, 144, 28 }
+sha3_impl! { Sha3_256, 136, 32 }
+sha3_impl! { Sha3_384, 104, 48 }
+sha3_impl! { Sha3_512, 72, 64 }
+
+macro_rules! shake_impl {
+    { $name:ident, $rate:expr } => {
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge<KeccakF1600>
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    sponge: Sponge::new($rate)
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name::new()
+            }
+        }
+
+        impl Update for $name {
+            fn update(&mut self, input: &[u8]) {
+                self.sponge.absorb(input);
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = Sponge<KeccakF1600>;
+
+            fn finalize_xof(mut self) -> Sponge<KeccakF1600> {
+                self.sponge.pad(SHAKE_DOMAIN);
+                self.sponge
+            }
+        }
+    };
+}
+
+shake_impl! { Shake128, 168 }
+shake_impl! { Shake256, 136 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Sha3_224, Sha3_256, Sha3_384, Sha3_512, Shake128, Shake256};
+
+    use hash::{ExtendableOutput, FixedOutput, XofReader};
+    use utils::hex;
+
+    // From the NIST CAVP SHA3 and SHAKE byte-oriented test vectors
+    const MSG_17: &'static str = "82e192e4043ddcd12ecf52969d0f807eed";
+    const MSG_200: &'static str = "
+        8c3798e51bc68482d7337d3abb75dc9ffe860714a9ad73551e120059860dde24ab87327222b64cf774415a70f724cdf2
+        70de3fe47dda07b61c9ef2a3551f45a5584860248fabde676e1cd75f6355aa3eaeabe3b51dc813d9fb2eaa4f0f1d9f83
+        4d7cad9c7c695ae84b329385bc0bef895b9f1edf44a03d4b410cc23a79a6b62e4f346a5e8dd851c2857995ddbf5b2d71
+        7aeb847310e1f6a46ac3d26a7f9b44985af656d2b7c9406e8a9e8f47dcb4ef6b83caacf9aefb6118bfcff7e44bef6937
+        ebddc89186839b77";
+
+    fn check_fixed<H: FixedOutput + Default>(msg: &str, expected: &str) {
+        let input = hex::decode(msg);
+        let expected = hex::decode(expected);
+
+        let mut hasher = H::default();
+        hasher.update(&input);
+        let mut output = vec![0; H::output_len()];
+        hasher.finalize(&mut output);
+        assert_eq!(output, expected);
+
+        // Feeding the input a byte at a time must not change the result.
+        let mut hasher = H::default();
+        for byte in input.chunks(1) {
+            hasher.update(byte);
+        }
+        let mut output = vec![0; H::output_len()];
+        hasher.finalize(&mut output);
+        assert_eq!(output, expected);
+    }
+
+    fn check_xof<H: ExtendableOutput + Default>(msg: &str, expected: &str) {
+        let input = hex::decode(msg);
+        let expected = hex::decode(expected);
+
+        let mut hasher = H::default();
+        hasher.update(&input);
+        let mut reader = hasher.finalize_xof();
+        let mut output = vec![0; expected.len()];
+        reader.read(&mut output);
+        assert_eq!(output, expected);
+
+        let mut hasher = H::default();
+        hasher.update(&input);
+        let mut reader = hasher.finalize_xof();
+        let mut output = vec![0; expected.len()];
+        for chunk in output.chunks_mut(7) {
+            reader.read(chunk);
+        }
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn sha3_224_known_answers() {
+        check_fixed::<Sha3_224>("", "6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7");
+        check_fixed::<Sha3_224>(MSG_17, "08045cf78d238d56972f1c850414bc404fc6dcb11f8d8210d034c610");
+        check_fixed::<Sha3_224>(MSG_200, "ec5c6db60b0834fb2e0e7106aeeafb9e614be093c847018214d8a5db");
+    }
+
+    #[test]
+    fn sha3_256_known_answers() {
+        check_fixed::<Sha3_256>("", "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a");
+        check_fixed::<Sha3_256>(MSG_17, "c7b12eff692d842110cc39ac60616707acb3f9b0f1cb361b94577efc529ca26c");
+        check_fixed::<Sha3_256>(MSG_200, "84970c79316e89b70e2b186a69db1a4c3e33c7a376b45c1b79bd346dd33ef4ce");
+    }
+
+    #[test]
+    fn sha3_384_known_answers() {
+        check_fixed::<Sha3_384>(MSG_17, "
+            1888e953727cb837de40c69869560c20729c50638e4561b385937bfc4c297e789ea6c03efcf2df3290b1fd36be268c32");
+        check_fixed::<Sha3_384>(MSG_200, "
+            27cef65d1aecb7051bad55da0d601bc9d7a16d938a5715374a43109dd41b5c27d26c91cb44e4b47002d9b90aba0584d1");
+    }
+
+    #[test]
+    fn sha3_512_known_answers() {
+        check_fixed::<Sha3_512>(MSG_17, "
+            9644e3c90b67e22124e96dfedce53d33c460f132868f0975d18b22cfd59f637d
+            d85aa405e39808a45570a498c0b8f2cba59f8e1437eaef89f20b88298adfa2de");
+        check_fixed::<Sha3_512>(MSG_200, "
+            a8aee42a77c9b6387dc97319581959d9bd878d061487fd069aca04d6f84d347e
+            23587a6c7c56329b2df88c56c7100ed51ace5b5f778d65478f059cafd6c098fd");
+    }
+
+    #[test]
+    fn shake128_known_answers() {
+        check_xof::<Shake128>("", "
+            7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef263cb1eea988004b93103cfb0aeefd2a68
+            6e01fa4a58e8a3639ca8a1e3f9ae57e235b8cc873c23dc62b8d260169afa2f75ab916a58d974918835d25e6a435085b2
+            badfd6dfaac359a5efbb7bcc4b59d538df9a04302e10c8bc1cbf1a0b3a5120ea17cda7cfad765f5623474d368ccca8af
+            0007cd9f5e4c849f167a580b14aabdefaee7eef47cb0fca9767be1fda69419dfb927e9df07348b196691abaeb580b32d");
+        check_xof::<Shake128>(MSG_17, "
+            3d62e50de92bf40637d481cedbd22f2dbabaeec0e94d1f99f3ef2e68b1f55d97ada13d2372104d63946e3eb7c50d7e58
+            f5aa0d5730e4366bbcb6cd332aef45da6c4e5efffda948e4e7e6fb9c49b46ac954cbd53a925c8ebf73bc4fd53ecf34f0
+            d67026136e8cff3c8b8661c9d5d19420509b47a19d4c848c83b791d1f843f8df7f69f736a414bb9075b4bb6070fa094a
+            0095f101fe569e45e4e7fdd2b3622b5b912c21be28208ce90c7c342e6de485d7344dcf3e89d9c71f320001c52054ead0");
+    }
+
+    #[test]
+    fn shake256_known_answers() {
+        check_xof::<Shake256>("", "
+            46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6
+            fc821c49479ab48640292eacb3b7c4be141e96616fb13957692cc7edd0b45ae3dc07223c8e92937bef84bc0eab862853
+            349ec75546f58fb7c2775c38462c5010d846c185c15111e595522a6bcd16cf86f3d122109e3b1fdd943b6aec468a2d62");
+        check_xof::<Shake256>(MSG_200, "
+            3340b37aedd2f0c66f2483abdc66c97b45055275231f1c7a925687b946c9135bb75aab2d11e6463073a1a8ced0ea83a4
+            2736ae85d1b99d1d2ee9aafb6fb9f4e7abacb39bc9f6d59883a9d1b0df86c259394a05842684e8a4573f1a3ceb46ece5
+            9df77259a0b025b831c2cc0dae259551c86db7de0c434fcbc35328810201903b92802cd5dacf9a6f4adcb05ec5e0629a");
+    }
+}
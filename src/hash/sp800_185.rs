@@ -0,0 +1,497 @@
+// The cSHAKE-derived functions of NIST Special Publication 800-185.
+
+use aead::AuthenticationError;
+use hash::{ExtendableOutput, Update, XofReader};
+use hash::sha3::{Shake128, Shake256};
+use permutation::keccak::KeccakF1600;
+use permutation::sponge::Sponge;
+use utils::compare::constant_time_eq;
+
+// See Section 3.3. The suffix 00 followed by the first bit of padding.
+const CSHAKE_DOMAIN: u8 = 0x04;
+// cSHAKE with an empty name and customization string is defined to be plain SHAKE.
+const SHAKE_DOMAIN: u8 = 0x1F;
+
+/// The shortest tag KMAC `verify` accepts, in bytes. Section 8.4.2 advises against KMAC outputs
+/// shorter than 32 bits.
+pub const MIN_TAG_LEN: usize = 4;
+
+/// Encodes `val` with its length in bytes prepended, as in Section 2.3.1.
+fn left_encode(val: u64) -> Vec<u8> {
+    let mut encoded = right_encode(val);
+    let len = encoded.pop().unwrap();
+    encoded.insert(0, len);
+    encoded
+}
+
+/// Encodes `val` with its length in bytes appended, as in Section 2.3.1.
+fn right_encode(val: u64) -> Vec<u8> {
+    let mut encoded: Vec<u8> = (0..8).rev().map(|i| (val >> (8 * i)) as u8).skip_while(|&b| b == 0).collect();
+    if encoded.is_empty() {
+        encoded.push(0);
+    }
+    let len = encoded.len() as u8;
+    encoded.push(len);
+    encoded
+}
+
+fn absorb_encoded_string(sponge: &mut Sponge<KeccakF1600>, string: &[u8]) {
+    sponge.absorb(&left_encode(8 * string.len() as u64));
+    sponge.absorb(string);
+}
+
+/// Creates a sponge that has absorbed bytepad(encode_string(name) || encode_string(customization)),
+/// or nothing at all if both strings are empty.
+fn cshake_sponge(rate: usize, name: &[u8], customization: &[u8]) -> Sponge<KeccakF1600> {
+    let mut sponge = Sponge::new(rate);
+    if !name.is_empty() || !customization.is_empty() {
+        sponge.absorb(&left_encode(rate as u64));
+        absorb_encoded_string(&mut sponge, name);
+        absorb_encoded_string(&mut sponge, customization);
+        sponge.zero_pad();
+    }
+    sponge
+}
+
+macro_rules! cshake_impl {
+    { $name:ident, $rate:expr } => {
+        /// cSHAKE, customizable by a function name (reserved for NIST) and a user-chosen string
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge<KeccakF1600>,
+            domain: u8
+        }
+
+        impl $name {
+            pub fn new(name: &[u8], customization: &[u8]) -> $name {
+                $name {
+                    sponge: cshake_sponge($rate, name, customization),
+                    domain: if name.is_empty() && customization.is_empty() { SHAKE_DOMAIN } else { CSHAKE_DOMAIN }
+                }
+            }
+        }
+
+        impl Update for $name {
+            fn update(&mut self, input: &[u8]) {
+                self.sponge.absorb(input);
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = Sponge<KeccakF1600>;
+
+            fn finalize_xof(mut self) -> Sponge<KeccakF1600> {
+                self.sponge.pad(self.domain);
+                self.sponge
+            }
+        }
+    };
+}
+
+cshake_impl! { CShake128, 168 }
+cshake_impl! { CShake256, 136 }
+
+macro_rules! kmac_impl {
+    { $name:ident, $cshake:ident, $rate:expr } => {
+        /// KMAC, a keyed hash with an output of any length.
+        ///
+        /// `finalize` commits to the requested output length, so tags of different lengths are
+        /// unrelated. The `ExtendableOutput` implementation is KMACXOF, which does not.
+        #[derive(Clone)]
+        pub struct $name {
+            cshake: $cshake
+        }
+
+        impl $name {
+            pub fn new(key: &[u8], customization: &[u8]) -> $name {
+                let mut cshake = $cshake::new(b"KMAC", customization);
+                cshake.sponge.absorb(&left_encode($rate));
+                absorb_encoded_string(&mut cshake.sponge, key);
+                cshake.sponge.zero_pad();
+                $name {
+                    cshake: cshake
+                }
+            }
+
+            pub fn finalize(mut self, output: &mut [u8]) {
+                self.cshake.update(&right_encode(8 * output.len() as u64));
+                self.cshake.finalize_xof().read(output);
+            }
+
+            /// Checks `tag` in constant time, using its length as the output length. Tags shorter
+            /// than `MIN_TAG_LEN` are rejected.
+            pub fn verify(self, tag: &[u8]) -> Result<(), AuthenticationError> {
+                if tag.len() < MIN_TAG_LEN {
+                    return Err(AuthenticationError);
+                }
+                let mut expected = vec![0; tag.len()];
+                self.finalize(&mut expected);
+                if constant_time_eq(&expected, tag) {
+                    Ok(())
+                } else {
+                    Err(AuthenticationError)
+                }
+            }
+        }
+
+        impl Update for $name {
+            fn update(&mut self, input: &[u8]) {
+                self.cshake.update(input);
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = Sponge<KeccakF1600>;
+
+            fn finalize_xof(mut self) -> Sponge<KeccakF1600> {
+                self.cshake.update(&right_encode(0));
+                self.cshake.finalize_xof()
+            }
+        }
+    };
+}
+
+kmac_impl! { Kmac128, CShake128, 168 }
+kmac_impl! { Kmac256, CShake256, 136 }
+
+macro_rules! tuple_hash_impl {
+    { $name:ident, $cshake:ident } => {
+        /// TupleHash, which hashes a sequence of strings such that the boundaries between them
+        /// matter.
+        #[derive(Clone)]
+        pub struct $name {
+            cshake: $cshake
+        }
+
+        impl $name {
+            pub fn new(customization: &[u8]) -> $name {
+                $name {
+                    cshake: $cshake::new(b"TupleHash", customization)
+                }
+            }
+
+            /// Appends a whole element to the tuple.
+            pub fn push(&mut self, element: &[u8]) {
+                absorb_encoded_string(&mut self.cshake.sponge, element);
+            }
+
+            pub fn finalize(mut self, output: &mut [u8]) {
+                self.cshake.update(&right_encode(8 * output.len() as u64));
+                self.cshake.finalize_xof().read(output);
+            }
+
+            /// TupleHashXOF
+            pub fn finalize_xof(mut self) -> Sponge<KeccakF1600> {
+                self.cshake.update(&right_encode(0));
+                self.cshake.finalize_xof()
+            }
+        }
+    };
+}
+
+tuple_hash_impl! { TupleHash128, CShake128 }
+tuple_hash_impl! { TupleHash256, CShake256 }
+
+macro_rules! parallel_hash_impl {
+    { $name:ident, $cshake:ident, $shake:ident, $chaining_len:expr } => {
+        /// ParallelHash, which hashes blocks of `block_size` bytes independently before combining
+        /// them. This implementation processes the blocks one after another.
+        #[derive(Clone)]
+        pub struct $name {
+            cshake: $cshake,
+            block_size: usize,
+            block: $shake,
+            block_len: usize,
+            blocks: u64
+        }
+
+        impl $name {
+            pub fn new(customization: &[u8], block_size: usize) -> $name {
+                assert!(block_size > 0);
+
+                let mut cshake = $cshake::new(b"ParallelHash", customization);
+                cshake.update(&left_encode(block_size as u64));
+                $name {
+                    cshake: cshake,
+                    block_size: block_size,
+                    block: $shake::new(),
+                    block_len: 0,
+                    blocks: 0
+                }
+            }
+
+            pub fn finalize(self, output: &mut [u8]) {
+                let len = 8 * output.len() as u64;
+                self.finish(len).read(output);
+            }
+
+            fn finish_block(&mut self) {
+                let block = ::std::mem::take(&mut self.block);
+                let mut chaining = [0; $chaining_len];
+                block.finalize_xof().read(&mut chaining);
+                self.cshake.update(&chaining);
+                self.block_len = 0;
+                self.blocks += 1;
+            }
+
+            fn finish(mut self, output_bits: u64) -> Sponge<KeccakF1600> {
+                if self.block_len != 0 {
+                    self.finish_block();
+                }
+                let blocks = self.blocks;
+                self.cshake.update(&right_encode(blocks));
+                self.cshake.update(&right_encode(output_bits));
+                self.cshake.finalize_xof()
+            }
+        }
+
+        impl Update for $name {
+            fn update(&mut self, input: &[u8]) {
+                let mut remaining = input;
+                while !remaining.is_empty() {
+                    let space = self.block_size - self.block_len;
+                    let len = if remaining.len() < space { remaining.len() } else { space };
+                    let (piece, rest) = remaining.split_at(len);
+                    self.block.update(piece);
+                    self.block_len += len;
+                    remaining = rest;
+
+                    if self.block_len == self.block_size {
+                        self.finish_block();
+                    }
+                }
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = Sponge<KeccakF1600>;
+
+            fn finalize_xof(self) -> Sponge<KeccakF1600> {
+                self.finish(0)
+            }
+        }
+    };
+}
+
+parallel_hash_impl! { ParallelHash128, CShake128, Shake128, 32 }
+parallel_hash_impl! { ParallelHash256, CShake256, Shake256, 64 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CShake128, CShake256, Kmac128, Kmac256, TupleHash128, TupleHash256, ParallelHash128, ParallelHash256,
+                MIN_TAG_LEN, left_encode, right_encode};
+
+    use aead::AuthenticationError;
+    use hash::{ExtendableOutput, Update, XofReader};
+    use hash::sha3::Shake128;
+    use utils::hex;
+
+    // The samples below are from the NIST SP 800-185 example values.
+    fn key() -> Vec<u8> {
+        (0x40..0x60).collect()
+    }
+
+    fn data_200() -> Vec<u8> {
+        (0..200).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(left_encode(0), vec![1, 0]);
+        assert_eq!(right_encode(0), vec![0, 1]);
+        assert_eq!(left_encode(168), vec![1, 168]);
+        assert_eq!(left_encode(0x1234), vec![2, 0x12, 0x34]);
+        assert_eq!(right_encode(0x1234), vec![0x12, 0x34, 2]);
+    }
+
+    #[test]
+    fn cshake_known_answers() {
+        let mut output = [0; 32];
+        let mut cshake = CShake128::new(b"", b"Email Signature");
+        cshake.update(&[0, 1, 2, 3]);
+        cshake.finalize_xof().read(&mut output);
+        assert_eq!(&output[..], &hex::decode("c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5")[..]);
+
+        let mut output = [0; 64];
+        let mut cshake = CShake256::new(b"", b"Email Signature");
+        cshake.update(&[0, 1, 2, 3]);
+        cshake.finalize_xof().read(&mut output);
+        assert_eq!(&output[..], &hex::decode("
+            d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1
+            64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c")[..]);
+    }
+
+    #[test]
+    fn cshake_without_strings_is_shake() {
+        let mut expected = [0; 32];
+        let mut shake = Shake128::new();
+        shake.update(b"abc");
+        shake.finalize_xof().read(&mut expected);
+
+        let mut output = [0; 32];
+        let mut cshake = CShake128::new(b"", b"");
+        cshake.update(b"abc");
+        cshake.finalize_xof().read(&mut output);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn kmac_known_answers() {
+        let mut output = [0; 32];
+        let mut kmac = Kmac128::new(&key(), b"");
+        kmac.update(&[0, 1, 2, 3]);
+        kmac.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e")[..]);
+
+        let mut output = [0; 32];
+        let mut kmac = Kmac128::new(&key(), b"My Tagged Application");
+        kmac.update(&data_200());
+        kmac.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230")[..]);
+
+        let mut output = [0; 64];
+        let mut kmac = Kmac256::new(&key(), b"My Tagged Application");
+        kmac.update(&[0, 1, 2, 3]);
+        kmac.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("
+            20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7
+            f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd")[..]);
+
+        let mut output = [0; 64];
+        let mut kmac = Kmac256::new(&key(), b"");
+        kmac.update(&data_200());
+        kmac.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("
+            75358cf39e41494e949707927cee0af20a3ff553904c86b08f21cc414bcfd691
+            589d27cf5e15369cbbff8b9a4c2eb17800855d0235ff635da82533ec6b759b69")[..]);
+    }
+
+    #[test]
+    fn kmac_xof_known_answers() {
+        let mut output = [0; 32];
+        let mut kmac = Kmac128::new(&key(), b"");
+        kmac.update(&[0, 1, 2, 3]);
+        kmac.finalize_xof().read(&mut output);
+        assert_eq!(&output[..], &hex::decode("cd83740bbd92ccc8cf032b1481a0f4460e7ca9dd12b08a0c4031178bacd6ec35")[..]);
+
+        let mut output = [0; 64];
+        let mut kmac = Kmac256::new(&key(), b"My Tagged Application");
+        kmac.update(&data_200());
+        kmac.finalize_xof().read(&mut output);
+        assert_eq!(&output[..], &hex::decode("
+            d5be731c954ed7732846bb59dbe3a8e30f83e77a4bff4459f2f1c2b4ecebb8ce
+            67ba01c62e8ab8578d2d499bd1bb276768781190020a306a97de281dcc30305d")[..]);
+    }
+
+    #[test]
+    fn kmac_verify() {
+        let mut tag = [0; 32];
+        let mut kmac = Kmac128::new(&key(), b"");
+        kmac.update(b"message");
+        kmac.clone().finalize(&mut tag);
+        assert_eq!(kmac.clone().verify(&tag), Ok(()));
+
+        tag[31] ^= 1;
+        assert_eq!(kmac.clone().verify(&tag), Err(AuthenticationError));
+        // A truncated tag is a different output length, not a prefix.
+        assert_eq!(kmac.verify(&tag[..16]), Err(AuthenticationError));
+    }
+
+    #[test]
+    fn kmac_verify_short_tags() {
+        let mut kmac = Kmac128::new(&key(), b"");
+        kmac.update(b"message");
+        for len in 0..MIN_TAG_LEN + 1 {
+            let mut tag = vec![0; len];
+            kmac.clone().finalize(&mut tag);
+            let expected = if len < MIN_TAG_LEN { Err(AuthenticationError) } else { Ok(()) };
+            assert_eq!(kmac.clone().verify(&tag), expected);
+        }
+    }
+
+    #[test]
+    fn tuple_hash_known_answers() {
+        let mut output = [0; 32];
+        let mut tuple = TupleHash128::new(b"");
+        tuple.push(&[0, 1, 2]);
+        tuple.push(&[0x10, 0x11, 0x12, 0x13, 0x14, 0x15]);
+        tuple.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1")[..]);
+
+        let mut output = [0; 32];
+        let mut tuple = TupleHash128::new(b"My Tuple App");
+        tuple.push(&[0, 1, 2]);
+        tuple.push(&[0x10, 0x11, 0x12, 0x13, 0x14, 0x15]);
+        tuple.push(&[0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28]);
+        tuple.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("e60f202c89a2631eda8d4c588ca5fd07f39e5151998deccf973adb3804bb6e84")[..]);
+
+        let mut output = [0; 64];
+        let mut tuple = TupleHash256::new(b"My Tuple App");
+        tuple.push(&[0, 1, 2]);
+        tuple.push(&[0x10, 0x11, 0x12, 0x13, 0x14, 0x15]);
+        tuple.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("
+            147c2191d5ed7efd98dbd96d7ab5a11692576f5fe2a5065f3e33de6bba9f3aa1
+            c4e9a068a289c61c95aab30aee1e410b0b607de3620e24a4e3bf9852a1d4367e")[..]);
+    }
+
+    #[test]
+    fn tuple_hash_separates_elements() {
+        let mut first = [0; 32];
+        let mut tuple = TupleHash128::new(b"");
+        tuple.push(b"ab");
+        tuple.push(b"c");
+        tuple.finalize(&mut first);
+
+        let mut second = [0; 32];
+        let mut tuple = TupleHash128::new(b"");
+        tuple.push(b"a");
+        tuple.push(b"bc");
+        tuple.finalize(&mut second);
+        assert!(first != second);
+    }
+
+    #[test]
+    fn parallel_hash_known_answers() {
+        let data = hex::decode("000102030405060710111213141516172021222324252627");
+
+        let mut output = [0; 32];
+        let mut hash = ParallelHash128::new(b"", 8);
+        hash.update(&data);
+        hash.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("ba8dc1d1d979331d3f813603c67f72609ab5e44b94a0b8f9af46514454a2b4f5")[..]);
+
+        let mut output = [0; 32];
+        let mut hash = ParallelHash128::new(b"Parallel Data", 8);
+        hash.update(&data);
+        hash.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("fc484dcb3f84dceedc353438151bee58157d6efed0445a81f165e495795b7206")[..]);
+
+        let mut output = [0; 64];
+        let mut hash = ParallelHash256::new(b"Parallel Data", 8);
+        hash.update(&data);
+        hash.finalize(&mut output);
+        assert_eq!(&output[..], &hex::decode("
+            cdf15289b54f6212b4bc270528b49526006dd9b54e2b6add1ef6900dda3963bb
+            33a72491f236969ca8afaea29c682d47a393c065b38e29fae651a2091c833110")[..]);
+    }
+
+    #[test]
+    fn parallel_hash_incremental() {
+        let data = data_200();
+
+        let mut expected = [0; 40];
+        let mut hash = ParallelHash256::new(b"", 12);
+        hash.update(&data);
+        hash.finalize_xof().read(&mut expected);
+
+        let mut output = [0; 40];
+        let mut hash = ParallelHash256::new(b"", 12);
+        for piece in data.chunks(5) {
+            hash.update(piece);
+        }
+        hash.finalize_xof().read(&mut output);
+        assert_eq!(&output[..], &expected[..]);
+    }
+}
@@ -3,8 +3,10 @@
 
 extern crate typenum;
 
+pub mod aead;
 pub mod array;
 pub mod cipher;
+pub mod hash;
 pub mod keyed;
 pub mod secret;
 pub mod permutation;
@@ -0,0 +1,155 @@
+use permutation::{ByteState, Permutation};
+
+/// The domain bits used by plain `absorb`, `squeeze` and `duplex` calls: no bits at all, just the
+/// terminating 1.
+const NO_DOMAIN: u8 = 0x01;
+
+/// A full-state keyed duplex object.
+///
+/// Since the state is keyed, every duplexing call may absorb into the whole state, not just the
+/// outer `rate` bytes; only output is restricted to the rate. Each call absorbs its (padded) input,
+/// applies the permutation, and then reads its output from the freshly permuted state.
+pub struct KeyedDuplex<P> {
+    state: P,
+    rate: usize
+}
+
+impl<P: Permutation + ByteState + Default> KeyedDuplex<P> {
+    /// Creates a duplex object keyed with `key`, which may be at most one byte smaller than the
+    /// state. `rate` is the number of bytes that can be read out after each call.
+    pub fn new(key: &[u8], rate: usize) -> KeyedDuplex<P> {
+        assert!(rate > 0 && rate < P::width(), "rate must leave some capacity");
+
+        let mut duplex = KeyedDuplex {
+            state: P::default(),
+            rate: rate
+        };
+        duplex.duplex(key, &mut []);
+        duplex
+    }
+
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Absorbs `input` in blocks of up to one byte less than the state width. An empty input still
+    /// results in a single (empty) block.
+    pub fn absorb(&mut self, input: &[u8]) {
+        let block_size = P::width() - 1;
+        let mut remaining = input;
+        loop {
+            let len = if remaining.len() < block_size { remaining.len() } else { block_size };
+            let (block, rest) = remaining.split_at(len);
+            self.duplex(block, &mut []);
+            remaining = rest;
+
+            if remaining.is_empty() {
+                break;
+            }
+        }
+    }
+
+    /// Fills `output`, making one empty duplexing call per `rate` bytes.
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        let rate = self.rate;
+        for block in output.chunks_mut(rate) {
+            self.duplex(&[], block);
+        }
+    }
+
+    /// A single duplexing call: absorbs `input`, permutes the state, and reads `output` from the
+    /// result. `input` must be smaller than the state and `output` no larger than the rate.
+    pub fn duplex(&mut self, input: &[u8], output: &mut [u8]) {
+        self.duplex_with_domain(input, NO_DOMAIN, output);
+    }
+
+    /// Like `duplex`, but appends domain separation bits to the input before padding it.
+    ///
+    /// `domain` holds the bits in order from least significant, followed by a single 1 bit that
+    /// marks their end. For example, `0x02` is the single bit 0 and `0x03` is the single bit 1.
+    pub fn duplex_with_domain(&mut self, input: &[u8], domain: u8, output: &mut [u8]) {
+        assert!(input.len() < P::width());
+        assert!(output.len() <= self.rate);
+        assert!(domain != 0 && domain < 0x80);
+
+        self.state.xor_bytes(0, input);
+        // pad10*1, with the first 1 being the terminator of the domain bits.
+        self.state.xor_bytes(input.len(), &[domain]);
+        self.state.xor_bytes(P::width() - 1, &[0x80]);
+        self.state.permute();
+
+        self.state.extract_bytes(0, output);
+    }
+
+    /// Irreversibly forgets the current state by zeroing the rate and permuting, so that a later
+    /// compromise of the state does not reveal earlier outputs.
+    pub fn ratchet(&mut self) {
+        let mut buffer = [0; 32];
+        let mut offset = 0;
+        while offset < self.rate {
+            let len = if self.rate - offset < buffer.len() { self.rate - offset } else { buffer.len() };
+            self.state.extract_bytes(offset, &mut buffer[..len]);
+            self.state.xor_bytes(offset, &buffer[..len]);
+            offset += len;
+        }
+        self.state.permute();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyedDuplex;
+
+    use permutation::norx::Norx32;
+
+    #[test]
+    fn same_key_same_output() {
+        let mut first = KeyedDuplex::<Norx32>::new(b"key", 48);
+        let mut second = KeyedDuplex::<Norx32>::new(b"key", 48);
+        first.absorb(b"some input");
+        second.absorb(b"some input");
+
+        let mut out1 = [0; 100];
+        let mut out2 = [0; 100];
+        first.squeeze(&mut out1);
+        second.squeeze(&mut out2);
+        assert_eq!(&out1[..], &out2[..]);
+    }
+
+    #[test]
+    fn key_changes_output() {
+        let mut first = KeyedDuplex::<Norx32>::new(b"key1", 48);
+        let mut second = KeyedDuplex::<Norx32>::new(b"key2", 48);
+
+        let mut out1 = [0; 48];
+        let mut out2 = [0; 48];
+        first.squeeze(&mut out1);
+        second.squeeze(&mut out2);
+        assert!(out1 != out2);
+    }
+
+    #[test]
+    fn ratchet_changes_output() {
+        let mut first = KeyedDuplex::<Norx32>::new(b"key", 48);
+        let mut second = KeyedDuplex::<Norx32>::new(b"key", 48);
+        second.ratchet();
+
+        let mut out1 = [0; 48];
+        let mut out2 = [0; 48];
+        first.squeeze(&mut out1);
+        second.squeeze(&mut out2);
+        assert!(out1 != out2);
+    }
+
+    #[test]
+    fn domain_separates() {
+        let mut first = KeyedDuplex::<Norx32>::new(b"key", 48);
+        let mut second = KeyedDuplex::<Norx32>::new(b"key", 48);
+
+        let mut out1 = [0; 48];
+        let mut out2 = [0; 48];
+        first.duplex_with_domain(b"input", 0x02, &mut out1);
+        second.duplex_with_domain(b"input", 0x03, &mut out2);
+        assert!(out1 != out2);
+    }
+}
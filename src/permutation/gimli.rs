@@ -0,0 +1,120 @@
+use permutation::{ByteState, Permutation, extract_lane_bytes, xor_lane_bytes};
+use secret::Secret;
+use utils::traits::rotate::RotateLeft;
+
+/// The 384-bit Gimli permutation, on a state of three rows of four words
+pub struct Gimli {
+    words: [Secret<u32>; 12]
+}
+
+impl Gimli {
+    /// Creates a state from its words, indexed by column + 4*row.
+    pub fn new(words: [Secret<u32>; 12]) -> Gimli {
+        Gimli {
+            words: words
+        }
+    }
+
+    pub fn words(&self) -> [Secret<u32>; 12] {
+        self.words
+    }
+}
+
+impl Clone for Gimli {
+    fn clone(&self) -> Gimli {
+        Gimli::new(self.words)
+    }
+}
+
+impl Default for Gimli {
+    fn default() -> Gimli {
+        Gimli::new([Secret::new(0); 12])
+    }
+}
+
+impl Permutation for Gimli {
+    fn permute(&mut self) {
+        let s = &mut self.words;
+        for round in (1..25).rev() {
+            // SP-box on each column
+            for column in 0..4 {
+                let x = s[column].rotate_left(24);
+                let y = s[4 + column].rotate_left(9);
+                let z = s[8 + column];
+                s[8 + column] = x ^ (z << 1) ^ ((y & z) << 2);
+                s[4 + column] = y ^ x ^ ((x | z) << 1);
+                s[column] = z ^ y ^ ((x & y) << 3);
+            }
+
+            match round % 4 {
+                0 => {
+                    // Small swap, then round constant
+                    s.swap(0, 1);
+                    s.swap(2, 3);
+                    s[0] ^= 0x9E377900 ^ round as u32;
+                },
+                2 => {
+                    // Big swap
+                    s.swap(0, 2);
+                    s.swap(1, 3);
+                },
+                _ => { }
+            }
+        }
+    }
+}
+
+impl ByteState for Gimli {
+    fn width() -> usize {
+        48
+    }
+
+    fn xor_bytes(&mut self, offset: usize, input: &[u8]) {
+        xor_lane_bytes(&mut self.words, offset, input);
+    }
+
+    fn extract_bytes(&self, offset: usize, output: &mut [u8]) {
+        extract_lane_bytes(&self.words, offset, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::Gimli;
+
+    use permutation::Permutation;
+    use secret::Secret;
+
+    use self::test::Bencher;
+
+    #[test]
+    fn known_answer() {
+        // The test vector from the Gimli paper's reference implementation
+        let mut words = [Secret::new(0); 12];
+        for (i, word) in words.iter_mut().enumerate() {
+            let i = i as u32;
+            *word = Secret::new(i.wrapping_mul(i).wrapping_mul(i).wrapping_add(i.wrapping_mul(0x9E3779B9)));
+        }
+        let mut state = Gimli::new(words);
+        state.permute();
+
+        let expected = [
+            0xBA11C85A, 0x91BAD119, 0x380CE880, 0xD24C2C68,
+            0x3ECEFFEA, 0x277A921C, 0x4F73A0BD, 0xDA5A9CD8,
+            0x84B673F0, 0x34E52FF7, 0x9E2BEF49, 0xF41BB8D6
+        ];
+        for (&word, &expected) in state.words().iter().zip(expected.iter()) {
+            assert_eq!(word.expose(), expected);
+        }
+    }
+
+    #[bench]
+    fn bench_gimli(bencher: &mut Bencher) {
+        let mut state = Gimli::default();
+        bencher.iter(|| {
+            state.permute();
+        });
+    }
+}
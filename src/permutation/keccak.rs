@@ -0,0 +1,264 @@
+use std::marker::PhantomData;
+use std::ops::{BitAnd, BitXor, Not};
+
+use permutation::{ByteState, Lane, Permutation, extract_lane_bytes, xor_lane_bytes};
+use secret::Secret;
+use utils::interleaved::Interleaved;
+use utils::traits::rotate::RotateLeft;
+
+use typenum::consts::U24;
+use typenum::uint::Unsigned;
+
+// See FIPS Publication 202, Sections 3.2.2 and 3.2.5. Indexed by x + 5*y.
+const RHO_OFFSETS: [u32; 25] = [
+     0,  1, 62, 28, 27,
+    36, 44,  6, 55, 20,
+     3, 10, 43, 25, 39,
+    41, 45, 15, 21,  8,
+    18,  2, 61, 56, 14
+];
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808A, 0x8000000080008000,
+    0x000000000000808B, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008A, 0x0000000000000088, 0x0000000080008009, 0x000000008000000A,
+    0x000000008000808B, 0x800000000000008B, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800A, 0x800000008000000A,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008
+];
+
+/// A representation of a 64-bit Keccak lane
+pub trait KeccakLane: Lane + BitAnd<Self, Output=Self> + BitXor<Self, Output=Self> + Not<Output=Self> + RotateLeft<u32, Output=Self> {
+    fn from_u64(val: u64) -> Self;
+}
+
+impl KeccakLane for u64 {
+    fn from_u64(val: u64) -> u64 {
+        val
+    }
+}
+
+impl KeccakLane for Secret<u64> {
+    fn from_u64(val: u64) -> Secret<u64> {
+        Secret::new(val)
+    }
+}
+
+// On 32-bit targets, bit interleaving turns each 64-bit rotation into two 32-bit rotations.
+impl KeccakLane for Interleaved<u32> {
+    fn from_u64(val: u64) -> Interleaved<u32> {
+        Interleaved::from_word(val)
+    }
+}
+
+impl KeccakLane for Interleaved<Secret<u32>> {
+    fn from_u64(val: u64) -> Interleaved<Secret<u32>> {
+        Interleaved::from_word(Secret::new(val))
+    }
+}
+
+/// The Keccak-p[1600, n] permutation, running the last `Rounds` rounds of Keccak-f[1600]
+pub struct KeccakP<L, Rounds> {
+    lanes: [L; 25],
+    _marker: PhantomData<Rounds>
+}
+
+/// The full 24-round Keccak-f[1600] permutation
+pub type KeccakF<L> = KeccakP<L, U24>;
+
+pub type KeccakF1600 = KeccakF<Secret<u64>>;
+pub type KeccakF1600Interleaved = KeccakF<Interleaved<Secret<u32>>>;
+
+impl<L: KeccakLane, Rounds: Unsigned> KeccakP<L, Rounds> {
+    /// Creates a state from its lanes, indexed by x + 5*y.
+    pub fn new(lanes: [L; 25]) -> KeccakP<L, Rounds> {
+        assert!(Rounds::to_usize() <= 24);
+        KeccakP {
+            lanes: lanes,
+            _marker: PhantomData
+        }
+    }
+
+    pub fn lanes(&self) -> [L; 25] {
+        self.lanes
+    }
+}
+
+impl<L: KeccakLane, Rounds: Unsigned> Clone for KeccakP<L, Rounds> {
+    fn clone(&self) -> KeccakP<L, Rounds> {
+        KeccakP::new(self.lanes)
+    }
+}
+
+impl<L: KeccakLane, Rounds: Unsigned> Default for KeccakP<L, Rounds> {
+    fn default() -> KeccakP<L, Rounds> {
+        KeccakP::new([L::from_u64(0); 25])
+    }
+}
+
+impl<L: KeccakLane, Rounds: Unsigned> Permutation for KeccakP<L, Rounds> {
+    fn permute(&mut self) {
+        for &constant in ROUND_CONSTANTS[24 - Rounds::to_usize()..].iter() {
+            keccak_round(&mut self.lanes, L::from_u64(constant));
+        }
+    }
+}
+
+impl<L: KeccakLane, Rounds: Unsigned> ByteState for KeccakP<L, Rounds> {
+    fn width() -> usize {
+        200
+    }
+
+    fn xor_bytes(&mut self, offset: usize, input: &[u8]) {
+        xor_lane_bytes(&mut self.lanes, offset, input);
+    }
+
+    fn extract_bytes(&self, offset: usize, output: &mut [u8]) {
+        extract_lane_bytes(&self.lanes, offset, output);
+    }
+}
+
+fn keccak_round<L: KeccakLane>(lanes: &mut [L; 25], constant: L) {
+    // Theta
+    let mut parities = [lanes[0]; 5];
+    for x in 0..5 {
+        parities[x] = lanes[x] ^ lanes[x + 5] ^ lanes[x + 10] ^ lanes[x + 15] ^ lanes[x + 20];
+    }
+    for x in 0..5 {
+        let effect = parities[(x + 4) % 5] ^ parities[(x + 1) % 5].rotate_left(1);
+        for y in 0..5 {
+            lanes[x + 5 * y] = lanes[x + 5 * y] ^ effect;
+        }
+    }
+
+    // Rho and pi
+    let mut moved = *lanes;
+    for x in 0..5 {
+        for y in 0..5 {
+            moved[y + 5 * ((2 * x + 3 * y) % 5)] = lanes[x + 5 * y].rotate_left(RHO_OFFSETS[x + 5 * y]);
+        }
+    }
+
+    // Chi
+    for y in 0..5 {
+        for x in 0..5 {
+            lanes[x + 5 * y] = moved[x + 5 * y] ^ (!moved[(x + 1) % 5 + 5 * y] & moved[(x + 2) % 5 + 5 * y]);
+        }
+    }
+
+    // Iota
+    lanes[0] = lanes[0] ^ constant;
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    extern crate rand;
+
+    use super::{KeccakF, KeccakP, KeccakLane};
+
+    use permutation::{ByteState, Permutation};
+    use secret::Secret;
+    use utils::interleaved::Interleaved;
+
+    use typenum::consts::U12;
+
+    use self::test::Bencher;
+    use self::rand::{Rng, thread_rng};
+
+    // From the Keccak team's KeccakF-1600-IntermediateValues.txt, starting from the zero state
+    const ZERO_PERMUTED_ONCE: [u64; 25] = [
+        0xF1258F7940E1DDE7, 0x84D5CCF933C0478A, 0xD598261EA65AA9EE, 0xBD1547306F80494D, 0x8B284E056253D057,
+        0xFF97A42D7F8E6FD4, 0x90FEE5A0A44647C4, 0x8C5BDA0CD6192E76, 0xAD30A6F71B19059C, 0x30935AB7D08FFC64,
+        0xEB5AA93F2317D635, 0xA9A6E6260D712103, 0x81A57C16DBCF555F, 0x43B831CD0347C826, 0x01F22F1A11A5569F,
+        0x05E5635A21D9AE61, 0x64BEFEF28CC970F2, 0x613670957BC46611, 0xB87C5A554FD00ECB, 0x8C3EE88A1CCF32C8,
+        0x940C7922AE3A2614, 0x1841F924A2C509E4, 0x16F53526E70465C2, 0x75F644E97F30A13B, 0xEAF1FF7B5CECA249
+    ];
+
+    const ZERO_PERMUTED_TWICE: [u64; 25] = [
+        0x2D5C954DF96ECB3C, 0x6A332CD07057B56D, 0x093D8D1270D76B6C, 0x8A20D9B25569D094, 0x4F9C4F99E5E7F156,
+        0xF957B9A2DA65FB38, 0x85773DAE1275AF0D, 0xFAF4F247C3D810F7, 0x1F1B9EE6F79A8759, 0xE4FECC0FEE98B425,
+        0x68CE61B6B9CE68A1, 0xDEEA66C4BA8F974F, 0x33C43D836EAFB1F5, 0xE00654042719DBD9, 0x7CF8A9F009831265,
+        0xFD5449A6BF174743, 0x97DDAD33D8994B40, 0x48EAD5FC5D0BE774, 0xE3B8C8EE55B7B03C, 0x91A0226E649E42E9,
+        0x900E3129E7BADD7B, 0x202A9EC5FAA3CCE8, 0x5B3402464E1C3DB6, 0x609F4E62A44C1059, 0x20D06CD26A8FBF5C
+    ];
+
+    fn check_known_answers<L: KeccakLane, F: Fn(L) -> u64>(expose: F) {
+        let mut state = KeccakF::<L>::default();
+        state.permute();
+        for (&lane, &expected) in state.lanes().iter().zip(ZERO_PERMUTED_ONCE.iter()) {
+            assert_eq!(expose(lane), expected);
+        }
+        state.permute();
+        for (&lane, &expected) in state.lanes().iter().zip(ZERO_PERMUTED_TWICE.iter()) {
+            assert_eq!(expose(lane), expected);
+        }
+    }
+
+    #[test]
+    fn known_answers_u64() {
+        check_known_answers(|lane: u64| lane);
+        check_known_answers(|lane: Secret<u64>| lane.expose());
+    }
+
+    #[test]
+    fn known_answers_interleaved() {
+        check_known_answers(|lane: Interleaved<u32>| lane.to_word::<u64>());
+        check_known_answers(|lane: Interleaved<Secret<u32>>| lane.to_word::<Secret<u64>>().expose());
+    }
+
+    #[test]
+    fn reduced_rounds_match_rand() {
+        let mut rng = thread_rng();
+        let mut lanes = [0u64; 25];
+        for lane in lanes.iter_mut() {
+            *lane = rng.gen();
+        }
+        let mut interleaved = [Interleaved::<u32>::from_u64(0); 25];
+        for (slot, &lane) in interleaved.iter_mut().zip(lanes.iter()) {
+            *slot = Interleaved::<u32>::from_u64(lane);
+        }
+
+        let mut direct = KeccakP::<u64, U12>::new(lanes);
+        let mut split = KeccakP::<Interleaved<u32>, U12>::new(interleaved);
+        direct.permute();
+        split.permute();
+        for (&a, &b) in direct.lanes().iter().zip(split.lanes().iter()) {
+            assert_eq!(a, b.to_word::<u64>());
+        }
+    }
+
+    #[test]
+    fn reduced_rounds_known_answer() {
+        // TurboSHAKE128 of the empty string with domain byte 0x1F, from RFC 9861. It is a single
+        // call to Keccak-p[1600, 12] on a padded block.
+        let expected = [
+            0x1E, 0x41, 0x5F, 0x1C, 0x59, 0x83, 0xAF, 0xF2, 0x16, 0x92, 0x17, 0x27, 0x7D, 0x17, 0xBB, 0x53,
+            0x8C, 0xD9, 0x45, 0xA3, 0x97, 0xDD, 0xEC, 0x54, 0x1F, 0x1C, 0xE4, 0x1A, 0xF2, 0xC1, 0xB7, 0x4C
+        ];
+
+        let mut state = KeccakP::<Interleaved<Secret<u32>>, U12>::default();
+        state.xor_bytes(0, &[0x1F]);
+        state.xor_bytes(167, &[0x80]);
+        state.permute();
+        let mut output = [0; 32];
+        state.extract_bytes(0, &mut output);
+        assert_eq!(output, expected);
+    }
+
+    #[bench]
+    fn bench_keccak_f_u64(bencher: &mut Bencher) {
+        let mut state = KeccakF::<Secret<u64>>::default();
+        bencher.iter(|| {
+            state.permute();
+        });
+    }
+
+    #[bench]
+    fn bench_keccak_f_interleaved(bencher: &mut Bencher) {
+        let mut state = KeccakF::<Interleaved<Secret<u32>>>::default();
+        bencher.iter(|| {
+            state.permute();
+        });
+    }
+}
@@ -1,5 +1,122 @@
+use secret::Secret;
+use utils::interleaved::Interleaved;
+use utils::traits::truncate::Truncate;
+
 pub mod norx;
+pub mod duplex;
+pub mod keccak;
+pub mod sponge;
+pub mod ascon;
+pub mod gimli;
+pub mod xoodoo;
+pub mod xoodyak;
 
-trait Permutation {
+pub trait Permutation {
     fn permute(&mut self);
 }
+
+/// A permutation whose inverse can also be computed
+pub trait InvertiblePermutation: Permutation {
+    fn unpermute(&mut self);
+}
+
+/// A permutation state that can be viewed as a string of bytes, which is how sponge and duplex
+/// constructions feed data into and read data out of it.
+pub trait ByteState {
+    /// The size of the state in bytes
+    fn width() -> usize;
+
+    /// XORs `input` into the state, starting at byte `offset`.
+    fn xor_bytes(&mut self, offset: usize, input: &[u8]);
+
+    /// Copies bytes of the state, starting at byte `offset`, into `output`.
+    fn extract_bytes(&self, offset: usize, output: &mut [u8]);
+}
+
+/// A word of a permutation state, serialized in little-endian byte order.
+pub trait Lane: Copy {
+    fn bytes() -> usize;
+    fn byte(self, index: usize) -> u8;
+    fn xor_byte(&mut self, index: usize, val: u8);
+}
+
+macro_rules! lane_impl {
+    { $t:ty, $bytes:expr } => {
+        impl Lane for $t {
+            fn bytes() -> usize {
+                $bytes
+            }
+
+            fn byte(self, index: usize) -> u8 {
+                (self >> (8 * index as u32)) as u8
+            }
+
+            fn xor_byte(&mut self, index: usize, val: u8) {
+                *self ^= (val as $t) << (8 * index as u32);
+            }
+        }
+
+        impl Lane for Secret<$t> {
+            fn bytes() -> usize {
+                $bytes
+            }
+
+            fn byte(self, index: usize) -> u8 {
+                Truncate::<Secret<u8>>::truncate(self >> (8 * index as u32)).expose()
+            }
+
+            fn xor_byte(&mut self, index: usize, val: u8) {
+                *self ^= (val as $t) << (8 * index as u32);
+            }
+        }
+    };
+}
+
+lane_impl! { u32, 4 }
+lane_impl! { u64, 8 }
+
+impl Lane for Interleaved<u32> {
+    fn bytes() -> usize {
+        8
+    }
+
+    fn byte(self, index: usize) -> u8 {
+        self.to_word::<u64>().byte(index)
+    }
+
+    fn xor_byte(&mut self, index: usize, val: u8) {
+        *self = *self ^ Interleaved::<u32>::from_word((val as u64) << (8 * index as u32));
+    }
+}
+
+impl Lane for Interleaved<Secret<u32>> {
+    fn bytes() -> usize {
+        8
+    }
+
+    fn byte(self, index: usize) -> u8 {
+        self.to_word::<Secret<u64>>().byte(index)
+    }
+
+    fn xor_byte(&mut self, index: usize, val: u8) {
+        *self = *self ^ Interleaved::<Secret<u32>>::from_word(Secret::new((val as u64) << (8 * index as u32)));
+    }
+}
+
+/// XORs `input` into a state made of lanes, starting at byte `offset`.
+pub fn xor_lane_bytes<L: Lane>(lanes: &mut [L], offset: usize, input: &[u8]) {
+    assert!(offset + input.len() <= lanes.len() * L::bytes());
+    for (i, &byte) in input.iter().enumerate() {
+        let pos = offset + i;
+        lanes[pos / L::bytes()].xor_byte(pos % L::bytes(), byte);
+    }
+}
+
+/// Copies bytes out of a state made of lanes, starting at byte `offset`.
+pub fn extract_lane_bytes<L: Lane>(lanes: &[L], offset: usize, output: &mut [u8]) {
+    assert!(offset + output.len() <= lanes.len() * L::bytes());
+    for (i, byte) in output.iter_mut().enumerate() {
+        let pos = offset + i;
+        *byte = lanes[pos / L::bytes()].byte(pos % L::bytes());
+    }
+}
@@ -0,0 +1,480 @@
+use std::marker::PhantomData;
+use std::ops::{BitAnd, BitXor, Shl};
+
+use permutation::{ByteState, InvertiblePermutation, Lane, Permutation, extract_lane_bytes, xor_lane_bytes};
+use secret::Secret;
+use utils::traits::rotate::{RotateLeft, RotateRight};
+
+use self::simd::{Portable, Vector4};
+#[cfg(target_arch = "x86_64")]
+use self::simd::{Sse2U32x4, Avx2U64x4};
+
+use typenum::consts::U4;
+use typenum::uint::Unsigned;
+
+pub(crate) mod simd;
+
+struct Blocks<T> {
+    b0:  T, b1:  T, b2:  T, b3:  T,
+    b4:  T, b5:  T, b6:  T, b7:  T,
+    b8:  T, b9:  T, b10: T, b11: T,
+    b12: T, b13: T, b14: T, b15: T,
+}
+
+impl<T: Copy> Blocks<T> {
+    fn from_words(words: [T; 16]) -> Blocks<T> {
+        Blocks {
+            b0:  words[0],  b1:  words[1],  b2:  words[2],  b3:  words[3],
+            b4:  words[4],  b5:  words[5],  b6:  words[6],  b7:  words[7],
+            b8:  words[8],  b9:  words[9],  b10: words[10], b11: words[11],
+            b12: words[12], b13: words[13], b14: words[14], b15: words[15],
+        }
+    }
+
+    fn to_words(&self) -> [T; 16] {
+        [
+            self.b0,  self.b1,  self.b2,  self.b3,
+            self.b4,  self.b5,  self.b6,  self.b7,
+            self.b8,  self.b9,  self.b10, self.b11,
+            self.b12, self.b13, self.b14, self.b15,
+        ]
+    }
+}
+
+const ROTATIONS_32: [u32; 4] = [8, 11, 16, 31];
+const ROTATIONS_64: [u32; 4] = [8, 19, 40, 63];
+
+/// A word size that NORX is defined for
+pub trait NorxWord: Lane + BitAnd<Self, Output=Self> + BitXor<Self, Output=Self> + Shl<u32, Output=Self> + RotateLeft<u32, Output=Self> + RotateRight<u32, Output=Self> {
+    fn zero() -> Self;
+    fn rotations() -> [u32; 4];
+
+    /// Applies `rounds` rounds of F to a state, using the fastest vector instructions available.
+    fn f_rounds(words: &mut [Self; 16], rounds: usize);
+}
+
+impl NorxWord for Secret<u32> {
+    fn zero() -> Secret<u32> {
+        Secret::new(0)
+    }
+
+    fn rotations() -> [u32; 4] {
+        ROTATIONS_32
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn f_rounds(words: &mut [Secret<u32>; 16], rounds: usize) {
+        f_vector::<Sse2U32x4>(words, ROTATIONS_32, rounds);
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn f_rounds(words: &mut [Secret<u32>; 16], rounds: usize) {
+        f_vector::<Portable<Secret<u32>>>(words, ROTATIONS_32, rounds);
+    }
+}
+
+impl NorxWord for Secret<u64> {
+    fn zero() -> Secret<u64> {
+        Secret::new(0)
+    }
+
+    fn rotations() -> [u32; 4] {
+        ROTATIONS_64
+    }
+
+    fn f_rounds(words: &mut [Secret<u64>; 16], rounds: usize) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                unsafe { f_rounds_avx2(words, rounds) };
+                return;
+            }
+        }
+        f_vector::<Portable<Secret<u64>>>(words, ROTATIONS_64, rounds);
+    }
+}
+
+// Callers must check that the processor supports AVX2 first. This is the only place the AVX2
+// vectors are used.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn f_rounds_avx2(words: &mut [Secret<u64>; 16], rounds: usize) {
+    f_vector::<Avx2U64x4>(words, ROTATIONS_64, rounds);
+}
+
+/// The NORX permutation F^l, applying `Rounds` rounds of F to a state of 16 words
+pub struct Norx<T, Rounds> {
+    words: [T; 16],
+    _marker: PhantomData<Rounds>
+}
+
+pub type Norx32 = Norx<Secret<u32>, U4>;
+pub type Norx64 = Norx<Secret<u64>, U4>;
+
+impl<T: NorxWord, Rounds: Unsigned> Norx<T, Rounds> {
+    pub fn new(words: [T; 16]) -> Norx<T, Rounds> {
+        Norx {
+            words: words,
+            _marker: PhantomData
+        }
+    }
+
+    pub fn words(&self) -> [T; 16] {
+        self.words
+    }
+
+    /// Applies the permutation one G at a time, without vector instructions. This is the
+    /// reference that the vectorized `permute` is checked against.
+    pub fn permute_scalar(&mut self) {
+        let mut blocks = Blocks::from_words(self.words);
+        for _ in 0..Rounds::to_usize() {
+            f_square_scramble(&mut blocks, T::rotations());
+        }
+        self.words = blocks.to_words();
+    }
+}
+
+impl<T: NorxWord, Rounds: Unsigned> Clone for Norx<T, Rounds> {
+    fn clone(&self) -> Norx<T, Rounds> {
+        Norx::new(self.words())
+    }
+}
+
+impl<T: NorxWord, Rounds: Unsigned> Default for Norx<T, Rounds> {
+    fn default() -> Norx<T, Rounds> {
+        Norx::new([T::zero(); 16])
+    }
+}
+
+impl<T: NorxWord, Rounds: Unsigned> Permutation for Norx<T, Rounds> {
+    fn permute(&mut self) {
+        T::f_rounds(&mut self.words, Rounds::to_usize());
+    }
+}
+
+impl<T: NorxWord, Rounds: Unsigned> InvertiblePermutation for Norx<T, Rounds> {
+    fn unpermute(&mut self) {
+        let mut blocks = Blocks::from_words(self.words);
+        for _ in 0..Rounds::to_usize() {
+            f_square_unscramble(&mut blocks, T::rotations());
+        }
+        self.words = blocks.to_words();
+    }
+}
+
+impl<T: NorxWord, Rounds: Unsigned> BitXor<Norx<T, Rounds>> for Norx<T, Rounds> {
+    type Output = Norx<T, Rounds>;
+    fn bitxor(self, rhs: Norx<T, Rounds>) -> Norx<T, Rounds> {
+        let mut words = self.words();
+        for (word, &other) in words.iter_mut().zip(rhs.words().iter()) {
+            *word = *word ^ other;
+        }
+        Norx::new(words)
+    }
+}
+
+impl<T: NorxWord, Rounds: Unsigned> ByteState for Norx<T, Rounds> {
+    fn width() -> usize {
+        16 * T::bytes()
+    }
+
+    fn xor_bytes(&mut self, offset: usize, input: &[u8]) {
+        xor_lane_bytes(&mut self.words, offset, input);
+    }
+
+    fn extract_bytes(&self, offset: usize, output: &mut [u8]) {
+        extract_lane_bytes(&self.words, offset, output);
+    }
+}
+
+#[inline(always)]
+fn h_add<T>(lhs: T, rhs: T) -> T
+        where T: Copy + BitAnd<T, Output=T> + BitXor<T, Output=T> + Shl<u32, Output=T> {
+    (lhs ^ rhs) ^ ((lhs & rhs) << 1)
+}
+
+// Inverse of h_add in its first argument: finds `lhs` such that `h_add(lhs, rhs) == sum`.
+//
+// Bit i of `lhs` only depends on the bits below it, so starting from the low bit and iterating
+// the defining equation fixes one more bit each time. A word of n bits needs n - 1 iterations.
+#[inline]
+fn h_sub<T: NorxWord>(sum: T, rhs: T) -> T {
+    let mut lhs = sum ^ rhs;
+    for _ in 1..8 * T::bytes() {
+        lhs = (sum ^ rhs) ^ ((lhs & rhs) << 1);
+    }
+    lhs
+}
+
+#[inline(always)]
+fn g_scramble<T>(blocks: [&mut T; 4], rotations: [u32; 4])
+        where T: Copy + BitAnd<T, Output=T> + BitXor<T, Output=T> + Shl<u32, Output=T> + RotateRight<u32, Output=T> {
+    *blocks[0] = h_add(*blocks[0], *blocks[1]);
+    *blocks[3] = (*blocks[0] ^ *blocks[3]).rotate_right(rotations[0]);
+    *blocks[2] = h_add(*blocks[2], *blocks[3]);
+    *blocks[1] = (*blocks[1] ^ *blocks[2]).rotate_right(rotations[1]);
+    *blocks[0] = h_add(*blocks[0], *blocks[1]);
+    *blocks[3] = (*blocks[0] ^ *blocks[3]).rotate_right(rotations[2]);
+    *blocks[2] = h_add(*blocks[2], *blocks[3]);
+    *blocks[1] = (*blocks[1] ^ *blocks[2]).rotate_right(rotations[3]);
+}
+
+fn f_square_scramble<T>(blocks: &mut Blocks<T>, rotations: [u32; 4])
+        where T: Copy + BitAnd<T, Output=T> + BitXor<T, Output=T> + Shl<u32, Output=T> + RotateRight<u32, Output=T> {
+    // Columns
+    g_scramble([&mut blocks.b0, &mut blocks.b4, &mut blocks.b8,  &mut blocks.b12], rotations);
+    g_scramble([&mut blocks.b1, &mut blocks.b5, &mut blocks.b9,  &mut blocks.b13], rotations);
+    g_scramble([&mut blocks.b2, &mut blocks.b6, &mut blocks.b10, &mut blocks.b14], rotations);
+    g_scramble([&mut blocks.b3, &mut blocks.b7, &mut blocks.b11, &mut blocks.b15], rotations);
+
+    // Diagonals
+    g_scramble([&mut blocks.b0, &mut blocks.b5, &mut blocks.b10, &mut blocks.b15], rotations);
+    g_scramble([&mut blocks.b1, &mut blocks.b6, &mut blocks.b11, &mut blocks.b12], rotations);
+    g_scramble([&mut blocks.b2, &mut blocks.b7, &mut blocks.b8,  &mut blocks.b13], rotations);
+    g_scramble([&mut blocks.b3, &mut blocks.b4, &mut blocks.b9,  &mut blocks.b14], rotations);
+}
+
+// F on a state held as four row vectors. Columns are G applied lane by lane; for the diagonals,
+// the rows are rotated so that each diagonal lands in a single lane, then rotated back.
+#[inline(always)]
+fn f_vector<V: Vector4>(words: &mut [V::Word; 16], rotations: [u32; 4], rounds: usize) {
+    let mut row0 = V::from_words([words[0],  words[1],  words[2],  words[3]]);
+    let mut row1 = V::from_words([words[4],  words[5],  words[6],  words[7]]);
+    let mut row2 = V::from_words([words[8],  words[9],  words[10], words[11]]);
+    let mut row3 = V::from_words([words[12], words[13], words[14], words[15]]);
+
+    for _ in 0..rounds {
+        g_scramble([&mut row0, &mut row1, &mut row2, &mut row3], rotations);
+
+        row1 = row1.rotate_lanes(1);
+        row2 = row2.rotate_lanes(2);
+        row3 = row3.rotate_lanes(3);
+        g_scramble([&mut row0, &mut row1, &mut row2, &mut row3], rotations);
+        row1 = row1.rotate_lanes(3);
+        row2 = row2.rotate_lanes(2);
+        row3 = row3.rotate_lanes(1);
+    }
+
+    for (row, vector) in [row0, row1, row2, row3].iter().enumerate() {
+        let lanes = vector.to_words();
+        for lane in 0..4 {
+            words[4 * row + lane] = lanes[lane];
+        }
+    }
+}
+
+// Inverse of g_scramble, undoing its steps in reverse order
+#[inline]
+fn g_unscramble<T: NorxWord>(blocks: [&mut T; 4], rotations: [u32; 4]) {
+    *blocks[1] = blocks[1].rotate_left(rotations[3]) ^ *blocks[2];
+    *blocks[2] = h_sub(*blocks[2], *blocks[3]);
+    *blocks[3] = blocks[3].rotate_left(rotations[2]) ^ *blocks[0];
+    *blocks[0] = h_sub(*blocks[0], *blocks[1]);
+    *blocks[1] = blocks[1].rotate_left(rotations[1]) ^ *blocks[2];
+    *blocks[2] = h_sub(*blocks[2], *blocks[3]);
+    *blocks[3] = blocks[3].rotate_left(rotations[0]) ^ *blocks[0];
+    *blocks[0] = h_sub(*blocks[0], *blocks[1]);
+}
+
+// Inverse of f_square_scramble
+fn f_square_unscramble<T: NorxWord>(blocks: &mut Blocks<T>, rotations: [u32; 4]) {
+    // Diagonals
+    g_unscramble([&mut blocks.b0, &mut blocks.b5, &mut blocks.b10, &mut blocks.b15], rotations);
+    g_unscramble([&mut blocks.b1, &mut blocks.b6, &mut blocks.b11, &mut blocks.b12], rotations);
+    g_unscramble([&mut blocks.b2, &mut blocks.b7, &mut blocks.b8,  &mut blocks.b13], rotations);
+    g_unscramble([&mut blocks.b3, &mut blocks.b4, &mut blocks.b9,  &mut blocks.b14], rotations);
+
+    // Columns
+    g_unscramble([&mut blocks.b0, &mut blocks.b4, &mut blocks.b8,  &mut blocks.b12], rotations);
+    g_unscramble([&mut blocks.b1, &mut blocks.b5, &mut blocks.b9,  &mut blocks.b13], rotations);
+    g_unscramble([&mut blocks.b2, &mut blocks.b6, &mut blocks.b10, &mut blocks.b14], rotations);
+    g_unscramble([&mut blocks.b3, &mut blocks.b7, &mut blocks.b11, &mut blocks.b15], rotations);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate test;
+    extern crate rand;
+
+    use super::{Blocks, Norx, Norx32, Norx64, ROTATIONS_32, ROTATIONS_64, f_square_scramble, f_vector};
+    use super::simd::Portable;
+    #[cfg(target_arch = "x86_64")]
+    use super::simd::Sse2U32x4;
+    #[cfg(target_arch = "x86_64")]
+    use super::f_rounds_avx2;
+
+    use permutation::{InvertiblePermutation, Permutation};
+    use secret::Secret;
+
+    use typenum::consts::U2;
+
+    use self::test::Bencher;
+    use self::rand::{Rng, thread_rng};
+
+    #[test]
+    fn unpermute_inverts_32() {
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut words = [Secret::new(0); 16];
+            for word in words.iter_mut() {
+                *word = Secret::new(rng.gen());
+            }
+            let original: Vec<u32> = words.iter().map(|w| w.expose()).collect();
+
+            let mut state = Norx32::new(words);
+            state.permute();
+            state.unpermute();
+            let result: Vec<u32> = state.words().iter().map(|w| w.expose()).collect();
+            assert_eq!(result, original);
+        }
+    }
+
+    #[test]
+    fn unpermute_inverts_64() {
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut words = [Secret::new(0); 16];
+            for word in words.iter_mut() {
+                *word = Secret::new(rng.gen());
+            }
+            let original: Vec<u64> = words.iter().map(|w| w.expose()).collect();
+
+            let mut state = Norx64::new(words);
+            state.unpermute();
+            state.permute();
+            let result: Vec<u64> = state.words().iter().map(|w| w.expose()).collect();
+            assert_eq!(result, original);
+        }
+    }
+
+    #[test]
+    fn vector_matches_scalar_32() {
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut words = [Secret::new(0); 16];
+            for word in words.iter_mut() {
+                *word = Secret::new(rng.gen());
+            }
+            let mut expected = Norx32::new(words);
+            expected.permute_scalar();
+            let expected: Vec<u32> = expected.words().iter().map(|w| w.expose()).collect();
+
+            let mut portable = words;
+            f_vector::<Portable<Secret<u32>>>(&mut portable, ROTATIONS_32, 4);
+            assert_eq!(portable.iter().map(|w| w.expose()).collect::<Vec<_>>(), expected);
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                let mut sse2 = words;
+                f_vector::<Sse2U32x4>(&mut sse2, ROTATIONS_32, 4);
+                assert_eq!(sse2.iter().map(|w| w.expose()).collect::<Vec<_>>(), expected);
+            }
+
+            let mut dispatched = Norx32::new(words);
+            dispatched.permute();
+            assert_eq!(dispatched.words().iter().map(|w| w.expose()).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn vector_matches_scalar_64() {
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut words = [Secret::new(0); 16];
+            for word in words.iter_mut() {
+                *word = Secret::new(rng.gen());
+            }
+            let mut expected = Norx64::new(words);
+            expected.permute_scalar();
+            let expected: Vec<u64> = expected.words().iter().map(|w| w.expose()).collect();
+
+            let mut portable = words;
+            f_vector::<Portable<Secret<u64>>>(&mut portable, ROTATIONS_64, 4);
+            assert_eq!(portable.iter().map(|w| w.expose()).collect::<Vec<_>>(), expected);
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    let mut avx2 = words;
+                    unsafe { f_rounds_avx2(&mut avx2, 4) };
+                    assert_eq!(avx2.iter().map(|w| w.expose()).collect::<Vec<_>>(), expected);
+                }
+            }
+
+            let mut dispatched = Norx64::new(words);
+            dispatched.permute();
+            assert_eq!(dispatched.words().iter().map(|w| w.expose()).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn initialization_constants() {
+        // NORX v3.0 derives its initialization constants u8..u15 as F^2(0, 1, ..., 15).
+        let mut words = [Secret::new(0); 16];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = Secret::new(i as u32);
+        }
+        let mut state = Norx::<Secret<u32>, U2>::new(words);
+        state.permute();
+        let expected = [0xA3D8D930, 0x3FA8B72C, 0xED84EB49, 0xEDCA4787, 0x335463EB, 0xF994220B, 0xBE0BF5C9, 0xD7C49104];
+        for (&word, &expected) in state.words()[8..].iter().zip(expected.iter()) {
+            assert_eq!(word.expose(), expected);
+        }
+
+        let mut words = [Secret::new(0); 16];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = Secret::new(i as u64);
+        }
+        let mut state = Norx::<Secret<u64>, U2>::new(words);
+        state.permute();
+        let expected = [
+            0xB15E641748DE5E6B, 0xAA95E955E10F8410, 0x28D1034441A9DD40, 0x7F31BBF964E93BF5,
+            0xB5E9E22493DFFB96, 0xB980C852479FAFBD, 0xDA24516BF55EAFD4, 0x86026AE8536F1501
+        ];
+        for (&word, &expected) in state.words()[8..].iter().zip(expected.iter()) {
+            assert_eq!(word.expose(), expected);
+        }
+    }
+
+    #[bench]
+    fn bench_permute_32(bencher: &mut Bencher) {
+        let mut state = Norx32::default();
+        bencher.iter(|| {
+            state.permute();
+        });
+    }
+
+    #[bench]
+    fn bench_permute_64(bencher: &mut Bencher) {
+        let mut state = Norx64::default();
+        bencher.iter(|| {
+            state.permute();
+        });
+    }
+
+    #[bench]
+    fn bench_f_32(bencher: &mut Bencher) {
+        let mut state: Blocks<u32> = Blocks {
+            b0:   0, b1:   1, b2:   2, b3:   3,
+            b4:   4, b5:   5, b6:   6, b7:   7,
+            b8:   8, b9:   9, b10: 10, b11: 11,
+            b12: 12, b13: 13, b14: 14, b15: 15,
+        };
+        bencher.iter(|| {
+            f_square_scramble(&mut state, ROTATIONS_32);
+        });
+    }
+
+    #[bench]
+    fn bench_f_64(bencher: &mut Bencher) {
+        let mut state: Blocks<u64> = Blocks {
+            b0:   0, b1:   1, b2:   2, b3:   3,
+            b4:   4, b5:   5, b6:   6, b7:   7,
+            b8:   8, b9:   9, b10: 10, b11: 11,
+            b12: 12, b13: 13, b14: 14, b15: 15,
+        };
+        bencher.iter(|| {
+            f_square_scramble(&mut state, ROTATIONS_64);
+        });
+    }
+}
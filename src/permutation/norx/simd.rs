@@ -0,0 +1,234 @@
+use std::ops::{BitAnd, BitXor, Shl};
+
+use permutation::norx::NorxWord;
+use utils::traits::rotate::RotateRight;
+
+/// Four NORX words operated on together, one per lane.
+///
+/// The state is held as four such vectors, one per row, so that G runs on all four columns at
+/// once. Rotating the lanes of rows 1, 2 and 3 by 1, 2 and 3 places lines the diagonals up as
+/// columns.
+pub(crate) trait Vector4: Copy + BitAnd<Self, Output=Self> + BitXor<Self, Output=Self> + Shl<u32, Output=Self> + RotateRight<u32, Output=Self> {
+    type Word: Copy;
+
+    fn from_words(words: [Self::Word; 4]) -> Self;
+    fn to_words(self) -> [Self::Word; 4];
+
+    /// Moves lane `i + n` (mod 4) into lane `i`.
+    fn rotate_lanes(self, n: usize) -> Self;
+}
+
+/// The fallback used when no vector instructions are available
+#[derive(Copy, Clone)]
+pub(crate) struct Portable<T>([T; 4]);
+
+impl<T: NorxWord> BitAnd<Portable<T>> for Portable<T> {
+    type Output = Portable<T>;
+    #[inline(always)]
+    fn bitand(self, rhs: Portable<T>) -> Portable<T> {
+        Portable([self.0[0] & rhs.0[0], self.0[1] & rhs.0[1], self.0[2] & rhs.0[2], self.0[3] & rhs.0[3]])
+    }
+}
+
+impl<T: NorxWord> BitXor<Portable<T>> for Portable<T> {
+    type Output = Portable<T>;
+    #[inline(always)]
+    fn bitxor(self, rhs: Portable<T>) -> Portable<T> {
+        Portable([self.0[0] ^ rhs.0[0], self.0[1] ^ rhs.0[1], self.0[2] ^ rhs.0[2], self.0[3] ^ rhs.0[3]])
+    }
+}
+
+impl<T: NorxWord> Shl<u32> for Portable<T> {
+    type Output = Portable<T>;
+    #[inline(always)]
+    fn shl(self, rhs: u32) -> Portable<T> {
+        Portable([self.0[0] << rhs, self.0[1] << rhs, self.0[2] << rhs, self.0[3] << rhs])
+    }
+}
+
+impl<T: NorxWord> RotateRight<u32> for Portable<T> {
+    type Output = Portable<T>;
+    #[inline(always)]
+    fn rotate_right(self, rhs: u32) -> Portable<T> {
+        Portable([self.0[0].rotate_right(rhs), self.0[1].rotate_right(rhs), self.0[2].rotate_right(rhs), self.0[3].rotate_right(rhs)])
+    }
+}
+
+impl<T: NorxWord> Vector4 for Portable<T> {
+    type Word = T;
+
+    #[inline(always)]
+    fn from_words(words: [T; 4]) -> Portable<T> {
+        Portable(words)
+    }
+
+    #[inline(always)]
+    fn to_words(self) -> [T; 4] {
+        self.0
+    }
+
+    #[inline(always)]
+    fn rotate_lanes(self, n: usize) -> Portable<T> {
+        let w = self.0;
+        Portable([w[n % 4], w[(n + 1) % 4], w[(n + 2) % 4], w[(n + 3) % 4]])
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) use self::x86_64::{Sse2U32x4, Avx2U64x4};
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use std::arch::x86_64::*;
+    use std::ops::{BitAnd, BitXor, Shl};
+
+    use secret::Secret;
+    use utils::traits::rotate::RotateRight;
+
+    use super::Vector4;
+
+    /// Four 32-bit words in an SSE2 register. SSE2 is part of the x86_64 baseline, so this is
+    /// always available there.
+    #[derive(Copy, Clone)]
+    pub(crate) struct Sse2U32x4(__m128i);
+
+    impl BitAnd<Sse2U32x4> for Sse2U32x4 {
+        type Output = Sse2U32x4;
+        #[inline(always)]
+        fn bitand(self, rhs: Sse2U32x4) -> Sse2U32x4 {
+            unsafe { Sse2U32x4(_mm_and_si128(self.0, rhs.0)) }
+        }
+    }
+
+    impl BitXor<Sse2U32x4> for Sse2U32x4 {
+        type Output = Sse2U32x4;
+        #[inline(always)]
+        fn bitxor(self, rhs: Sse2U32x4) -> Sse2U32x4 {
+            unsafe { Sse2U32x4(_mm_xor_si128(self.0, rhs.0)) }
+        }
+    }
+
+    impl Shl<u32> for Sse2U32x4 {
+        type Output = Sse2U32x4;
+        #[inline(always)]
+        fn shl(self, rhs: u32) -> Sse2U32x4 {
+            unsafe { Sse2U32x4(_mm_sll_epi32(self.0, _mm_cvtsi32_si128(rhs as i32))) }
+        }
+    }
+
+    impl RotateRight<u32> for Sse2U32x4 {
+        type Output = Sse2U32x4;
+        #[inline(always)]
+        fn rotate_right(self, rhs: u32) -> Sse2U32x4 {
+            unsafe {
+                let right = _mm_srl_epi32(self.0, _mm_cvtsi32_si128(rhs as i32));
+                let left = _mm_sll_epi32(self.0, _mm_cvtsi32_si128(32 - rhs as i32));
+                Sse2U32x4(_mm_or_si128(right, left))
+            }
+        }
+    }
+
+    impl Vector4 for Sse2U32x4 {
+        type Word = Secret<u32>;
+
+        #[inline(always)]
+        fn from_words(words: [Secret<u32>; 4]) -> Sse2U32x4 {
+            unsafe {
+                Sse2U32x4(_mm_set_epi32(words[3].expose() as i32, words[2].expose() as i32,
+                                        words[1].expose() as i32, words[0].expose() as i32))
+            }
+        }
+
+        #[inline(always)]
+        fn to_words(self) -> [Secret<u32>; 4] {
+            let mut words = [0u32; 4];
+            unsafe { _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, self.0) };
+            [Secret::new(words[0]), Secret::new(words[1]), Secret::new(words[2]), Secret::new(words[3])]
+        }
+
+        #[inline(always)]
+        fn rotate_lanes(self, n: usize) -> Sse2U32x4 {
+            unsafe {
+                Sse2U32x4(match n % 4 {
+                    0 => self.0,
+                    1 => _mm_shuffle_epi32(self.0, 0x39),
+                    2 => _mm_shuffle_epi32(self.0, 0x4E),
+                    _ => _mm_shuffle_epi32(self.0, 0x93)
+                })
+            }
+        }
+    }
+
+    /// Four 64-bit words in an AVX2 register. Its safe methods run AVX2 instructions, so it stays
+    /// private to the crate and is only used from `f_rounds_avx2`, which `NorxWord::f_rounds` calls
+    /// after checking that the processor supports AVX2.
+    #[derive(Copy, Clone)]
+    pub(crate) struct Avx2U64x4(__m256i);
+
+    impl BitAnd<Avx2U64x4> for Avx2U64x4 {
+        type Output = Avx2U64x4;
+        #[inline(always)]
+        fn bitand(self, rhs: Avx2U64x4) -> Avx2U64x4 {
+            unsafe { Avx2U64x4(_mm256_and_si256(self.0, rhs.0)) }
+        }
+    }
+
+    impl BitXor<Avx2U64x4> for Avx2U64x4 {
+        type Output = Avx2U64x4;
+        #[inline(always)]
+        fn bitxor(self, rhs: Avx2U64x4) -> Avx2U64x4 {
+            unsafe { Avx2U64x4(_mm256_xor_si256(self.0, rhs.0)) }
+        }
+    }
+
+    impl Shl<u32> for Avx2U64x4 {
+        type Output = Avx2U64x4;
+        #[inline(always)]
+        fn shl(self, rhs: u32) -> Avx2U64x4 {
+            unsafe { Avx2U64x4(_mm256_sll_epi64(self.0, _mm_cvtsi32_si128(rhs as i32))) }
+        }
+    }
+
+    impl RotateRight<u32> for Avx2U64x4 {
+        type Output = Avx2U64x4;
+        #[inline(always)]
+        fn rotate_right(self, rhs: u32) -> Avx2U64x4 {
+            unsafe {
+                let right = _mm256_srl_epi64(self.0, _mm_cvtsi32_si128(rhs as i32));
+                let left = _mm256_sll_epi64(self.0, _mm_cvtsi32_si128(64 - rhs as i32));
+                Avx2U64x4(_mm256_or_si256(right, left))
+            }
+        }
+    }
+
+    impl Vector4 for Avx2U64x4 {
+        type Word = Secret<u64>;
+
+        #[inline(always)]
+        fn from_words(words: [Secret<u64>; 4]) -> Avx2U64x4 {
+            unsafe {
+                Avx2U64x4(_mm256_set_epi64x(words[3].expose() as i64, words[2].expose() as i64,
+                                            words[1].expose() as i64, words[0].expose() as i64))
+            }
+        }
+
+        #[inline(always)]
+        fn to_words(self) -> [Secret<u64>; 4] {
+            let mut words = [0u64; 4];
+            unsafe { _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, self.0) };
+            [Secret::new(words[0]), Secret::new(words[1]), Secret::new(words[2]), Secret::new(words[3])]
+        }
+
+        #[inline(always)]
+        fn rotate_lanes(self, n: usize) -> Avx2U64x4 {
+            unsafe {
+                Avx2U64x4(match n % 4 {
+                    0 => self.0,
+                    1 => _mm256_permute4x64_epi64(self.0, 0x39),
+                    2 => _mm256_permute4x64_epi64(self.0, 0x4E),
+                    _ => _mm256_permute4x64_epi64(self.0, 0x93)
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use permutation::{ByteState, Permutation};
+
+/// An unkeyed sponge, absorbing into and squeezing out of the first `rate` bytes of the state.
+///
+/// Input may be absorbed in arbitrary pieces until the sponge is padded, after which it can only
+/// be squeezed.
+#[derive(Clone)]
+pub struct Sponge<P> {
+    state: P,
+    rate: usize,
+    position: usize,
+    squeezing: bool
+}
+
+impl<P: Permutation + ByteState + Default> Sponge<P> {
+    pub fn new(rate: usize) -> Sponge<P> {
+        Sponge::from_state(P::default(), rate)
+    }
+
+    /// Creates a sponge starting from `state` rather than from the all-zero state, for
+    /// constructions that begin with an initialization vector.
+    pub fn from_state(state: P, rate: usize) -> Sponge<P> {
+        assert!(rate > 0 && rate < P::width(), "rate must leave some capacity");
+
+        Sponge {
+            state: state,
+            rate: rate,
+            position: 0,
+            squeezing: false
+        }
+    }
+
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    pub fn absorb(&mut self, input: &[u8]) {
+        assert!(!self.squeezing, "cannot absorb after padding");
+
+        let mut remaining = input;
+        while !remaining.is_empty() {
+            let space = self.rate - self.position;
+            let len = if remaining.len() < space { remaining.len() } else { space };
+            let (block, rest) = remaining.split_at(len);
+            self.state.xor_bytes(self.position, block);
+            self.position += len;
+            remaining = rest;
+
+            if self.position == self.rate {
+                self.state.permute();
+                self.position = 0;
+            }
+        }
+    }
+
+    /// Fills the rest of the current block with zeros, so that the next input starts a new block.
+    pub fn zero_pad(&mut self) {
+        assert!(!self.squeezing, "cannot absorb after padding");
+
+        if self.position != 0 {
+            self.state.permute();
+            self.position = 0;
+        }
+    }
+
+    /// Appends domain separation bits and pad10*1, switching the sponge to squeezing.
+    ///
+    /// As with the duplex, `domain` holds the bits from least significant upwards followed by a
+    /// terminating 1, so the SHA-3 suffix `01` is `0x06` and the SHAKE suffix `1111` is `0x1F`.
+    pub fn pad(&mut self, domain: u8) {
+        assert!(domain < 0x80);
+        self.finish_absorbing(domain, true);
+    }
+
+    /// Applies the simple padding 10*, without the final 1 bit, switching the sponge to
+    /// squeezing. This is what Ascon uses, relying on its initialization vector rather than the
+    /// padding to separate different rates.
+    pub fn pad_simple(&mut self) {
+        self.finish_absorbing(0x01, false);
+    }
+
+    fn finish_absorbing(&mut self, domain: u8, final_bit: bool) {
+        assert!(!self.squeezing, "sponge is already padded");
+        assert!(domain != 0);
+
+        self.state.xor_bytes(self.position, &[domain]);
+        if final_bit {
+            self.state.xor_bytes(self.rate - 1, &[0x80]);
+        }
+        self.state.permute();
+        self.position = 0;
+        self.squeezing = true;
+    }
+
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        assert!(self.squeezing, "sponge must be padded before squeezing");
+
+        let mut remaining = output;
+        while !remaining.is_empty() {
+            if self.position == self.rate {
+                self.state.permute();
+                self.position = 0;
+            }
+
+            let space = self.rate - self.position;
+            let len = if remaining.len() < space { remaining.len() } else { space };
+            let (block, rest) = { remaining }.split_at_mut(len);
+            self.state.extract_bytes(self.position, block);
+            self.position += len;
+            remaining = rest;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sponge;
+
+    use permutation::keccak::KeccakF1600;
+
+    #[test]
+    fn split_input_and_output() {
+        let input: Vec<u8> = (0..500).map(|i| i as u8).collect();
+
+        let mut whole = Sponge::<KeccakF1600>::new(136);
+        whole.absorb(&input);
+        whole.pad(0x1F);
+        let mut expected = [0; 400];
+        whole.squeeze(&mut expected);
+
+        for &split in [0, 1, 135, 136, 137, 300].iter() {
+            let mut pieces = Sponge::<KeccakF1600>::new(136);
+            pieces.absorb(&input[..split]);
+            pieces.absorb(&input[split..]);
+            pieces.pad(0x1F);
+            let mut output = [0; 400];
+            {
+                let (first, second) = output.split_at_mut(split);
+                pieces.squeeze(first);
+                pieces.squeeze(second);
+            }
+            assert_eq!(&output[..], &expected[..]);
+        }
+    }
+}
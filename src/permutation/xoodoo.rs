@@ -0,0 +1,145 @@
+use std::marker::PhantomData;
+
+use permutation::{ByteState, Permutation, extract_lane_bytes, xor_lane_bytes};
+use secret::Secret;
+use utils::traits::rotate::RotateLeft;
+
+use typenum::consts::{U6, U12};
+use typenum::uint::Unsigned;
+
+// See the Xoodoo cookbook, Table 2. A run of n rounds uses the last n constants.
+const ROUND_CONSTANTS: [u32; 12] = [
+    0x058, 0x038, 0x3C0, 0x0D0, 0x120, 0x014,
+    0x060, 0x02C, 0x380, 0x0F0, 0x1A0, 0x012
+];
+
+/// The 384-bit Xoodoo permutation, running the last `Rounds` rounds of Xoodoo[12]
+pub struct XoodooP<Rounds> {
+    lanes: [Secret<u32>; 12],
+    _marker: PhantomData<Rounds>
+}
+
+pub type Xoodoo = XoodooP<U12>;
+pub type Xoodoo6 = XoodooP<U6>;
+
+impl<Rounds: Unsigned> XoodooP<Rounds> {
+    /// Creates a state from its lanes, indexed by x + 4*y.
+    pub fn new(lanes: [Secret<u32>; 12]) -> XoodooP<Rounds> {
+        assert!(Rounds::to_usize() <= 12);
+        XoodooP {
+            lanes: lanes,
+            _marker: PhantomData
+        }
+    }
+
+    pub fn lanes(&self) -> [Secret<u32>; 12] {
+        self.lanes
+    }
+}
+
+impl<Rounds: Unsigned> Clone for XoodooP<Rounds> {
+    fn clone(&self) -> XoodooP<Rounds> {
+        XoodooP::new(self.lanes)
+    }
+}
+
+impl<Rounds: Unsigned> Default for XoodooP<Rounds> {
+    fn default() -> XoodooP<Rounds> {
+        XoodooP::new([Secret::new(0); 12])
+    }
+}
+
+impl<Rounds: Unsigned> Permutation for XoodooP<Rounds> {
+    fn permute(&mut self) {
+        for &constant in ROUND_CONSTANTS[12 - Rounds::to_usize()..].iter() {
+            xoodoo_round(&mut self.lanes, constant);
+        }
+    }
+}
+
+impl<Rounds: Unsigned> ByteState for XoodooP<Rounds> {
+    fn width() -> usize {
+        48
+    }
+
+    fn xor_bytes(&mut self, offset: usize, input: &[u8]) {
+        xor_lane_bytes(&mut self.lanes, offset, input);
+    }
+
+    fn extract_bytes(&self, offset: usize, output: &mut [u8]) {
+        extract_lane_bytes(&self.lanes, offset, output);
+    }
+}
+
+fn xoodoo_round(a: &mut [Secret<u32>; 12], constant: u32) {
+    // Theta
+    let mut parities = [a[0]; 4];
+    for x in 0..4 {
+        parities[x] = a[x] ^ a[x + 4] ^ a[x + 8];
+    }
+    for x in 0..4 {
+        let parity = parities[(x + 3) % 4];
+        let effect = parity.rotate_left(5) ^ parity.rotate_left(14);
+        for y in 0..3 {
+            a[x + 4 * y] ^= effect;
+        }
+    }
+
+    // Rho west: shift plane 1 along x, rotate the lanes of plane 2
+    let moved = *a;
+    for x in 0..4 {
+        a[x + 4] = moved[(x + 3) % 4 + 4];
+        a[x + 8] = moved[x + 8].rotate_left(11);
+    }
+
+    // Iota
+    a[0] ^= constant;
+
+    // Chi
+    let planes = *a;
+    for x in 0..4 {
+        a[x] = planes[x] ^ (!planes[x + 4] & planes[x + 8]);
+        a[x + 4] = planes[x + 4] ^ (!planes[x + 8] & planes[x]);
+        a[x + 8] = planes[x + 8] ^ (!planes[x] & planes[x + 4]);
+    }
+
+    // Rho east: rotate the lanes of plane 1, shift and rotate plane 2
+    let moved = *a;
+    for x in 0..4 {
+        a[x + 4] = moved[x + 4].rotate_left(1);
+        a[x + 8] = moved[(x + 2) % 4 + 8].rotate_left(8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::Xoodoo;
+
+    use permutation::Permutation;
+
+    use self::test::Bencher;
+
+    #[test]
+    fn zero_state() {
+        // Xoodoo[12] on the all-zero state. These values are self-generated by a separate model of
+        // the specification, not taken from the XKCP reference.
+        let mut state = Xoodoo::default();
+        state.permute();
+        let lanes: Vec<u32> = state.lanes().iter().map(|lane| lane.expose()).collect();
+        assert_eq!(lanes, vec![
+            0x89D5D88D, 0xA963FCBF, 0x1B232D19, 0xFFA5A014,
+            0x36B18106, 0xAFC7C1FE, 0xAEE57CBE, 0xA77540BD,
+            0x2E86E870, 0xFEF5B7C9, 0x8B4FADF2, 0x5E4F4062
+        ]);
+    }
+
+    #[bench]
+    fn bench_xoodoo(bencher: &mut Bencher) {
+        let mut state = Xoodoo::default();
+        bencher.iter(|| {
+            state.permute();
+        });
+    }
+}
@@ -0,0 +1,340 @@
+use aead::AuthenticationError;
+use permutation::{ByteState, Permutation};
+use permutation::xoodoo::Xoodoo;
+use utils::compare::constant_time_eq;
+
+// See the Xoodyak specification, Section 2.2.
+const HASH_RATE: usize = 16;
+const KEYED_ABSORB_RATE: usize = 44;
+const KEYED_SQUEEZE_RATE: usize = 24;
+const RATCHET_LEN: usize = 16;
+
+// Domain separation constants. Down calls use the low bits of the last byte and up calls the
+// high bits; in hash mode only the lowest bit is used.
+const ABSORB_KEY: u8 = 0x02;
+const ABSORB: u8 = 0x03;
+const SQUEEZE_RATCHET: u8 = 0x10;
+const SQUEEZE_KEY: u8 = 0x20;
+const SQUEEZE: u8 = 0x40;
+const CRYPT: u8 = 0x80;
+
+/// The Xoodyak instance of the Cyclist mode, usable both as a hash and as a keyed object.
+///
+/// In keyed mode, everything absorbed, encrypted and decrypted so far is authenticated by
+/// `squeeze`, so a tag is simply squeezed after the text.
+pub struct Xoodyak {
+    state: Xoodoo,
+    // Whether the last call to the permutation was an up call rather than a down call
+    up: bool,
+    keyed: bool,
+    absorb_rate: usize,
+    squeeze_rate: usize
+}
+
+impl Xoodyak {
+    /// Creates an object in hash mode.
+    pub fn hash() -> Xoodyak {
+        Xoodyak {
+            state: Xoodoo::default(),
+            up: true,
+            keyed: false,
+            absorb_rate: HASH_RATE,
+            squeeze_rate: HASH_RATE
+        }
+    }
+
+    /// Creates an object in keyed mode. `id` may hold a nonce, and a nonzero `counter` is
+    /// absorbed a byte at a time to limit what a side channel can learn about the state.
+    pub fn keyed(key: &[u8], id: &[u8], counter: &[u8]) -> Xoodyak {
+        assert!(!key.is_empty());
+        assert!(key.len() + id.len() < KEYED_ABSORB_RATE, "key and id are too long");
+
+        let mut xoodyak = Xoodyak {
+            state: Xoodoo::default(),
+            up: true,
+            keyed: true,
+            absorb_rate: KEYED_ABSORB_RATE,
+            squeeze_rate: KEYED_SQUEEZE_RATE
+        };
+
+        let mut key_block = key.to_vec();
+        key_block.extend_from_slice(id);
+        key_block.push(id.len() as u8);
+        xoodyak.absorb_any(&key_block, KEYED_ABSORB_RATE, ABSORB_KEY);
+        if !counter.is_empty() {
+            xoodyak.absorb_any(counter, 1, 0x00);
+        }
+        xoodyak
+    }
+
+    pub fn absorb(&mut self, input: &[u8]) {
+        let rate = self.absorb_rate;
+        self.absorb_any(input, rate, ABSORB);
+    }
+
+    /// Encrypts `buffer` in place.
+    pub fn encrypt(&mut self, buffer: &mut [u8]) {
+        assert!(self.keyed, "encryption requires keyed mode");
+
+        let mut keystream = [0; KEYED_SQUEEZE_RATE];
+        let mut domain = CRYPT;
+        let mut start = 0;
+        loop {
+            let end = if buffer.len() - start < KEYED_SQUEEZE_RATE { buffer.len() } else { start + KEYED_SQUEEZE_RATE };
+            let block = &mut buffer[start..end];
+            let keystream = &mut keystream[..block.len()];
+            self.up(keystream, domain);
+            self.down(block, 0x00);
+            for (byte, &key) in block.iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+            domain = 0x00;
+            start = end;
+
+            if start == buffer.len() {
+                break;
+            }
+        }
+    }
+
+    /// Decrypts `buffer` in place. The result must not be used until a tag squeezed afterwards
+    /// has been checked.
+    pub fn decrypt(&mut self, buffer: &mut [u8]) {
+        assert!(self.keyed, "decryption requires keyed mode");
+
+        let mut keystream = [0; KEYED_SQUEEZE_RATE];
+        let mut domain = CRYPT;
+        let mut start = 0;
+        loop {
+            let end = if buffer.len() - start < KEYED_SQUEEZE_RATE { buffer.len() } else { start + KEYED_SQUEEZE_RATE };
+            let block = &mut buffer[start..end];
+            let keystream = &mut keystream[..block.len()];
+            self.up(keystream, domain);
+            for (byte, &key) in block.iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+            self.down(block, 0x00);
+            domain = 0x00;
+            start = end;
+
+            if start == buffer.len() {
+                break;
+            }
+        }
+    }
+
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        self.squeeze_any(output, SQUEEZE);
+    }
+
+    /// Squeezes output meant to be used as a key, such as for a later session.
+    pub fn squeeze_key(&mut self, output: &mut [u8]) {
+        assert!(self.keyed, "key derivation requires keyed mode");
+        self.squeeze_any(output, SQUEEZE_KEY);
+    }
+
+    /// Irreversibly forgets the current state, so that a later compromise of the state does not
+    /// reveal earlier secrets.
+    pub fn ratchet(&mut self) {
+        assert!(self.keyed, "ratcheting requires keyed mode");
+
+        let mut buffer = [0; RATCHET_LEN];
+        self.squeeze_any(&mut buffer, SQUEEZE_RATCHET);
+        let rate = self.absorb_rate;
+        self.absorb_any(&buffer, rate, 0x00);
+    }
+
+    /// Squeezes a tag and compares it with `tag` in constant time.
+    pub fn verify(&mut self, tag: &[u8]) -> Result<(), AuthenticationError> {
+        let mut expected = vec![0; tag.len()];
+        self.squeeze(&mut expected);
+        if constant_time_eq(&expected, tag) {
+            Ok(())
+        } else {
+            Err(AuthenticationError)
+        }
+    }
+
+    fn absorb_any(&mut self, input: &[u8], rate: usize, domain: u8) {
+        // An empty input is still absorbed as a single empty block.
+        let mut domain = domain;
+        let mut remaining = input;
+        loop {
+            let len = if remaining.len() < rate { remaining.len() } else { rate };
+            let (block, rest) = remaining.split_at(len);
+            if !self.up {
+                self.up(&mut [], 0x00);
+            }
+            self.down(block, domain);
+            domain = 0x00;
+            remaining = rest;
+
+            if remaining.is_empty() {
+                break;
+            }
+        }
+    }
+
+    fn squeeze_any(&mut self, output: &mut [u8], domain: u8) {
+        // Even an empty output takes one up call.
+        let rate = self.squeeze_rate;
+        let mut domain = domain;
+        let mut start = 0;
+        loop {
+            let end = if output.len() - start < rate { output.len() } else { start + rate };
+            self.up(&mut output[start..end], domain);
+            domain = 0x00;
+            start = end;
+
+            if start == output.len() {
+                break;
+            }
+            self.down(&[], 0x00);
+        }
+    }
+
+    fn up(&mut self, output: &mut [u8], domain: u8) {
+        if self.keyed {
+            self.state.xor_bytes(Xoodoo::width() - 1, &[domain]);
+        }
+        self.state.permute();
+        self.up = true;
+        self.state.extract_bytes(0, output);
+    }
+
+    fn down(&mut self, input: &[u8], domain: u8) {
+        self.state.xor_bytes(0, input);
+        self.state.xor_bytes(input.len(), &[0x01]);
+        let domain = if self.keyed { domain } else { domain & 0x01 };
+        self.state.xor_bytes(Xoodoo::width() - 1, &[domain]);
+        self.up = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use super::Xoodyak;
+
+    use aead::AuthenticationError;
+    use utils::hex;
+
+    use self::test::Bencher;
+
+    // Entries of the reference implementation's LWC_HASH_KAT_256.txt, where the message counts up
+    // from 00.
+    #[test]
+    fn hash_known_answers() {
+        let kats = [
+            (0, "EA152F2B47BCE24EFB66C479D4ADF17BD324D806E85FF75EE369EE50DC8F8BD1"),
+            (1, "27921F8DDF392894460B70B3ED6C091E6421B7D2147DCD6031D7EFEBAD3030CC"),
+            (16, "9EA695347CDDDFF9BC63ECE30FE231441D581768FE223DD6BD7367094FD216B3"),
+            (17, "20593B39BB6D595019331601244411323F713085BB1A30218C972B96D9B7B7B3"),
+            (48, "8A77EDB598024B886C6DB04E3C5271953FF24E0E3E997DE44933DE4AB8E5226B")
+        ];
+        for &(len, expected) in kats.iter() {
+            let message: Vec<u8> = (0..len as u8).collect();
+            let mut xoodyak = Xoodyak::hash();
+            xoodyak.absorb(&message);
+            let mut output = [0; 32];
+            xoodyak.squeeze(&mut output);
+            assert_eq!(&output[..], &hex::decode(expected)[..]);
+        }
+    }
+
+    // Entries of the reference implementation's LWC_AEAD_KAT_128_128.txt, where the key and nonce
+    // are 00..0F and the plaintext and associated data count up from 00. The expected value is the
+    // ciphertext followed by the tag.
+    #[test]
+    fn aead_known_answers() {
+        let kats = [
+            (0, 0, "4BF0E393144CB58069FC1FEBCAFCFB3C"),
+            (0, 1, "4D2A8D1716DFE3401F3BBE8ACB637AB0"),
+            (1, 0, "890788EAC729D9539F401845B35A34D19F"),
+            (24, 44, "A9CA79E0336307EC0971E6AD123E0DBA68360FF628EE6EB4DDFBB4D29964FD1072DED54C3A27DD90"),
+            (25, 45, "01AEAB51C06CD1C4BAC235C1DC0D0B0B860CEA310354D9EF4B8AC39AFCBE597EE4E1E32C64199B6824"),
+            (32, 32, "DC56EC14215C53A5F2A2A5B957865F46F6201A071795A20FFA0116AD49DE4DE4007C270D39722FF5F3271700B1935B97")
+        ];
+        let key: Vec<u8> = (0..16).collect();
+        for &(text_len, ad_len, expected) in kats.iter() {
+            let plaintext: Vec<u8> = (0..text_len as u8).collect();
+            let associated_data: Vec<u8> = (0..ad_len as u8).collect();
+            let expected = hex::decode(expected);
+
+            let mut xoodyak = Xoodyak::keyed(&key, &[], &[]);
+            xoodyak.absorb(&key);
+            xoodyak.absorb(&associated_data);
+            let mut buffer = plaintext.clone();
+            xoodyak.encrypt(&mut buffer);
+            let mut tag = [0; 16];
+            xoodyak.squeeze(&mut tag);
+            assert_eq!(&buffer[..], &expected[..text_len]);
+            assert_eq!(&tag[..], &expected[text_len..]);
+
+            let mut xoodyak = Xoodyak::keyed(&key, &[], &[]);
+            xoodyak.absorb(&key);
+            xoodyak.absorb(&associated_data);
+            xoodyak.decrypt(&mut buffer);
+            assert_eq!(xoodyak.verify(&tag), Ok(()));
+            assert_eq!(buffer, plaintext);
+        }
+    }
+
+    #[test]
+    fn session_round_trip() {
+        let mut sender = Xoodyak::keyed(b"secret key", b"id", &[1, 2]);
+        let mut receiver = Xoodyak::keyed(b"secret key", b"id", &[1, 2]);
+
+        let mut message = b"a message long enough to need several blocks of keystream".to_vec();
+        sender.absorb(b"header");
+        sender.encrypt(&mut message);
+        sender.ratchet();
+        let mut tag = [0; 16];
+        sender.squeeze(&mut tag);
+
+        receiver.absorb(b"header");
+        receiver.decrypt(&mut message);
+        receiver.ratchet();
+        assert_eq!(receiver.verify(&tag), Ok(()));
+        assert_eq!(&message[..], &b"a message long enough to need several blocks of keystream"[..]);
+
+        // Both sides stay in step for later derivations.
+        let mut sender_key = [0; 32];
+        let mut receiver_key = [0; 32];
+        sender.squeeze_key(&mut sender_key);
+        receiver.squeeze_key(&mut receiver_key);
+        assert_eq!(sender_key, receiver_key);
+        assert_eq!(receiver.verify(&tag), Err(AuthenticationError));
+    }
+
+    #[test]
+    fn modes_are_separated() {
+        let mut squeezed = [0; 32];
+        let mut xoodyak = Xoodyak::keyed(b"secret key", &[], &[]);
+        xoodyak.squeeze(&mut squeezed);
+
+        let mut derived = [0; 32];
+        let mut xoodyak = Xoodyak::keyed(b"secret key", &[], &[]);
+        xoodyak.squeeze_key(&mut derived);
+        assert!(squeezed != derived);
+
+        let mut ratcheted = [0; 32];
+        let mut xoodyak = Xoodyak::keyed(b"secret key", &[], &[]);
+        xoodyak.ratchet();
+        xoodyak.squeeze(&mut ratcheted);
+        assert!(squeezed != ratcheted);
+    }
+
+    #[bench]
+    fn bench_hash_1k(bencher: &mut Bencher) {
+        let data = [0; 1024];
+        bencher.iter(|| {
+            let mut xoodyak = Xoodyak::hash();
+            xoodyak.absorb(&data);
+            let mut output = [0; 32];
+            xoodyak.squeeze(&mut output);
+            output
+        });
+    }
+}
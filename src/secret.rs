@@ -1,9 +1,10 @@
 use std::convert::From;
+use std::mem;
 use std::ops::{Not, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Shl, ShlAssign, Shr, ShrAssign};
 use utils::traits::rotate::{RotateLeft, RotateRight};
 use utils::traits::signs::{ToSigned, ToUnsigned};
 use utils::traits::truncate::Truncate;
-use utils::traits::wrapping::{WrappingAdd, WrappingSub};
+use utils::traits::wrapping::{WrappingAdd, WrappingMul, WrappingSub};
 
 /// A type designating data that will only be used in a constant time manner
 pub struct Secret<T: Copy> {
@@ -88,6 +89,26 @@ macro_rules! pod_impl {
     };
 }
 
+// Rotation by a secret amount, modulo the width. This runs every stage of a barrel shifter,
+// rotating by 1, 2, 4, ... bits and using a mask to keep either the rotated or the unrotated value,
+// so neither timing nor control flow depends on the amount.
+macro_rules! secret_rotate_impl {
+    { $tr:ident, $method:ident, $t:ty } => {
+        impl $tr<Secret<u32>> for Secret<$t> {
+            type Output = Secret<$t>;
+            fn $method(self, rhs: Secret<u32>) -> Secret<$t> {
+                let amount = rhs.expose();
+                let mut value = self.expose();
+                for stage in 0..(8 * mem::size_of::<$t>() as u32).trailing_zeros() {
+                    let mask = ((amount >> stage & 1) as $t).wrapping_neg();
+                    value = $tr::$method(value, 1 << stage) & mask | value & !mask;
+                }
+                Secret::new(value)
+            }
+        }
+    };
+}
+
 macro_rules! pod_impls {
     { $signtr:ident, $signmeth:ident: $t:ty } => {
         impl Not for Secret<$t> {
@@ -113,6 +134,9 @@ macro_rules! pod_impls {
         pod_impl! { shift: RotateRight, rotate_right, $t }
         pod_impl! { WrappingAdd, wrapping_add, $t }
         pod_impl! { WrappingSub, wrapping_sub, $t }
+        pod_impl! { WrappingMul, wrapping_mul, $t }
+        secret_rotate_impl! { RotateLeft, rotate_left, $t }
+        secret_rotate_impl! { RotateRight, rotate_right, $t }
     };
 
     { $signtr:ident, $signmeth:ident: $t:ty, $($rest:ty),* } => {
@@ -141,5 +165,30 @@ macro_rules! pod_impls {
     };
 }
 
-pod_impls! { ToSigned, to_signed: u64, u32, u16, u8 }
-pod_impls! { ToUnsigned, to_unsigned: i64, i32, i16, i8 }
+pod_impls! { ToSigned, to_signed: u128, u64, u32, u16, u8 }
+pod_impls! { ToUnsigned, to_unsigned: i128, i64, i32, i16, i8 }
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::Secret;
+    use utils::traits::rotate::{RotateLeft, RotateRight};
+
+    use self::rand::{Rng, thread_rng};
+
+    #[test]
+    fn secret_rotations() {
+        let mut rng = thread_rng();
+        for amount in 0..80 {
+            let x: u32 = rng.gen();
+            let y: u64 = rng.gen();
+            let z: i8 = rng.gen();
+            assert_eq!(Secret::new(x).rotate_left(Secret::new(amount)).expose(), x.rotate_left(amount));
+            assert_eq!(Secret::new(x).rotate_right(Secret::new(amount)).expose(), x.rotate_right(amount));
+            assert_eq!(Secret::new(y).rotate_left(Secret::new(amount)).expose(), y.rotate_left(amount));
+            assert_eq!(Secret::new(y).rotate_right(Secret::new(amount)).expose(), y.rotate_right(amount));
+            assert_eq!(Secret::new(z).rotate_left(Secret::new(amount)).expose(), z.rotate_left(amount));
+        }
+    }
+}
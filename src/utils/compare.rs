@@ -0,0 +1,32 @@
+use secret::Secret;
+use utils::traits::wrapping::WrappingSub;
+
+/// Compares two byte strings without branching on their contents. Only the lengths and the final
+/// result are treated as public.
+pub fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    if lhs.len() != rhs.len() {
+        return false;
+    }
+
+    let mut diff = Secret::new(0u8);
+    for (&l, &r) in lhs.iter().zip(rhs.iter()) {
+        diff |= Secret::new(l) ^ r;
+    }
+
+    // Widened to 16 bits, `diff - 1` only has its top bit set when `diff` is 0.
+    (Secret::<u16>::from(diff).wrapping_sub(1) >> 15).expose() == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn compares() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(!constant_time_eq(&[0x80], &[0x00]));
+    }
+}
@@ -0,0 +1,8 @@
+/// Decodes a hexadecimal string, ignoring any whitespace, for writing out test vectors.
+pub fn decode(hex: &str) -> Vec<u8> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !(*b as char).is_whitespace()).map(|b| {
+        (b as char).to_digit(16).expect("invalid hex digit") as u8
+    }).collect();
+    assert!(digits.len().is_multiple_of(2), "odd number of hex digits");
+    digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+}
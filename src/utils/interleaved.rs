@@ -1,13 +1,63 @@
-use std::ops::{BitAnd, BitOr, BitXor};
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
 
 use utils::traits::rotate::{RotateRight, RotateLeft};
+use utils::traits::truncate::Truncate;
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+/// A 64-bit word stored as two 32-bit halves, one holding its even bits and the other its odd
+/// bits. This lets 64-bit rotations be done with 32-bit rotations of the halves.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Interleaved<T> {
     evens: T,
     odds: T
 }
 
+#[inline(always)]
+fn delta_swap<W>(word: W, mask: u64, shift: u32) -> W
+        where W: Copy + BitAnd<u64, Output=W> + BitXor<W, Output=W> + Shl<u32, Output=W> + Shr<u32, Output=W> {
+    let temp = ((word >> shift) ^ word) & mask;
+    word ^ temp ^ (temp << shift)
+}
+
+impl<T: Copy> Interleaved<T> {
+    /// Splits a 64-bit word into its even and odd bits.
+    pub fn from_word<W>(word: W) -> Interleaved<T>
+            where W: Copy + BitAnd<u64, Output=W> + BitXor<W, Output=W> + Shl<u32, Output=W> + Shr<u32, Output=W> + Truncate<T> {
+        // Gather the even bits into the low half and the odd bits into the high half.
+        let word = delta_swap(word, 0x2222222222222222, 1);
+        let word = delta_swap(word, 0x0C0C0C0C0C0C0C0C, 2);
+        let word = delta_swap(word, 0x00F000F000F000F0, 4);
+        let word = delta_swap(word, 0x0000FF000000FF00, 8);
+        let word = delta_swap(word, 0x00000000FFFF0000, 16);
+
+        Interleaved {
+            evens: word.truncate(),
+            odds: (word >> 32).truncate()
+        }
+    }
+
+    /// Inverse of `from_word`
+    pub fn to_word<W>(self) -> W
+            where W: Copy + From<T> + BitAnd<u64, Output=W> + BitOr<W, Output=W> + BitXor<W, Output=W> + Shl<u32, Output=W> + Shr<u32, Output=W> {
+        let word = W::from(self.odds) << 32 | W::from(self.evens);
+
+        let word = delta_swap(word, 0x00000000FFFF0000, 16);
+        let word = delta_swap(word, 0x0000FF000000FF00, 8);
+        let word = delta_swap(word, 0x00F000F000F000F0, 4);
+        let word = delta_swap(word, 0x0C0C0C0C0C0C0C0C, 2);
+        delta_swap(word, 0x2222222222222222, 1)
+    }
+}
+
+impl<T: Not> Not for Interleaved<T> {
+    type Output = Interleaved<<T as Not>::Output>;
+    fn not(self) -> Self::Output {
+        Interleaved {
+            evens: !self.evens,
+            odds: !self.odds
+        }
+    }
+}
+
 impl<T: RotateLeft<u32>> RotateLeft<u32> for Interleaved<T> {
     type Output = Interleaved<<T as RotateLeft<u32>>::Output>;
     fn rotate_left(self, rhs: u32) -> Self::Output {
@@ -60,3 +110,63 @@ bitwise_impl!{ BitAnd, bitand }
 bitwise_impl!{ BitOr, bitor }
 bitwise_impl!{ BitXor, bitxor }
 
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::Interleaved;
+
+    use secret::Secret;
+    use utils::traits::rotate::{RotateLeft, RotateRight};
+
+    use self::rand::{Rng, thread_rng};
+
+    #[test]
+    fn splits_bits() {
+        let val = Interleaved::<u32>::from_word(0x5555555555555555u64);
+        assert_eq!(val.evens, 0xFFFFFFFF);
+        assert_eq!(val.odds, 0);
+
+        let val = Interleaved::<u32>::from_word(0x0000000000000006u64);
+        assert_eq!(val.evens, 2);
+        assert_eq!(val.odds, 1);
+    }
+
+    #[test]
+    fn round_trip_rand() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let val: u64 = rng.gen();
+            assert_eq!(Interleaved::<u32>::from_word(val).to_word::<u64>(), val);
+            let secret = Interleaved::<Secret<u32>>::from_word(Secret::new(val));
+            assert_eq!(secret.to_word::<Secret<u64>>().expose(), val);
+        }
+    }
+
+    #[test]
+    fn rotations_match_rand() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let val: u64 = rng.gen();
+            let amount = rng.gen::<u32>() % 64;
+            let interleaved = Interleaved::<u32>::from_word(val);
+            assert_eq!(interleaved.rotate_left(amount).to_word::<u64>(), val.rotate_left(amount));
+            assert_eq!(interleaved.rotate_right(amount).to_word::<u64>(), val.rotate_right(amount));
+        }
+    }
+
+    #[test]
+    fn bitwise_matches_rand() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let a: u64 = rng.gen();
+            let b: u64 = rng.gen();
+            let ia = Interleaved::<u32>::from_word(a);
+            let ib = Interleaved::<u32>::from_word(b);
+            assert_eq!((ia & ib).to_word::<u64>(), a & b);
+            assert_eq!((ia | ib).to_word::<u64>(), a | b);
+            assert_eq!((ia ^ ib).to_word::<u64>(), a ^ b);
+            assert_eq!((!ia).to_word::<u64>(), !a);
+        }
+    }
+}
@@ -1,3 +1,8 @@
 pub mod traits;
 pub mod nodrop;
 pub mod interleaved;
+pub mod compare;
+#[cfg(test)]
+pub mod hex;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,25 @@
+// Helpers shared by the tests and benchmarks of the ciphers.
+
+extern crate test;
+
+use array::{Array, ArrayLength};
+use cipher::block::BlockFn;
+use secret::Secret;
+
+use self::test::Bencher;
+
+/// A key made of the first `N` bytes of `bytes`
+pub fn make_key<N: ArrayLength<Secret<u8>>>(bytes: &[u8]) -> Array<Secret<u8>, N> {
+    Array::from_fn(|i| Secret::new(bytes[i]))
+}
+
+/// Benchmarks encrypting `block` over and over with `cipher`, counting `bytes` bytes per block.
+pub fn bench_encrypt<C: BlockFn>(bencher: &mut Bencher, cipher: &C, block: C::Block, bytes: u64)
+    where C::Block: Copy
+{
+    let mut block = block;
+    bencher.bytes = bytes;
+    bencher.iter(|| {
+        block = cipher.encrypt(block);
+    });
+}
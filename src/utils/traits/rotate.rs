@@ -31,4 +31,4 @@ macro_rules! pod_impls {
     };
 }
 
-pod_impls! { u8, u16, u32, u64, i8, i16, i32, i64 }
+pod_impls! { u8, u16, u32, u64, u128, i8, i16, i32, i64, i128 }
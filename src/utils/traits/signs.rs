@@ -30,3 +30,4 @@ pod_impl! { u8, i8 }
 pod_impl! { u16, i16 }
 pod_impl! { u32, i32 }
 pod_impl! { u64, i64 }
+pod_impl! { u128, i128 }
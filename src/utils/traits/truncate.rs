@@ -17,5 +17,5 @@ macro_rules! pod_impls {
     };
 }
 
-pod_impls! { u64, u32, u16, u8 }
-pod_impls! { i64, i32, i16, i8 }
+pod_impls! { u128, u64, u32, u16, u8 }
+pod_impls! { i128, i64, i32, i16, i8 }
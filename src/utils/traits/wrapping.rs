@@ -26,6 +26,11 @@ pub trait WrappingSub<Rhs> {
     fn wrapping_sub(self, rhs: Rhs) -> Self::Output;
 }
 
+pub trait WrappingMul<Rhs> {
+    type Output;
+    fn wrapping_mul(self, rhs: Rhs) -> Self::Output;
+}
+
 macro_rules! pod_impl {
     { $t:ty } => {
         impl WrappingAdd<$t> for $t {
@@ -41,6 +46,13 @@ macro_rules! pod_impl {
                 self.wrapping_sub(rhs)
             }
         }
+
+        impl WrappingMul<$t> for $t {
+            type Output = $t;
+            fn wrapping_mul(self, rhs: $t) -> $t {
+                self.wrapping_mul(rhs)
+            }
+        }
     };
 }
 
@@ -48,16 +60,18 @@ pod_impl! { u8 }
 pod_impl! { u16 }
 pod_impl! { u32 }
 pod_impl! { u64 }
+pod_impl! { u128 }
 pod_impl! { usize }
 pod_impl! { i8 }
 pod_impl! { i16 }
 pod_impl! { i32 }
 pod_impl! { i64 }
+pod_impl! { i128 }
 pod_impl! { isize }
 
 #[cfg(test)]
 mod tests {
-    use super::{WrappingAdd, WrappingSub};
+    use super::{WrappingAdd, WrappingMul, WrappingSub};
 
     #[test]
     fn no_loop() {
@@ -82,5 +96,16 @@ mod tests {
         assert_eq!(WrappingSub::wrapping_sub(0i32, 0i32), 0);
         assert_eq!(WrappingSub::wrapping_sub(0i64, 0i64), 0);
         assert_eq!(WrappingSub::wrapping_sub(0isize, 0isize), 0);
+
+        assert_eq!(WrappingMul::wrapping_mul(0u8, 0u8), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0u16, 0u16), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0u32, 0u32), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0u64, 0u64), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0usize, 0usize), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0i8, 0i8), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0i16, 0i16), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0i32, 0i32), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0i64, 0i64), 0);
+        assert_eq!(WrappingMul::wrapping_mul(0isize, 0isize), 0);
     }
 }